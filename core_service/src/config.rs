@@ -0,0 +1,614 @@
+use serde::Deserialize;
+use std::env;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+use std::time::Duration;
+
+// Config file read when CONFIG_FILE is not set; it is optional
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+// Application configuration, loaded once at startup from defaults, an optional TOML file and
+// environment variables (in increasing order of precedence), then validated as a whole
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub app: AppConfig,
+    pub database: DatabaseConfig,
+    pub redis: RedisConfig,
+    pub cmc: CmcConfig,
+    pub price_refresh: PriceRefreshConfig,
+    pub health: HealthConfig,
+    pub leader: LeaderConfig,
+    pub stream: StreamConfig,
+    pub webhooks: WebhookConfig,
+    pub idempotency: IdempotencyConfig,
+}
+
+// HTTP server and logging settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AppConfig {
+    pub port: u16,
+    pub log_format: String,         // "json" or "pretty"
+    pub shutdown_timeout_secs: u64, // How long shutdown waits for background tasks
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            port: 9000,
+            log_format: "json".to_string(),
+            shutdown_timeout_secs: 30,
+        }
+    }
+}
+
+// PostgreSQL connection settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseConfig {
+    pub url: Option<String>,
+    pub url_file: Option<String>, // File holding the URL, e.g. a Docker secret
+    pub max_connections: u32,
+    pub acquire_timeout_secs: u64,
+}
+
+impl Default for DatabaseConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            url_file: None,
+            max_connections: 5,
+            acquire_timeout_secs: 30,
+        }
+    }
+}
+
+// Redis price cache settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RedisConfig {
+    pub url: String,
+    pub url_file: Option<String>, // File holding the URL when it contains a password
+    pub price_ttl_secs: u64,
+}
+
+impl Default for RedisConfig {
+    fn default() -> Self {
+        Self {
+            url: "redis://127.0.0.1:6379".to_string(),
+            url_file: None,
+            price_ttl_secs: 3600,
+        }
+    }
+}
+
+// CoinMarketCap client settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CmcConfig {
+    pub api_key: Option<String>,
+    pub api_key_file: Option<String>, // File holding the API key, e.g. a Docker secret
+    pub base_url: String,
+    pub timeout_secs: u64,
+    pub max_retries: u32,
+    pub retry_base_delay_ms: u64,
+    pub circuit_breaker_threshold: u32,
+    pub circuit_breaker_cooldown_secs: u64,
+    pub daily_credit_budget: i64, // 0 disables the budget
+}
+
+impl Default for CmcConfig {
+    fn default() -> Self {
+        Self {
+            api_key: None,
+            api_key_file: None,
+            base_url: "https://pro-api.coinmarketcap.com".to_string(),
+            timeout_secs: 10,
+            max_retries: 3,
+            retry_base_delay_ms: 500,
+            circuit_breaker_threshold: 5,
+            circuit_breaker_cooldown_secs: 60,
+            daily_credit_budget: 0,
+        }
+    }
+}
+
+// Intervals of the background price refresh tiers
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PriceRefreshConfig {
+    pub held_interval_secs: u64,
+    pub tracked_interval_secs: u64,
+}
+
+impl Default for PriceRefreshConfig {
+    fn default() -> Self {
+        Self {
+            held_interval_secs: 300,
+            tracked_interval_secs: 3600,
+        }
+    }
+}
+
+// Readiness check settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct HealthConfig {
+    pub max_price_age_secs: u64,
+    pub max_refresh_failures: u32, // Consecutive failed refreshes of a tier tolerated while ready
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            max_price_age_secs: 1800,
+            max_refresh_failures: 3,
+        }
+    }
+}
+
+// Leader election of scheduled jobs between replicas
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LeaderConfig {
+    pub enabled: bool,            // When false every instance runs every job
+    pub check_interval_secs: u64, // How often standbys retry the lock and leaders verify it
+}
+
+impl Default for LeaderConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            check_interval_secs: 15,
+        }
+    }
+}
+
+// Live update streaming settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StreamConfig {
+    pub heartbeat_interval_secs: u64,
+    pub retention_secs: u64, // How long events are kept for clients resuming after a reconnect
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval_secs: 15,
+            retention_secs: 3600,
+        }
+    }
+}
+
+// Outbound webhook delivery settings
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WebhookConfig {
+    pub poll_interval_secs: u64, // How often the outbox is checked for due deliveries
+    pub timeout_secs: u64,
+    pub max_attempts: u32,
+    pub retry_base_delay_secs: u64, // Delay before the first retry, doubled for each further one
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            poll_interval_secs: 5,
+            timeout_secs: 10,
+            max_attempts: 8,
+            retry_base_delay_secs: 30,
+        }
+    }
+}
+
+// Idempotency-Key handling of mutating requests
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdempotencyConfig {
+    pub key_ttl_secs: u64, // How long stored responses are replayed for retries
+}
+
+impl Default for IdempotencyConfig {
+    fn default() -> Self {
+        Self {
+            key_ttl_secs: 86400,
+        }
+    }
+}
+
+// All problems found while loading the configuration
+#[derive(Debug)]
+pub struct ConfigError(pub Vec<String>);
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid configuration:")?;
+        for error in &self.0 {
+            write!(f, "\n  - {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Config {
+    // Loads the configuration from CONFIG_FILE (default: config.toml, if present) and
+    // environment variables, resolves secret files and validates the result
+    pub fn load() -> Result<Self, ConfigError> {
+        let mut errors = Vec::new();
+        let mut config = Self::from_file(&mut errors);
+        config.apply_env(&mut errors);
+        config.resolve_secrets(&mut errors);
+        config.validate(&mut errors);
+
+        if errors.is_empty() {
+            Ok(config)
+        } else {
+            Err(ConfigError(errors))
+        }
+    }
+
+    // Reads the TOML file, falling back to defaults when the default file does not exist
+    fn from_file(errors: &mut Vec<String>) -> Self {
+        let (path, required) = match env::var("CONFIG_FILE") {
+            Ok(path) => (path, true),
+            Err(_) => (DEFAULT_CONFIG_FILE.to_string(), false),
+        };
+        if !required && !Path::new(&path).exists() {
+            return Self::default();
+        }
+
+        match fs::read_to_string(&path) {
+            Ok(contents) => toml::from_str(&contents).unwrap_or_else(|e| {
+                errors.push(format!("{}: {}", path, e));
+                Self::default()
+            }),
+            Err(e) => {
+                errors.push(format!("Failed to read config file {}: {}", path, e));
+                Self::default()
+            }
+        }
+    }
+
+    // Overrides values with the environment variables used before the config file existed
+    fn apply_env(&mut self, errors: &mut Vec<String>) {
+        env_parse(errors, "APP_PORT", &mut self.app.port);
+        env_parse(errors, "LOG_FORMAT", &mut self.app.log_format);
+        env_parse(
+            errors,
+            "SHUTDOWN_TIMEOUT_SECS",
+            &mut self.app.shutdown_timeout_secs,
+        );
+
+        env_optional(errors, "DATABASE_URL", &mut self.database.url);
+        env_optional(errors, "DATABASE_URL_FILE", &mut self.database.url_file);
+        env_parse(
+            errors,
+            "SQLX_MAX_CONNECTIONS",
+            &mut self.database.max_connections,
+        );
+        env_parse(
+            errors,
+            "SQLX_ACQUIRE_TIMEOUT",
+            &mut self.database.acquire_timeout_secs,
+        );
+
+        env_parse(errors, "REDIS_URL", &mut self.redis.url);
+        env_optional(errors, "REDIS_URL_FILE", &mut self.redis.url_file);
+        env_parse(errors, "REDIS_PRICE_TTL", &mut self.redis.price_ttl_secs);
+
+        env_optional(errors, "COINMARKETCAP_API_KEY", &mut self.cmc.api_key);
+        env_optional(
+            errors,
+            "COINMARKETCAP_API_KEY_FILE",
+            &mut self.cmc.api_key_file,
+        );
+        env_parse(errors, "CMC_BASE_URL", &mut self.cmc.base_url);
+        env_parse(errors, "CMC_TIMEOUT_SECS", &mut self.cmc.timeout_secs);
+        env_parse(errors, "CMC_MAX_RETRIES", &mut self.cmc.max_retries);
+        env_parse(
+            errors,
+            "CMC_RETRY_BASE_DELAY_MS",
+            &mut self.cmc.retry_base_delay_ms,
+        );
+        env_parse(
+            errors,
+            "CMC_CIRCUIT_BREAKER_THRESHOLD",
+            &mut self.cmc.circuit_breaker_threshold,
+        );
+        env_parse(
+            errors,
+            "CMC_CIRCUIT_BREAKER_COOLDOWN_SECS",
+            &mut self.cmc.circuit_breaker_cooldown_secs,
+        );
+        env_parse(
+            errors,
+            "CMC_DAILY_CREDIT_BUDGET",
+            &mut self.cmc.daily_credit_budget,
+        );
+
+        env_parse(
+            errors,
+            "PRICE_REFRESH_HELD_INTERVAL_SECS",
+            &mut self.price_refresh.held_interval_secs,
+        );
+        env_parse(
+            errors,
+            "PRICE_REFRESH_TRACKED_INTERVAL_SECS",
+            &mut self.price_refresh.tracked_interval_secs,
+        );
+
+        env_parse(
+            errors,
+            "HEALTH_MAX_PRICE_AGE_SECS",
+            &mut self.health.max_price_age_secs,
+        );
+        env_parse(
+            errors,
+            "HEALTH_MAX_REFRESH_FAILURES",
+            &mut self.health.max_refresh_failures,
+        );
+
+        env_parse(errors, "LEADER_ELECTION_ENABLED", &mut self.leader.enabled);
+        env_parse(
+            errors,
+            "LEADER_CHECK_INTERVAL_SECS",
+            &mut self.leader.check_interval_secs,
+        );
+
+        env_parse(
+            errors,
+            "STREAM_HEARTBEAT_INTERVAL_SECS",
+            &mut self.stream.heartbeat_interval_secs,
+        );
+        env_parse(
+            errors,
+            "STREAM_RETENTION_SECS",
+            &mut self.stream.retention_secs,
+        );
+
+        env_parse(
+            errors,
+            "WEBHOOK_POLL_INTERVAL_SECS",
+            &mut self.webhooks.poll_interval_secs,
+        );
+        env_parse(
+            errors,
+            "WEBHOOK_TIMEOUT_SECS",
+            &mut self.webhooks.timeout_secs,
+        );
+        env_parse(
+            errors,
+            "WEBHOOK_MAX_ATTEMPTS",
+            &mut self.webhooks.max_attempts,
+        );
+        env_parse(
+            errors,
+            "WEBHOOK_RETRY_BASE_DELAY_SECS",
+            &mut self.webhooks.retry_base_delay_secs,
+        );
+
+        env_parse(
+            errors,
+            "IDEMPOTENCY_KEY_TTL_SECS",
+            &mut self.idempotency.key_ttl_secs,
+        );
+    }
+
+    // Replaces secrets with the contents of their *_file settings
+    fn resolve_secrets(&mut self, errors: &mut Vec<String>) {
+        if let Some(path) = &self.database.url_file {
+            self.database.url = read_secret(errors, "database.url_file", path);
+        }
+        if let Some(path) = &self.redis.url_file {
+            if let Some(url) = read_secret(errors, "redis.url_file", path) {
+                self.redis.url = url;
+            }
+        }
+        if let Some(path) = &self.cmc.api_key_file {
+            self.cmc.api_key = read_secret(errors, "cmc.api_key_file", path);
+        }
+    }
+
+    // Checks required values and ranges
+    fn validate(&self, errors: &mut Vec<String>) {
+        if !matches!(self.app.log_format.as_str(), "json" | "pretty") {
+            errors.push(format!(
+                "app.log_format (LOG_FORMAT) must be 'json' or 'pretty', got '{}'",
+                self.app.log_format
+            ));
+        }
+
+        match self.database.url.as_deref() {
+            None | Some("") => errors
+                .push("database.url (DATABASE_URL or DATABASE_URL_FILE) must be set".to_string()),
+            Some(url) if !url.starts_with("postgres://") && !url.starts_with("postgresql://") => {
+                errors.push("database.url must be a postgres:// URL".to_string())
+            }
+            _ => {}
+        }
+        if self.database.max_connections == 0 {
+            errors.push(
+                "database.max_connections (SQLX_MAX_CONNECTIONS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+
+        if !self.redis.url.starts_with("redis://") && !self.redis.url.starts_with("rediss://") {
+            errors.push("redis.url (REDIS_URL) must be a redis:// or rediss:// URL".to_string());
+        }
+        if self.redis.price_ttl_secs == 0 {
+            errors.push(
+                "redis.price_ttl_secs (REDIS_PRICE_TTL) must be greater than zero".to_string(),
+            );
+        }
+
+        if self.cmc.api_key.as_deref().unwrap_or("").is_empty() {
+            errors.push(
+                "cmc.api_key (COINMARKETCAP_API_KEY or COINMARKETCAP_API_KEY_FILE) must be set"
+                    .to_string(),
+            );
+        }
+        if !self.cmc.base_url.starts_with("http://") && !self.cmc.base_url.starts_with("https://") {
+            errors.push("cmc.base_url (CMC_BASE_URL) must be an http(s) URL".to_string());
+        }
+        if self.cmc.timeout_secs == 0 {
+            errors
+                .push("cmc.timeout_secs (CMC_TIMEOUT_SECS) must be greater than zero".to_string());
+        }
+        if self.cmc.circuit_breaker_threshold == 0 {
+            errors.push(
+                "cmc.circuit_breaker_threshold (CMC_CIRCUIT_BREAKER_THRESHOLD) must be greater than zero"
+                    .to_string(),
+            );
+        }
+        if self.cmc.daily_credit_budget < 0 {
+            errors.push(
+                "cmc.daily_credit_budget (CMC_DAILY_CREDIT_BUDGET) must not be negative"
+                    .to_string(),
+            );
+        }
+
+        if self.price_refresh.held_interval_secs == 0 {
+            errors.push(
+                "price_refresh.held_interval_secs (PRICE_REFRESH_HELD_INTERVAL_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+        if self.price_refresh.tracked_interval_secs == 0 {
+            errors.push(
+                "price_refresh.tracked_interval_secs (PRICE_REFRESH_TRACKED_INTERVAL_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+
+        if self.health.max_refresh_failures == 0 {
+            errors.push(
+                "health.max_refresh_failures (HEALTH_MAX_REFRESH_FAILURES) must be greater than zero"
+                    .to_string(),
+            );
+        }
+
+        if self.leader.check_interval_secs == 0 {
+            errors.push(
+                "leader.check_interval_secs (LEADER_CHECK_INTERVAL_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+
+        if self.stream.heartbeat_interval_secs == 0 {
+            errors.push(
+                "stream.heartbeat_interval_secs (STREAM_HEARTBEAT_INTERVAL_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+        if self.stream.retention_secs == 0 {
+            errors.push(
+                "stream.retention_secs (STREAM_RETENTION_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+
+        if self.webhooks.poll_interval_secs == 0 {
+            errors.push(
+                "webhooks.poll_interval_secs (WEBHOOK_POLL_INTERVAL_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+        if self.webhooks.timeout_secs == 0 {
+            errors.push(
+                "webhooks.timeout_secs (WEBHOOK_TIMEOUT_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+        if self.webhooks.max_attempts == 0 {
+            errors.push(
+                "webhooks.max_attempts (WEBHOOK_MAX_ATTEMPTS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+
+        if self.idempotency.key_ttl_secs == 0 {
+            errors.push(
+                "idempotency.key_ttl_secs (IDEMPOTENCY_KEY_TTL_SECS) must be greater than zero"
+                    .to_string(),
+            );
+        }
+    }
+}
+
+impl DatabaseConfig {
+    // Returns the validated connection URL
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or_default()
+    }
+}
+
+impl CmcConfig {
+    // Returns the validated API key
+    pub fn api_key(&self) -> &str {
+        self.api_key.as_deref().unwrap_or_default()
+    }
+
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout_secs)
+    }
+
+    pub fn retry_base_delay(&self) -> Duration {
+        Duration::from_millis(self.retry_base_delay_ms)
+    }
+
+    pub fn circuit_breaker_cooldown(&self) -> Duration {
+        Duration::from_secs(self.circuit_breaker_cooldown_secs)
+    }
+}
+
+// Overrides a value with a parsed environment variable, recording invalid values
+fn env_parse<T: FromStr>(errors: &mut Vec<String>, name: &str, target: &mut T)
+where
+    T::Err: fmt::Display,
+{
+    if let Some(value) = env_value(errors, name) {
+        match value.parse::<T>() {
+            Ok(parsed) => *target = parsed,
+            Err(e) => errors.push(format!("{} has an invalid value '{}': {}", name, value, e)),
+        }
+    }
+}
+
+// Overrides an optional string with an environment variable
+fn env_optional(errors: &mut Vec<String>, name: &str, target: &mut Option<String>) {
+    if let Some(value) = env_value(errors, name) {
+        *target = Some(value);
+    }
+}
+
+// Reads an environment variable; empty values count as unset because docker-compose passes
+// variables missing from .env as empty strings
+fn env_value(errors: &mut Vec<String>, name: &str) -> Option<String> {
+    match env::var(name) {
+        Ok(value) if value.is_empty() => None,
+        Ok(value) => Some(value),
+        Err(env::VarError::NotPresent) => None,
+        Err(e) => {
+            errors.push(format!("{}: {}", name, e));
+            None
+        }
+    }
+}
+
+// Reads a secret from a file, trimming the trailing newline most editors and tools add
+fn read_secret(errors: &mut Vec<String>, setting: &str, path: &str) -> Option<String> {
+    match fs::read_to_string(path) {
+        Ok(contents) => Some(contents.trim_end_matches(['\r', '\n']).to_string()),
+        Err(e) => {
+            errors.push(format!("{}: failed to read {}: {}", setting, path, e));
+            None
+        }
+    }
+}
@@ -13,12 +13,35 @@ pub struct AssetDto {
     pub cmc_id: i32,
     pub decimals: Option<i32>,
     pub rank: Option<i32>,
+    pub slug: Option<String>,
+    pub tags: Vec<String>,
+    pub platform: Option<AssetPlatformDto>,
+    pub circulating_supply: Option<f64>,
+    pub total_supply: Option<f64>,
+    pub max_supply: Option<f64>,
+    #[schema(value_type = Option<String>)]
+    pub date_added: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub metadata_updated_at: Option<String>,
     #[schema(value_type = String)]
     pub created_at: String,
 }
 
+// DTO for the platform a token is issued on
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct AssetPlatformDto {
+    pub name: String,
+    pub symbol: Option<String>,
+    pub token_address: Option<String>,
+}
+
 impl From<AssetDb> for AssetDto {
     fn from(record: AssetDb) -> Self {
+        let platform = record.platform_name.map(|name| AssetPlatformDto {
+            name,
+            symbol: record.platform_symbol,
+            token_address: record.token_address,
+        });
         Self {
             id: record.id,
             symbol: record.symbol,
@@ -26,11 +49,36 @@ impl From<AssetDb> for AssetDto {
             cmc_id: record.cmc_id,
             decimals: record.decimals,
             rank: record.rank,
+            slug: record.slug,
+            tags: record.tags,
+            platform,
+            circulating_supply: record.circulating_supply,
+            total_supply: record.total_supply,
+            max_supply: record.max_supply,
+            date_added: record.date_added.map(format_iso8601),
+            metadata_updated_at: record.metadata_updated_at.map(format_iso8601),
             created_at: format_iso8601(record.created_at),
         }
     }
 }
 
+// DTO for a symbol shared by several assets
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SymbolCollisionDto {
+    pub symbol: String,
+    pub assets: Vec<CollidingAssetDto>,
+}
+
+// DTO for one of the assets sharing a symbol
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CollidingAssetDto {
+    pub asset_id: i32,
+    pub cmc_id: i32,
+    pub name: String,
+    pub rank: Option<i32>,
+    pub held_amount: f64,
+}
+
 // DTO for creating a new asset via API
 #[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
 pub struct CreateAssetDto {
@@ -54,6 +102,27 @@ pub struct UpdateAssetsResponse {
     pub updated_at: String,
 }
 
+// DTO for requesting an immediate price refresh of specific assets
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct RefreshPricesDto {
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Between 1 and 100 asset IDs are required"
+    ))]
+    pub asset_ids: Vec<i32>,
+}
+
+// DTO for the response of a price refresh
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RefreshPricesResponse {
+    pub refreshed_count: usize,
+    pub unknown_asset_ids: Vec<i32>, // Requested IDs that do not exist
+    pub missing_price_asset_ids: Vec<i32>, // Existing assets CoinMarketCap returned no price for
+    #[schema(value_type = String)]
+    pub refreshed_at: String,
+}
+
 // DTO for asset price response with asset details in API
 #[derive(Debug, Serialize, ToSchema)]
 pub struct AssetPriceWithDetailsDto {
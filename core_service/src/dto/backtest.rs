@@ -0,0 +1,124 @@
+use crate::models::backtest::{BacktestInterval, BacktestStrategy};
+use crate::models::dca::DcaFrequency;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+// DTO for an asset of a backtest and its target weight
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct BacktestAssetDto {
+    #[validate(range(min = 1, message = "Asset ID must be positive"))]
+    pub asset_id: i32,
+    #[validate(range(exclusive_min = 0.0, message = "Weight must be positive"))]
+    pub weight: Option<f64>, // Relative to the other assets; default: equal weights
+}
+
+// DTO for replaying a strategy against the stored price history
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct BacktestRequestDto {
+    #[validate(custom(
+        function = "validate_strategy",
+        message = "Strategy must be one of 'dca', 'lump_sum' or 'rebalance'"
+    ))]
+    pub strategy: String,
+    // Assets are validated one by one by the service so errors can name the failing asset
+    #[validate(length(min = 1, max = 20, message = "Between 1 and 20 assets are required"))]
+    pub assets: Vec<BacktestAssetDto>,
+    pub start_date: String,       // ISO 8601
+    pub end_date: Option<String>, // ISO 8601; default: now
+    #[validate(range(exclusive_min = 0.0, message = "Initial capital must be positive"))]
+    pub initial_capital_usd: f64,
+    #[validate(range(min = 0.0, max = 0.1, message = "Fee rate must be between 0 and 0.1"))]
+    pub fee_rate: Option<f64>, // Fraction of every trade, e.g. 0.001 for 0.1%; default: 0
+    #[validate(range(min = 0.0, message = "Fixed fee must be non-negative"))]
+    pub fixed_fee_usd: Option<f64>, // Charged per trade; default: 0
+    #[validate(custom(
+        function = "validate_interval",
+        message = "Interval must be one of 'hourly' or 'daily'"
+    ))]
+    pub interval: Option<String>, // Spacing of the equity curve; default: "daily"
+    #[validate(custom(
+        function = "validate_dca_frequency",
+        message = "DCA frequency must be one of 'daily', 'weekly', 'biweekly' or 'monthly'"
+    ))]
+    pub dca_frequency: Option<String>, // "dca" only; default: "weekly"
+    #[validate(range(exclusive_min = 0.0, message = "DCA amount must be positive"))]
+    pub dca_amount_usd: Option<f64>, // "dca" only; default: the capital split over all buys
+    #[validate(range(
+        exclusive_min = 0.0,
+        max = 1.0,
+        message = "Rebalance threshold must be greater than 0 and at most 1"
+    ))]
+    pub rebalance_threshold: Option<f64>, // "rebalance" only; weight drift that triggers it
+}
+
+// DTO for the final position in an asset of a backtest
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BacktestAssetResultDto {
+    pub asset_id: i32,
+    pub symbol: String,
+    pub target_weight: f64, // Normalized weight from the request
+    pub amount: f64,
+    pub price_usd: f64, // Price at the end of the backtest
+    pub value_usd: f64,
+    pub weight: f64, // Share of the final portfolio value including cash
+}
+
+// DTO for a point of the equity curve of a backtest
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BacktestPointDto {
+    #[schema(value_type = String)]
+    pub timestamp: String,
+    pub value_usd: f64, // Cash plus holdings
+    pub cash_usd: f64,
+}
+
+// DTO for the result of a backtest
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BacktestResultDto {
+    pub strategy: String,
+    #[schema(value_type = String)]
+    pub start_date: String, // First point at which every asset has a stored price
+    #[schema(value_type = String)]
+    pub end_date: String,
+    pub initial_capital_usd: f64,
+    pub final_value_usd: f64,
+    pub cash_usd: f64,     // Capital not invested at the end
+    pub invested_usd: f64, // Total spent on buys, including their fees
+    pub total_fees_usd: f64,
+    pub trades: u32,
+    pub rebalances: u32,
+    pub total_return_pct: f64,
+    pub annualized_return_pct: Option<f64>, // None for periods shorter than 30 days
+    pub max_drawdown_pct: f64,
+    pub volatility_pct: Option<f64>, // Annualized, from the returns between curve points
+    pub assets: Vec<BacktestAssetResultDto>,
+    pub equity_curve: Vec<BacktestPointDto>,
+}
+
+// Custom validation function for the strategy of a backtest
+fn validate_strategy(strategy: &str) -> Result<(), ValidationError> {
+    if BacktestStrategy::parse(strategy).is_some() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("strategy"))
+    }
+}
+
+// Custom validation function for the interval of a backtest
+fn validate_interval(interval: &str) -> Result<(), ValidationError> {
+    if BacktestInterval::parse(interval).is_some() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("interval"))
+    }
+}
+
+// Custom validation function for the DCA frequency of a backtest
+fn validate_dca_frequency(frequency: &str) -> Result<(), ValidationError> {
+    if DcaFrequency::parse(frequency).is_some() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("dca_frequency"))
+    }
+}
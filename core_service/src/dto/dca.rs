@@ -0,0 +1,184 @@
+use crate::models::dca::{DcaExecutionDb, DcaFrequency, DcaPlanDb};
+use crate::utils::datetime::format_iso8601;
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+// DTO for DCA plan response in API
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DcaPlanDto {
+    pub id: i32,
+    pub asset_id: i32,
+    pub asset: String, // Symbol of the asset
+    pub wallet_id: i32,
+    pub wallet: String, // Name of the wallet
+    pub amount_usd: f64,
+    pub frequency: String,
+    #[schema(value_type = String)]
+    pub start_date: String,
+    #[schema(value_type = Option<String>)]
+    pub end_date: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub next_execution_at: Option<String>, // None once the plan has passed its end date
+    pub active: bool,
+    pub notes: Option<String>,
+    pub pending_executions: i64,
+    pub latest_price_usd: Option<f64>,
+    #[schema(value_type = String)]
+    pub created_at: String,
+    #[schema(value_type = String)]
+    pub updated_at: String,
+}
+
+impl From<DcaPlanDb> for DcaPlanDto {
+    fn from(record: DcaPlanDb) -> Self {
+        Self {
+            id: record.id,
+            asset_id: record.asset_id,
+            asset: record.asset,
+            wallet_id: record.wallet_id,
+            wallet: record.wallet,
+            amount_usd: record.amount_usd,
+            frequency: record.frequency,
+            start_date: format_iso8601(record.start_date),
+            end_date: record.end_date.map(format_iso8601),
+            next_execution_at: record.next_execution_at.map(format_iso8601),
+            active: record.active,
+            notes: record.notes,
+            pending_executions: record.pending_executions,
+            latest_price_usd: record.latest_price_usd,
+            created_at: format_iso8601(record.created_at),
+            updated_at: format_iso8601(record.updated_at),
+        }
+    }
+}
+
+// DTO for creating a DCA plan via API
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct CreateDcaPlanDto {
+    #[validate(range(min = 1, message = "Asset ID must be positive"))]
+    pub asset_id: i32,
+    #[validate(range(min = 1, message = "Wallet ID must be positive"))]
+    pub wallet_id: i32,
+    #[validate(range(exclusive_min = 0.0, message = "Amount must be positive"))]
+    pub amount_usd: f64,
+    #[validate(custom(
+        function = "validate_frequency",
+        message = "Frequency must be one of 'daily', 'weekly', 'biweekly' or 'monthly'"
+    ))]
+    pub frequency: String,
+    pub start_date: String, // ISO 8601; executions before the plan is created are not generated
+    pub end_date: Option<String>, // ISO 8601, inclusive
+    #[validate(length(max = 500))]
+    pub notes: Option<String>, // Copied to the confirmed transactions
+}
+
+// DTO for updating a DCA plan via API; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct UpdateDcaPlanDto {
+    #[validate(range(exclusive_min = 0.0, message = "Amount must be positive"))]
+    pub amount_usd: Option<f64>,
+    #[validate(custom(
+        function = "validate_frequency",
+        message = "Frequency must be one of 'daily', 'weekly', 'biweekly' or 'monthly'"
+    ))]
+    pub frequency: Option<String>,
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[schema(value_type = Option<String>)]
+    pub end_date: Option<Option<String>>, // null removes the end date
+    pub active: Option<bool>, // Executions missed while paused are not generated
+    #[validate(length(max = 500))]
+    #[serde(default, deserialize_with = "deserialize_nullable")]
+    #[schema(value_type = Option<String>)]
+    pub notes: Option<Option<String>>, // null removes the notes
+}
+
+// DTO for an execution generated from a DCA plan
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DcaExecutionDto {
+    pub id: i32,
+    pub plan_id: i32,
+    pub asset_id: i32,
+    pub asset: String,
+    pub wallet_id: i32,
+    pub wallet: String,
+    #[schema(value_type = String)]
+    pub scheduled_at: String,
+    pub amount_usd: f64,
+    pub price: Option<f64>,          // Latest stored price when generated
+    pub amount: Option<f64>,         // amount_usd at that price
+    pub status: String,              // "pending", "confirmed" or "skipped"
+    pub transaction_id: Option<i32>, // Transaction created on confirmation
+    #[schema(value_type = String)]
+    pub created_at: String,
+    #[schema(value_type = Option<String>)]
+    pub resolved_at: Option<String>,
+}
+
+impl From<DcaExecutionDb> for DcaExecutionDto {
+    fn from(record: DcaExecutionDb) -> Self {
+        Self {
+            id: record.id,
+            plan_id: record.plan_id,
+            asset_id: record.asset_id,
+            asset: record.asset,
+            wallet_id: record.wallet_id,
+            wallet: record.wallet,
+            scheduled_at: format_iso8601(record.scheduled_at),
+            amount_usd: record.amount_usd,
+            price: record.price,
+            amount: record.amount,
+            status: record.status,
+            transaction_id: record.transaction_id,
+            created_at: format_iso8601(record.created_at),
+            resolved_at: record.resolved_at.map(format_iso8601),
+        }
+    }
+}
+
+// DTO for confirming a pending execution as a buy, optionally adjusting it
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct ConfirmDcaExecutionDto {
+    #[validate(range(exclusive_min = 0.0, message = "Price must be positive"))]
+    pub price: Option<f64>, // Default: the price of the execution
+    #[validate(range(exclusive_min = 0.0, message = "Amount must be positive"))]
+    pub amount: Option<f64>, // Default: amount_usd of the execution at the price
+    #[validate(range(min = 0.0, message = "Fee must be non-negative"))]
+    pub fee: Option<f64>,
+    #[validate(length(max = 500))]
+    pub notes: Option<String>, // Default: the notes of the plan
+}
+
+// DTO for an upcoming execution of a DCA plan
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DcaUpcomingExecutionDto {
+    pub plan_id: i32,
+    pub asset_id: i32,
+    pub asset: String,
+    pub wallet_id: i32,
+    pub wallet: String,
+    #[schema(value_type = String)]
+    pub scheduled_at: String,
+    pub amount_usd: f64,
+    pub estimated_price: Option<f64>,  // Latest stored price
+    pub estimated_amount: Option<f64>, // amount_usd at the latest stored price
+}
+
+// Deserializes a field that is present, as null or a value, into Some, so a missing field (None
+// by default) can be told apart from an explicit null
+fn deserialize_nullable<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+// Custom validation function for the frequency of a plan
+fn validate_frequency(frequency: &str) -> Result<(), ValidationError> {
+    if DcaFrequency::parse(frequency).is_some() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("frequency"))
+    }
+}
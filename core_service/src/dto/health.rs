@@ -0,0 +1,49 @@
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// DTO for the liveness check response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LivenessDto {
+    pub status: String, // Always "alive" when the process can serve requests
+}
+
+// DTO for the state of a single readiness component
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ComponentHealthDto {
+    pub status: String, // "up" or "down"
+    pub critical: bool, // Whether a "down" status makes the service not ready
+    pub message: Option<String>,
+    pub latency_ms: Option<u64>,
+}
+
+// DTO for the readiness components checked
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessComponentsDto {
+    pub database: ComponentHealthDto,
+    pub redis: ComponentHealthDto,
+    pub prices: ComponentHealthDto,
+    pub held_price_refresh: ComponentHealthDto,
+    pub tracked_price_refresh: ComponentHealthDto,
+}
+
+// DTO for the status of a supervised background task
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaskStatusDto {
+    pub name: String,
+    pub state: String, // "running", "standby", "restarting" or "stopped"
+    pub restarts: u32,
+    #[schema(value_type = String)]
+    pub started_at: String,
+    pub last_error: Option<String>,
+    #[schema(value_type = Option<String>)]
+    pub last_error_at: Option<String>,
+}
+
+// DTO for the readiness check response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadinessDto {
+    pub status: String, // "ready" or "not_ready"
+    pub components: ReadinessComponentsDto,
+    #[schema(value_type = String)]
+    pub checked_at: String,
+}
@@ -1,4 +1,13 @@
 pub mod asset;
+pub mod backtest;
+pub mod dca;
+pub mod health;
+pub mod pagination;
+pub mod scenario;
 pub mod snapshot;
+pub mod stream;
+pub mod tag;
 pub mod transaction;
 pub mod wallet;
+pub mod watchlist;
+pub mod webhook;
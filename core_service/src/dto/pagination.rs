@@ -0,0 +1,23 @@
+use crate::models::pagination::{PageParams, SortFields};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// DTO for a page of a list endpoint
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PageDto<T> {
+    pub items: Vec<T>,
+    pub total: i64, // Number of items matching the filters across all pages
+    pub next_cursor: Option<String>, // Pass as `cursor` to fetch the next page; null on the last page
+}
+
+impl<T> PageDto<T> {
+    // Creates the page of the given parameters from its items and the total count
+    pub fn new<S: SortFields>(items: Vec<T>, total: i64, page: &PageParams<S>) -> Self {
+        let next_cursor = page.next_cursor(items.len(), total);
+        Self {
+            items,
+            total,
+            next_cursor,
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+// DTO for the price of one asset in a scenario, as a percentage change or an absolute price
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct ScenarioOverrideDto {
+    #[validate(range(min = 1, message = "Asset ID must be positive"))]
+    pub asset_id: i32,
+    #[validate(range(min = -100.0, message = "Change must be at least -100%"))]
+    pub change_pct: Option<f64>, // e.g. -30 for a 30% drop
+    #[validate(range(min = 0.0, message = "Price must be non-negative"))]
+    pub price_usd: Option<f64>,
+}
+
+// DTO for a what-if price scenario applied to the current holdings
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct ScenarioRequestDto {
+    // Overrides are validated one by one by the service so errors can name the failing override
+    #[validate(length(max = 100, message = "At most 100 overrides are allowed"))]
+    #[serde(default)]
+    pub overrides: Vec<ScenarioOverrideDto>,
+    #[validate(range(min = -100.0, message = "Shock must be at least -100%"))]
+    pub shock_pct: Option<f64>, // Change of every asset without an override; default: 0
+}
+
+// DTO for a held asset under a scenario
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScenarioAssetDto {
+    pub asset_id: i32,
+    pub symbol: String,
+    pub amount: f64,
+    pub adjustment: String, // "change_pct", "price_usd", "shock" or "none"
+    pub current_price_usd: Option<f64>, // Latest stored price; None if the asset has none
+    pub scenario_price_usd: Option<f64>,
+    pub current_value_usd: Option<f64>,
+    pub scenario_value_usd: Option<f64>,
+    pub change_usd: Option<f64>,
+    pub change_pct: Option<f64>,
+    pub current_weight: Option<f64>, // Share of current_value_usd of the portfolio
+    pub scenario_weight: Option<f64>, // Share of scenario_value_usd of the portfolio
+}
+
+// DTO for the result of a scenario
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ScenarioResultDto {
+    pub current_value_usd: f64, // Assets without a stored price are left out of the totals
+    pub scenario_value_usd: f64,
+    pub change_usd: f64,
+    pub change_pct: Option<f64>, // None when the current value is zero
+    pub assets: Vec<ScenarioAssetDto>,
+}
@@ -6,6 +6,7 @@ use utoipa::ToSchema;
 // DTO for a single asset in a snapshot
 #[derive(Debug, Deserialize, Serialize, ToSchema, Clone)]
 pub struct SnapshotAssetDto {
+    pub asset_id: Option<i32>,
     pub symbol: String,
     pub amount: f64,
     pub cmc_id: i32,
@@ -28,6 +29,7 @@ impl From<SnapshotDb> for SnapshotDto {
             .0
             .into_iter()
             .map(|asset| SnapshotAssetDto {
+                asset_id: asset.asset_id,
                 symbol: asset.symbol,
                 amount: asset.amount,
                 cmc_id: asset.cmc_id,
@@ -45,6 +47,7 @@ impl From<SnapshotDb> for SnapshotDto {
 // DTO for difference in asset amounts
 #[derive(Debug, Deserialize, Serialize, ToSchema)]
 pub struct SnapshotDiffDto {
+    pub asset_id: Option<i32>,
     pub symbol: String,
     pub amount_diff: f64,
     pub cmc_id: i32,
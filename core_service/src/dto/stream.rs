@@ -0,0 +1,54 @@
+use crate::models::stream::PriceEventDb;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+// DTO for a price pushed to streaming clients
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct PriceUpdateDto {
+    pub asset_id: i32,
+    pub price_usd: f64,
+    #[schema(value_type = String)]
+    pub timestamp: String,
+}
+
+impl From<PriceEventDb> for PriceUpdateDto {
+    fn from(price: PriceEventDb) -> Self {
+        Self {
+            asset_id: price.asset_id,
+            price_usd: price.price_usd,
+            timestamp: price.timestamp,
+        }
+    }
+}
+
+// DTO for the data of a "prices" stream event
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct PricesEventDto {
+    pub prices: Vec<PriceUpdateDto>,
+}
+
+// DTO for a holding in a "portfolio" stream event
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct PortfolioHoldingDto {
+    pub asset_id: i32,
+    pub symbol: String,
+    pub amount: f64,
+    pub price_usd: Option<f64>, // None when no price is stored yet
+    pub value_usd: Option<f64>,
+}
+
+// DTO for the data of a "portfolio" stream event, valued with the latest stored prices
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct PortfolioEventDto {
+    pub total_value_usd: f64,
+    pub holdings: Vec<PortfolioHoldingDto>,
+    #[schema(value_type = String)]
+    pub updated_at: String,
+}
+
+// DTO for the data of a "heartbeat" stream event
+#[derive(Debug, Serialize, Clone, ToSchema)]
+pub struct HeartbeatDto {
+    #[schema(value_type = String)]
+    pub timestamp: String,
+}
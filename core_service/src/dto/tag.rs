@@ -0,0 +1,98 @@
+use crate::models::tag::TagDb;
+use crate::utils::datetime::format_iso8601;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::{Validate, ValidationError};
+
+// DTO for tag response in API
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct TagDto {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub transaction_count: i64,
+    #[schema(value_type = String)]
+    pub created_at: String,
+}
+
+impl From<TagDb> for TagDto {
+    fn from(record: TagDb) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            description: record.description,
+            transaction_count: record.transaction_count,
+            created_at: format_iso8601(record.created_at),
+        }
+    }
+}
+
+// DTO for creating or updating a tag via API
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct CreateTagDto {
+    #[validate(custom(
+        function = "validate_tag_name",
+        message = "Name must be 1-50 letters, digits, spaces, '-', '_' or '.'"
+    ))]
+    pub name: String,
+    #[validate(length(max = 200, message = "Description must be at most 200 characters long"))]
+    pub description: Option<String>,
+}
+
+// DTO for tagging transactions in bulk via API
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct TagTransactionsDto {
+    #[validate(length(
+        min = 1,
+        max = 500,
+        message = "Between 1 and 500 transaction IDs are required"
+    ))]
+    pub transaction_ids: Vec<i32>,
+}
+
+// DTO for the result of tagging transactions in bulk
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagTransactionsResponse {
+    pub tagged: u64,         // Transactions that did not have the tag yet
+    pub already_tagged: u64, // Transactions that already had the tag
+}
+
+// DTO for the holdings and profit and loss of the transactions of one tag
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagBreakdownDto {
+    pub tag_id: Option<i32>, // None for the transactions without tags
+    pub tag: Option<String>,
+    pub value_usd: f64, // Sum over the assets with a known price
+    pub cost_basis_usd: f64,
+    pub realized_pnl_usd: f64,
+    pub unrealized_pnl_usd: f64,
+    pub assets: Vec<TagAssetBreakdownDto>,
+}
+
+// DTO for the holding and profit and loss of one asset within a tag
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagAssetBreakdownDto {
+    pub asset_id: i32,
+    pub symbol: String,
+    pub amount: f64,                   // Bought minus sold
+    pub average_cost_usd: Option<f64>, // None when nothing was bought
+    pub cost_basis_usd: Option<f64>,   // Average cost of the amount still held
+    pub price_usd: Option<f64>,        // Latest stored price
+    pub value_usd: Option<f64>,
+    pub realized_pnl_usd: Option<f64>, // Sells against the average cost
+    pub unrealized_pnl_usd: Option<f64>, // Amount still held at the latest price against the average cost
+}
+
+// Custom validation function for tag names
+fn validate_tag_name(name: &str) -> Result<(), ValidationError> {
+    let valid = !name.trim().is_empty()
+        && name.chars().count() <= 50
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(ValidationError::new("name"))
+    }
+}
@@ -1,21 +1,24 @@
-use crate::models::transaction::TransactionDb;
+use crate::models::transaction::{validate_transaction_type, TransactionDb};
 use crate::utils::datetime::format_iso8601;
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use utoipa::ToSchema;
-use validator::{Validate, ValidationError};
+use validator::Validate;
 
 // DTO for transaction response in API
 #[derive(Debug, Deserialize, Serialize, FromRow, ToSchema)]
 pub struct TransactionDto {
     pub id: i32,
-    pub asset: String,
-    pub wallet: String,
+    pub asset_id: i32,
+    pub asset: String, // Symbol of the asset
+    pub wallet_id: i32,
+    pub wallet: String, // Name of the wallet
     pub amount: f64,
     pub price: f64,
     pub transaction_type: String,
     pub fee: Option<f64>,
     pub notes: Option<String>,
+    pub tags: Vec<String>, // Names of the tags of the transaction
     #[schema(value_type = String)]
     pub created_at: String,
 }
@@ -24,13 +27,16 @@ impl From<TransactionDb> for TransactionDto {
     fn from(record: TransactionDb) -> Self {
         Self {
             id: record.id,
+            asset_id: record.asset_id,
             asset: record.asset,
+            wallet_id: record.wallet_id,
             wallet: record.wallet,
             amount: record.amount,
             price: record.price,
             transaction_type: record.transaction_type,
             fee: record.fee,
             notes: record.notes,
+            tags: record.tags,
             created_at: format_iso8601(record.created_at),
         }
     }
@@ -58,11 +64,21 @@ pub struct CreateTransactionDto {
     pub notes: Option<String>,
 }
 
-// Custom validation function for transaction_type
-fn validate_transaction_type(transaction_type: &str) -> Result<(), ValidationError> {
-    if transaction_type == "BUY" || transaction_type == "SELL" {
-        Ok(())
-    } else {
-        Err(ValidationError::new("transaction_type"))
-    }
+// DTO for creating several transactions at once via API
+#[derive(Debug, Deserialize, Validate, ToSchema)]
+pub struct CreateTransactionBatchDto {
+    // Items are validated one by one by the service so errors can name the failing item
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Batch must contain between 1 and 100 transactions"
+    ))]
+    pub transactions: Vec<CreateTransactionDto>,
+}
+
+// DTO for the result of a batch of transactions
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionBatchDto {
+    pub created: usize,
+    pub transactions: Vec<TransactionDto>, // Created transactions in the order of the request
 }
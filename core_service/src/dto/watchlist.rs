@@ -0,0 +1,87 @@
+use crate::models::watchlist::{WatchlistAssetDb, WatchlistDb};
+use crate::utils::datetime::format_iso8601;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+// DTO for watchlist response in API
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct WatchlistDto {
+    pub id: i32,
+    pub name: String,
+    pub asset_ids: Vec<i32>,
+    #[schema(value_type = String)]
+    pub created_at: String,
+}
+
+impl From<WatchlistDb> for WatchlistDto {
+    fn from(record: WatchlistDb) -> Self {
+        Self {
+            id: record.id,
+            name: record.name,
+            asset_ids: record.asset_ids,
+            created_at: format_iso8601(record.created_at),
+        }
+    }
+}
+
+// DTO for a watchlist asset with its latest price and changes
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct WatchlistAssetDto {
+    pub asset_id: i32,
+    pub cmc_id: i32,
+    pub symbol: String,
+    pub name: String,
+    pub price_usd: Option<f64>,
+    pub percent_change_24h: Option<f64>,
+    pub percent_change_7d: Option<f64>,
+    #[schema(value_type = Option<String>)]
+    pub timestamp: Option<String>,
+}
+
+impl From<WatchlistAssetDb> for WatchlistAssetDto {
+    fn from(record: WatchlistAssetDb) -> Self {
+        Self {
+            asset_id: record.asset_id,
+            cmc_id: record.cmc_id,
+            symbol: record.symbol,
+            name: record.name,
+            price_usd: record.price_usd,
+            percent_change_24h: record.percent_change_24h,
+            percent_change_7d: record.percent_change_7d,
+            timestamp: record.timestamp.map(format_iso8601),
+        }
+    }
+}
+
+// DTO for watchlist details response in API
+#[derive(Debug, Deserialize, Serialize, ToSchema)]
+pub struct WatchlistDetailDto {
+    pub id: i32,
+    pub name: String,
+    #[schema(value_type = String)]
+    pub created_at: String,
+    pub assets: Vec<WatchlistAssetDto>,
+}
+
+// DTO for creating a new watchlist via API
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct CreateWatchlistDto {
+    #[validate(length(min = 1, max = 50, message = "Name must be 1-50 characters long"))]
+    pub name: String,
+    pub asset_ids: Option<Vec<i32>>,
+}
+
+// DTO for renaming a watchlist via API
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct UpdateWatchlistDto {
+    #[validate(length(min = 1, max = 50, message = "Name must be 1-50 characters long"))]
+    pub name: String,
+}
+
+// DTO for adding an asset to a watchlist via API
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct AddWatchlistAssetDto {
+    #[validate(range(min = 1, message = "Asset ID must be positive"))]
+    pub asset_id: i32,
+}
@@ -0,0 +1,104 @@
+use crate::models::webhook::{WebhookDeliveryDb, WebhookSubscriptionDb};
+use crate::utils::datetime::format_iso8601;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use validator::Validate;
+
+// DTO for webhook subscription response in API; the secret is only returned when it is set
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDto {
+    pub id: i32,
+    pub url: String,
+    pub event_types: Vec<String>, // Empty means every event type
+    pub description: Option<String>,
+    pub active: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    #[schema(value_type = String)]
+    pub created_at: String,
+    #[schema(value_type = String)]
+    pub updated_at: String,
+}
+
+impl From<WebhookSubscriptionDb> for WebhookDto {
+    fn from(record: WebhookSubscriptionDb) -> Self {
+        Self {
+            id: record.id,
+            url: record.url,
+            event_types: record.event_types,
+            description: record.description,
+            active: record.active,
+            secret: None,
+            created_at: format_iso8601(record.created_at),
+            updated_at: format_iso8601(record.updated_at),
+        }
+    }
+}
+
+// DTO for creating a webhook subscription via API
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct CreateWebhookDto {
+    #[validate(url(message = "URL must be a valid http(s) URL"))]
+    pub url: String,
+    pub event_types: Option<Vec<String>>, // Omitted or empty subscribes to every event type
+    #[validate(length(max = 200, message = "Description must be at most 200 characters long"))]
+    pub description: Option<String>,
+    #[validate(length(min = 16, max = 200, message = "Secret must be 16-200 characters long"))]
+    pub secret: Option<String>, // Generated when omitted
+}
+
+// DTO for updating a webhook subscription via API; omitted fields are left unchanged
+#[derive(Debug, Deserialize, Serialize, Validate, ToSchema)]
+pub struct UpdateWebhookDto {
+    #[validate(url(message = "URL must be a valid http(s) URL"))]
+    pub url: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    #[validate(length(max = 200, message = "Description must be at most 200 characters long"))]
+    pub description: Option<String>,
+    pub active: Option<bool>,
+    #[validate(length(min = 16, max = 200, message = "Secret must be 16-200 characters long"))]
+    pub secret: Option<String>,
+}
+
+// DTO for an entry of the webhook delivery log
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveryDto {
+    pub id: i64,
+    pub subscription_id: i32,
+    pub event_id: String,
+    pub event_type: String,
+    pub status: String, // "pending", "delivered" or "failed"
+    pub attempts: i32,
+    #[schema(value_type = Option<String>)]
+    pub next_attempt_at: Option<String>, // Only set while pending
+    #[schema(value_type = Option<String>)]
+    pub last_attempt_at: Option<String>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub payload: serde_json::Value,
+    #[schema(value_type = String)]
+    pub created_at: String,
+    #[schema(value_type = Option<String>)]
+    pub delivered_at: Option<String>,
+}
+
+impl From<WebhookDeliveryDb> for WebhookDeliveryDto {
+    fn from(record: WebhookDeliveryDb) -> Self {
+        Self {
+            id: record.id,
+            subscription_id: record.subscription_id,
+            event_id: record.event_id.to_string(),
+            event_type: record.event_type,
+            next_attempt_at: (record.status == "pending")
+                .then(|| format_iso8601(record.next_attempt_at)),
+            status: record.status,
+            attempts: record.attempts,
+            last_attempt_at: record.last_attempt_at.map(format_iso8601),
+            response_status: record.response_status,
+            last_error: record.last_error,
+            payload: record.payload.0,
+            created_at: format_iso8601(record.created_at),
+            delivered_at: record.delivered_at.map(format_iso8601),
+        }
+    }
+}
@@ -1,108 +1,292 @@
+use crate::logging::current_request_id;
+use crate::services::cmc::CmcError;
+use actix_web::http::header;
 use actix_web::{http::StatusCode, HttpResponse, ResponseError};
 use actix_web_validator::Error as ValidatorError;
 use serde::Serialize;
+use sqlx::postgres::PgDatabaseError;
 use std::error::Error as StdError;
 use std::fmt;
+use std::time::Duration;
+use utoipa::ToSchema;
+use validator::ValidationErrors;
 
-// Custom application error type wrapping anyhow::Error
+// Prefix of the problem type URIs; the stable error code is appended
+const PROBLEM_TYPE_PREFIX: &str = "urn:austeris:error:";
+// Detail returned for unexpected failures, whose cause is only logged
+const INTERNAL_ERROR_DETAIL: &str = "An unexpected error occurred";
+
+// Application errors returned by handlers. Each variant maps to an HTTP status and a stable,
+// machine-readable code, and is rendered as an RFC 7807 problem document.
 #[derive(Debug)]
-pub struct AppError {
-    inner: anyhow::Error,
-    status: StatusCode,
+pub enum AppError {
+    // The requested resource does not exist
+    NotFound(String),
+    // The request conflicts with existing data, e.g. a duplicate unique value
+    Conflict(String),
+    // The request is invalid; errors lists the offending fields when known
+    Validation {
+        message: String,
+        errors: Vec<FieldError>,
+    },
+    // An upstream service (CoinMarketCap) is temporarily unavailable or rate limited
+    UpstreamUnavailable {
+        message: String,
+        retry_after: Option<Duration>,
+    },
+    // An upstream service returned an error or an invalid response
+    UpstreamFailed(String),
+    // Prices needed by the request are outdated and could not be refreshed
+    StalePrice {
+        message: String,
+        asset_ids: Vec<i32>,
+    },
+    // Any other failure; the cause is logged but not returned to the client
+    Internal(anyhow::Error),
 }
 
 impl AppError {
-    // Creates a new AppError with a specific status code
-    pub fn new(err: anyhow::Error, status: StatusCode) -> Self {
-        log::error!("Error occurred: {}", err); // Log the error
-        Self { inner: err, status }
+    // Convenience method for invalid requests not tied to a single field
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::Validation {
+            message: message.into(),
+            errors: Vec::new(),
+        }
+    }
+
+    // Convenience method for an invalid value of a single request field
+    pub fn invalid_field(field: &str, message: impl Into<String>) -> Self {
+        let message = message.into();
+        Self::Validation {
+            message: format!("Validation error: {}: {}", field, message),
+            errors: vec![FieldError {
+                field: field.to_string(),
+                message,
+            }],
+        }
     }
 
-    // Convenience method for internal server errors
-    pub fn internal(err: impl Into<anyhow::Error>) -> Self {
-        Self::new(err.into(), StatusCode::INTERNAL_SERVER_ERROR)
+    // Convenience method for invalid values of several request fields
+    pub fn invalid_fields(errors: Vec<FieldError>) -> Self {
+        let message = errors
+            .iter()
+            .map(|error| format!("{}: {}", error.field, error.message))
+            .collect::<Vec<_>>()
+            .join("; ");
+        Self::Validation {
+            message: format!("Validation error: {}", message),
+            errors,
+        }
     }
 
-    // Convenience method for bad request errors
-    pub fn bad_request(err: impl Into<anyhow::Error>) -> Self {
-        Self::new(err.into(), StatusCode::BAD_REQUEST)
+    // Convenience method for missing resources
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::NotFound(message.into())
     }
 
-    // Convenience method for service unavailable errors
-    pub fn service_unavailable(err: impl Into<anyhow::Error>) -> Self {
-        Self::new(err.into(), StatusCode::SERVICE_UNAVAILABLE)
+    // Stable machine-readable code of the error
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::Conflict(_) => "conflict",
+            Self::Validation { .. } => "validation_failed",
+            Self::UpstreamUnavailable { .. } => "upstream_unavailable",
+            Self::UpstreamFailed(_) => "upstream_failed",
+            Self::StalePrice { .. } => "stale_price",
+            Self::Internal(_) => "internal_error",
+        }
+    }
+
+    // Message returned to the client
+    fn detail(&self) -> String {
+        match self {
+            Self::Internal(_) => INTERNAL_ERROR_DETAIL.to_string(),
+            _ => self.to_string(),
+        }
     }
 }
 
 // Implementation of Display for AppError
 impl fmt::Display for AppError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "{}", self.inner)
+        match self {
+            Self::NotFound(message)
+            | Self::Conflict(message)
+            | Self::Validation { message, .. }
+            | Self::UpstreamUnavailable { message, .. }
+            | Self::UpstreamFailed(message)
+            | Self::StalePrice { message, .. } => write!(f, "{}", message),
+            Self::Internal(err) => write!(f, "{:#}", err),
+        }
     }
 }
 
 // Implementation of std::error::Error for AppError
 impl StdError for AppError {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        self.inner.source()
+        match self {
+            Self::Internal(err) => err.source(),
+            _ => None,
+        }
     }
 }
 
 impl From<anyhow::Error> for AppError {
     fn from(err: anyhow::Error) -> Self {
-        Self::internal(err)
+        // Typed errors raised below the service layer keep their meaning when passed up as anyhow
+        let err = match err.downcast::<AppError>() {
+            Ok(app_err) => return app_err,
+            Err(err) => err,
+        };
+        let err = match err.downcast::<CmcError>() {
+            Ok(cmc_err) => return Self::from(cmc_err),
+            Err(err) => err,
+        };
+        match err.downcast::<sqlx::Error>() {
+            Ok(db_err) => Self::from(db_err),
+            Err(err) => Self::Internal(err),
+        }
+    }
+}
+
+impl From<CmcError> for AppError {
+    fn from(err: CmcError) -> Self {
+        // Upstream CoinMarketCap failures are reported as gateway errors instead of internal ones
+        if !err.is_unavailable() {
+            return Self::UpstreamFailed(err.to_string());
+        }
+        let retry_after = match &err {
+            CmcError::RateLimited { retry_after, .. } => *retry_after,
+            CmcError::CircuitOpen { retry_in } => Some(*retry_in),
+            _ => None,
+        };
+        Self::UpstreamUnavailable {
+            message: err.to_string(),
+            retry_after,
+        }
+    }
+}
+
+impl From<sqlx::Error> for AppError {
+    fn from(err: sqlx::Error) -> Self {
+        // Constraint violations are caused by the request data rather than the server
+        if let Some(pg_err) = err
+            .as_database_error()
+            .and_then(|e| e.try_downcast_ref::<PgDatabaseError>())
+        {
+            let message = pg_err.detail().unwrap_or(pg_err.message()).to_string();
+            match pg_err.code() {
+                "23505" => return Self::Conflict(message),
+                "23503" => return Self::validation(message),
+                _ => {}
+            }
+        }
+        Self::Internal(err.into())
     }
 }
 
 impl From<ValidatorError> for AppError {
     fn from(err: ValidatorError) -> Self {
         match err {
-            ValidatorError::Validate(e) => {
-                let message = e
-                    .field_errors()
-                    .iter()
-                    .flat_map(|(field, errors)| {
-                        errors.iter().map(move |error| {
-                            let msg = error
-                                .message
-                                .as_ref()
-                                .map(|m| m.to_string())
-                                .unwrap_or_else(|| "Validation failed".to_string());
-                            format!("{}: {}", field, msg)
-                        })
-                    })
-                    .collect::<Vec<_>>()
-                    .join("; ");
-                Self::bad_request(anyhow::anyhow!("Validation error: {}", message))
-            }
-            _ => Self::internal(anyhow::anyhow!("Unexpected validation error: {}", err)),
+            ValidatorError::Validate(e) => Self::invalid_fields(field_errors(&e)),
+            // Malformed bodies and query strings are client errors too
+            _ => Self::validation(err.to_string()),
         }
     }
 }
 
+// Lists the field errors reported by validator, one per failed rule
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    errors
+        .field_errors()
+        .iter()
+        .flat_map(|(field, errors)| {
+            errors.iter().map(move |error| FieldError {
+                field: field.to_string(),
+                message: error
+                    .message
+                    .as_ref()
+                    .map(|m| m.to_string())
+                    .unwrap_or_else(|| "Validation failed".to_string()),
+            })
+        })
+        .collect()
+}
+
 impl ResponseError for AppError {
     fn status_code(&self) -> StatusCode {
-        self.status
+        match self {
+            Self::NotFound(_) => StatusCode::NOT_FOUND,
+            Self::Conflict(_) => StatusCode::CONFLICT,
+            Self::Validation { .. } => StatusCode::BAD_REQUEST,
+            Self::UpstreamUnavailable { .. } | Self::StalePrice { .. } => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+            Self::UpstreamFailed(_) => StatusCode::BAD_GATEWAY,
+            Self::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
     }
 
     fn error_response(&self) -> HttpResponse {
-        let response = ErrorResponse {
-            status: self.status.as_u16(),
-            error: self
-                .status
-                .canonical_reason()
-                .unwrap_or("Unknown")
-                .to_string(),
-            message: self.inner.to_string(),
+        let status = self.status_code();
+        if status.is_server_error() {
+            log::error!("Error occurred ({}): {}", self.code(), self);
+        } else {
+            log::warn!("Request rejected ({}): {}", self.code(), self);
+        }
+
+        let problem = ProblemDetails {
+            problem_type: format!("{}{}", PROBLEM_TYPE_PREFIX, self.code()),
+            title: status.canonical_reason().unwrap_or("Unknown").to_string(),
+            status: status.as_u16(),
+            detail: self.detail(),
+            code: self.code().to_string(),
+            request_id: current_request_id(),
+            errors: match self {
+                Self::Validation { errors, .. } if !errors.is_empty() => Some(errors.clone()),
+                _ => None,
+            },
+            asset_ids: match self {
+                Self::StalePrice { asset_ids, .. } => Some(asset_ids.clone()),
+                _ => None,
+            },
         };
-        HttpResponse::build(self.status).json(response)
+
+        let mut response = HttpResponse::build(status);
+        response.insert_header((header::CONTENT_TYPE, "application/problem+json"));
+        if let Self::UpstreamUnavailable {
+            retry_after: Some(retry_after),
+            ..
+        } = self
+        {
+            response.insert_header((header::RETRY_AFTER, retry_after.as_secs().max(1)));
+        }
+        response.body(serde_json::to_string(&problem).unwrap_or_default())
     }
 }
 
-// Structure for JSON error response
-#[derive(Serialize)]
-struct ErrorResponse {
-    status: u16,
-    error: String,
-    message: String,
+// RFC 7807 problem document returned for every error, with the error code and request id as
+// extension members
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    #[schema(example = "urn:austeris:error:not_found")]
+    pub problem_type: String, // URI of the problem type, derived from the code
+    pub title: String,  // Reason phrase of the status
+    pub status: u16,    // HTTP status code
+    pub detail: String, // Explanation of this occurrence
+    #[schema(example = "not_found")]
+    pub code: String, // Stable machine-readable error code
+    pub request_id: Option<String>, // Matches the X-Request-Id header and the server logs
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub errors: Option<Vec<FieldError>>, // Invalid fields of a validation_failed error
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub asset_ids: Option<Vec<i32>>, // Assets without a fresh price of a stale_price error
+}
+
+// Invalid request field reported in a problem document
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
 }
@@ -1,99 +1,302 @@
-use actix_web::{web, App, HttpServer};
+use actix_web::dev::Service;
+use actix_web::{web, App, HttpResponse, HttpServer};
 use anyhow::Result;
-use env_logger;
-use log::LevelFilter;
-use std::env;
-use utoipa::OpenApi;
+use std::time::{Duration, Instant};
+use utoipa::openapi::path::{Operation, ParameterBuilder, ParameterIn};
+use utoipa::openapi::{ContentBuilder, ObjectBuilder, Ref, Required, ResponseBuilder, Type};
+use utoipa::{Modify, OpenApi};
 use utoipa_swagger_ui::SwaggerUi;
 
 // Project modules
+mod config;
 mod db;
 mod dto;
 mod error;
+mod logging;
 mod models;
 mod repository;
 mod routes;
 mod services;
 mod utils;
 
+use config::Config;
 use db::connect;
 use error::AppError;
-use routes::{asset, snapshots, transaction, wallet};
+use routes::{
+    analytics, asset, dca, health, metrics, snapshots, stream, tag, transaction, wallet, watchlist,
+    webhook,
+};
 use services::asset::AssetService;
+use services::backtest::BacktestService;
 use services::cmc::CmcService;
+use services::dca::DcaService;
+use services::health::HealthService;
+use services::idempotency::{IdempotencyService, MAX_KEY_LEN};
+use services::leader::LeaderElection;
+use services::metrics::MetricsService;
 use services::portfolio::PortfolioService;
+use services::price_refresh::{PriceRefreshService, RefreshTier};
 use services::redis::RedisService;
+use services::scenario::ScenarioService;
 use services::snapshot::SnapshotService;
+use services::stream::StreamService;
+use services::supervisor::TaskSupervisor;
+use services::tag::TagService;
 use services::transaction::TransactionService;
 use services::wallet::WalletService;
+use services::watchlist::WatchlistService;
+use services::webhook::WebhookService;
 
+// Versioned resource API, served under /api/v1
 #[derive(OpenApi)]
 #[openapi(
     paths(
         asset::get_assets,
+        asset::get_asset,
         asset::create_asset,
         asset::update_assets,
         asset::get_asset_prices,
         asset::get_price_history,
+        asset::refresh_asset_prices,
+        asset::get_symbol_collisions,
         wallet::get_wallets,
         wallet::create_wallet,
         transaction::get_transactions,
         transaction::create_transaction,
+        transaction::create_transactions,
         transaction::get_portfolio_value,
         snapshots::create_snapshot,
-        snapshots::get_snapshots
+        snapshots::get_snapshots,
+        watchlist::get_watchlists,
+        watchlist::create_watchlist,
+        watchlist::get_watchlist,
+        watchlist::update_watchlist,
+        watchlist::delete_watchlist,
+        watchlist::add_watchlist_asset,
+        watchlist::remove_watchlist_asset,
+        tag::get_tags,
+        tag::create_tag,
+        tag::get_tag_breakdown,
+        tag::get_tag,
+        tag::update_tag,
+        tag::delete_tag,
+        tag::tag_transactions,
+        tag::untag_transaction,
+        dca::get_dca_plans,
+        dca::create_dca_plan,
+        dca::get_dca_plan,
+        dca::update_dca_plan,
+        dca::delete_dca_plan,
+        dca::get_dca_executions,
+        dca::confirm_dca_execution,
+        dca::skip_dca_execution,
+        dca::get_upcoming_dca_executions,
+        analytics::run_backtest,
+        analytics::run_scenario,
+        stream::get_stream,
+        webhook::get_webhooks,
+        webhook::create_webhook,
+        webhook::get_webhook,
+        webhook::update_webhook,
+        webhook::delete_webhook,
+        webhook::get_webhook_deliveries,
+        webhook::retry_webhook_delivery
+    ),
+    components(schemas(
+        dto::asset::AssetDto,
+        dto::asset::AssetPlatformDto,
+        dto::asset::CreateAssetDto,
+        dto::asset::UpdateAssetsResponse,
+        dto::asset::RefreshPricesDto,
+        dto::asset::RefreshPricesResponse,
+        dto::asset::AssetPriceWithDetailsDto,
+        dto::asset::AssetPriceHistoryDto,
+        dto::asset::SymbolCollisionDto,
+        dto::asset::CollidingAssetDto,
+        dto::wallet::WalletDto,
+        dto::wallet::CreateWalletDto,
+        dto::transaction::TransactionDto,
+        dto::transaction::CreateTransactionDto,
+        dto::transaction::CreateTransactionBatchDto,
+        dto::transaction::TransactionBatchDto,
+        dto::snapshot::SnapshotDto,
+        dto::snapshot::SnapshotAssetDto,
+        dto::snapshot::SnapshotDiffDto,
+        dto::watchlist::WatchlistDto,
+        dto::watchlist::WatchlistDetailDto,
+        dto::watchlist::WatchlistAssetDto,
+        dto::watchlist::CreateWatchlistDto,
+        dto::watchlist::UpdateWatchlistDto,
+        dto::watchlist::AddWatchlistAssetDto,
+        dto::tag::TagDto,
+        dto::tag::CreateTagDto,
+        dto::tag::TagTransactionsDto,
+        dto::tag::TagTransactionsResponse,
+        dto::tag::TagBreakdownDto,
+        dto::tag::TagAssetBreakdownDto,
+        dto::dca::DcaPlanDto,
+        dto::dca::CreateDcaPlanDto,
+        dto::dca::UpdateDcaPlanDto,
+        dto::dca::DcaExecutionDto,
+        dto::dca::ConfirmDcaExecutionDto,
+        dto::dca::DcaUpcomingExecutionDto,
+        dto::backtest::BacktestRequestDto,
+        dto::backtest::BacktestAssetDto,
+        dto::backtest::BacktestResultDto,
+        dto::backtest::BacktestAssetResultDto,
+        dto::backtest::BacktestPointDto,
+        dto::scenario::ScenarioRequestDto,
+        dto::scenario::ScenarioOverrideDto,
+        dto::scenario::ScenarioResultDto,
+        dto::scenario::ScenarioAssetDto,
+        dto::stream::PriceUpdateDto,
+        dto::stream::PricesEventDto,
+        dto::stream::PortfolioHoldingDto,
+        dto::stream::PortfolioEventDto,
+        dto::stream::HeartbeatDto,
+        dto::webhook::WebhookDto,
+        dto::webhook::CreateWebhookDto,
+        dto::webhook::UpdateWebhookDto,
+        dto::webhook::WebhookDeliveryDto
+    )),
+    modifiers(&IdempotencyKeyHeader)
+)]
+struct ApiV1Doc;
+
+// Documents the optional Idempotency-Key header, and the conflict it can cause, on every
+// mutating operation
+struct IdempotencyKeyHeader;
+
+impl Modify for IdempotencyKeyHeader {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        for item in openapi.paths.paths.values_mut() {
+            let operations = [&mut item.post, &mut item.put, &mut item.patch];
+            for operation in operations.into_iter().flatten() {
+                add_idempotency_key(operation);
+            }
+        }
+    }
+}
+
+fn add_idempotency_key(operation: &mut Operation) {
+    let parameter = ParameterBuilder::new()
+        .name("Idempotency-Key")
+        .parameter_in(ParameterIn::Header)
+        .required(Required::False)
+        .description(Some(
+            "Client-chosen key making the request safe to retry: the first response is replayed \
+             for identical requests with the same key",
+        ))
+        .schema(Some(
+            ObjectBuilder::new()
+                .schema_type(Type::String)
+                .min_length(Some(1))
+                .max_length(Some(MAX_KEY_LEN)),
+        ))
+        .build();
+    operation
+        .parameters
+        .get_or_insert_with(Vec::new)
+        .push(parameter);
+
+    // Operations that can already conflict describe their own 409 response
+    operation
+        .responses
+        .responses
+        .entry("409".to_string())
+        .or_insert_with(|| {
+            ResponseBuilder::new()
+                .description(
+                    "Idempotency-Key already used for a different request, or its first request \
+                     is still being processed",
+                )
+                .content(
+                    "application/problem+json",
+                    ContentBuilder::new()
+                        .schema(Some(Ref::from_schema_name("ProblemDetails")))
+                        .build(),
+                )
+                .build()
+                .into()
+        });
+}
+
+// Whole API: operational endpoints at the root and the versioned resource API nested below
+#[derive(OpenApi)]
+#[openapi(
+    paths(
+        health::get_liveness,
+        health::get_readiness,
+        health::get_tasks,
+        metrics::get_metrics
+    ),
+    nest(
+        (path = "/api/v1", api = ApiV1Doc)
     ),
     components(
         schemas(
-            dto::asset::AssetDto,
-            dto::asset::CreateAssetDto,
-            dto::asset::UpdateAssetsResponse,
-            dto::asset::AssetPriceWithDetailsDto,
-            dto::asset::AssetPriceHistoryDto,
-            dto::wallet::WalletDto,
-            dto::wallet::CreateWalletDto,
-            dto::transaction::TransactionDto,
-            dto::transaction::CreateTransactionDto,
-            dto::snapshot::SnapshotDto,
-            dto::snapshot::SnapshotAssetDto,
-            dto::snapshot::SnapshotDiffDto
+            dto::health::LivenessDto,
+            dto::health::ReadinessDto,
+            dto::health::ReadinessComponentsDto,
+            dto::health::ComponentHealthDto,
+            dto::health::TaskStatusDto,
+            error::ProblemDetails,
+            error::FieldError
         )
     ),
     tags(
         (name = "Assets", description = "Asset management"),
         (name = "Wallets", description = "Wallet management"),
         (name = "Transactions", description = "Transaction management"),
-        (name = "Snapshots", description = "Portfolio snapshot management")
+        (name = "Snapshots", description = "Portfolio snapshot management"),
+        (name = "Watchlists", description = "Watchlists of followed assets"),
+        (name = "Tags", description = "Custom labels on transactions"),
+        (name = "DCA", description = "Recurring dollar-cost-averaging plans"),
+        (name = "Analytics", description = "Backtests and what-if analysis over stored prices"),
+        (name = "Health", description = "Liveness and readiness checks"),
+        (name = "Metrics", description = "Prometheus metrics"),
+        (name = "Stream", description = "Live price and portfolio updates"),
+        (name = "Webhooks", description = "Outbound webhook subscriptions and deliveries")
     )
 )]
 struct ApiDoc;
 
 #[actix_web::main]
 async fn main() -> Result<()> {
-    // Initialize logging with default level INFO
-    env_logger::Builder::new()
-        .filter_level(LevelFilter::Info)
-        .init();
-    let port = env::var("APP_PORT")
-        .unwrap_or_else(|_| "9000".to_string())
-        .parse::<u16>()
-        .expect("PORT must be a valid number");
+    // Load environment variables from .env file, if present
+    dotenv::dotenv().ok();
+
+    // Load and validate the whole configuration before starting anything
+    let config = match Config::load() {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    logging::init(&config.app.log_format);
+    let port = config.app.port;
 
-    log::info!("Starting application on 127.0.0.1:{}", port);
+    log::info!("Starting application on 0.0.0.0:{}", port);
 
     // Establish database connection
-    let pool = connect().await?;
+    let pool = connect(&config.database).await?;
 
     // Initialize services
-    let cmc_service = CmcService::new();
-    let redis_service = RedisService::new()?;
+    let cmc_service = CmcService::new(web::Data::new(pool.clone()), config.cmc.clone());
+    let redis_service = RedisService::new(&config.redis);
+    let webhook_service = WebhookService::new(web::Data::new(pool.clone()), &config.webhooks);
     let asset_service = AssetService::new(
         web::Data::new(pool.clone()),
         web::Data::new(cmc_service.clone()),
         web::Data::new(redis_service.clone()),
+        web::Data::new(webhook_service.clone()),
     );
     let wallet_service = WalletService::new(web::Data::new(pool.clone()));
-    let transaction_service = TransactionService::new(web::Data::new(pool.clone()));
+    let transaction_service = TransactionService::new(
+        web::Data::new(pool.clone()),
+        web::Data::new(webhook_service.clone()),
+    );
     let portfolio_service = PortfolioService::new(
         web::Data::new(pool.clone()),
         web::Data::new(cmc_service.clone()),
@@ -102,38 +305,104 @@ async fn main() -> Result<()> {
     let snapshot_service = SnapshotService::new(
         web::Data::new(pool.clone()),
         web::Data::new(portfolio_service.clone()),
+        web::Data::new(webhook_service.clone()),
+    );
+    let watchlist_service = WatchlistService::new(web::Data::new(pool.clone()));
+    let tag_service = TagService::new(web::Data::new(pool.clone()));
+    let dca_service = DcaService::new(
+        web::Data::new(pool.clone()),
+        web::Data::new(transaction_service.clone()),
     );
+    let backtest_service = BacktestService::new(
+        web::Data::new(pool.clone()),
+        web::Data::new(redis_service.clone()),
+    );
+    let scenario_service = ScenarioService::new(web::Data::new(portfolio_service.clone()));
+    let idempotency_service =
+        IdempotencyService::new(web::Data::new(pool.clone()), &config.idempotency);
 
-    // Spawn periodic price updates
-    let pool_for_task = pool.clone();
-    let cmc_service_for_task = cmc_service.clone();
-    let redis_service_for_task = redis_service.clone();
-    tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(15 * 60)); // Every 15 minutes
-        loop {
-            interval.tick().await;
-            log::info!("Updating asset prices...");
-            match cmc_service_for_task
-                .fetch_quotes_for_assets(&pool_for_task)
-                .await
-            {
-                Ok(quotes) => {
-                    let price_repo = repository::asset_price::AssetPriceRepository::new(
-                        &pool_for_task,
-                        redis_service_for_task.clone(),
-                    );
-                    match price_repo.save_prices(quotes).await {
-                        Ok(count) => log::info!("Updated {} asset prices successfully", count),
-                        Err(e) => log::error!("Failed to save prices: {}", e),
-                    }
-                }
-                Err(e) => log::error!("Failed to fetch quotes from CMC: {}", e),
-            }
-        }
-    });
+    let price_refresh_service = PriceRefreshService::new(
+        web::Data::new(pool.clone()),
+        web::Data::new(cmc_service.clone()),
+        web::Data::new(redis_service.clone()),
+        web::Data::new(webhook_service.clone()),
+        &config.price_refresh,
+    );
 
-    // Configure and start the HTTP server
-    HttpServer::new(move || {
+    let stream_service = StreamService::new(
+        web::Data::new(pool.clone()),
+        web::Data::new(redis_service.clone()),
+        web::Data::new(portfolio_service.clone()),
+        &config.stream,
+    );
+
+    let supervisor = TaskSupervisor::new(LeaderElection::new(&pool, &config.leader));
+
+    let health_service = HealthService::new(
+        web::Data::new(pool.clone()),
+        web::Data::new(redis_service.clone()),
+        web::Data::new(price_refresh_service.clone()),
+        web::Data::new(supervisor.clone()),
+        &config.health,
+    );
+
+    let metrics_service = MetricsService::new(
+        web::Data::new(pool.clone()),
+        web::Data::new(cmc_service.clone()),
+        web::Data::new(redis_service.clone()),
+        web::Data::new(portfolio_service.clone()),
+        web::Data::new(price_refresh_service.clone()),
+    );
+
+    // Run periodic price updates under supervision, one loop per refresh tier and only on the
+    // elected instance so replicas do not duplicate CoinMarketCap calls
+    for tier in [RefreshTier::Held, RefreshTier::Tracked] {
+        let price_refresh_service = price_refresh_service.clone();
+        supervisor.spawn_exclusive(&format!("price_refresh_{}", tier), move |cancel| {
+            let price_refresh_service = price_refresh_service.clone();
+            async move { price_refresh_service.run(tier, cancel).await }
+        });
+    }
+
+    // Forward live updates from the event log to the streaming clients of this instance
+    {
+        let stream_service = stream_service.clone();
+        supervisor.spawn("stream_events", move |cancel| {
+            let stream_service = stream_service.clone();
+            async move { stream_service.run(cancel).await }
+        });
+    }
+
+    // Send queued webhook deliveries from one instance at a time
+    {
+        let webhook_service = webhook_service.clone();
+        supervisor.spawn_exclusive("webhook_delivery", move |cancel| {
+            let webhook_service = webhook_service.clone();
+            async move { webhook_service.run(cancel).await }
+        });
+    }
+
+    // Delete expired idempotency keys from one instance at a time
+    {
+        let idempotency_service = idempotency_service.clone();
+        supervisor.spawn_exclusive("idempotency_cleanup", move |cancel| {
+            let idempotency_service = idempotency_service.clone();
+            async move { idempotency_service.run(cancel).await }
+        });
+    }
+
+    // Generate pending executions of due DCA plans from one instance at a time
+    {
+        let dca_service = dca_service.clone();
+        supervisor.spawn_exclusive("dca_scheduler", move |cancel| {
+            let dca_service = dca_service.clone();
+            async move { dca_service.run(cancel).await }
+        });
+    }
+
+    // Configure and start the HTTP server; signals are handled below to stop background tasks first
+    let open_streams = stream_service.clone();
+    let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(pool.clone()))
             .app_data(web::Data::new(cmc_service.clone()))
@@ -143,6 +412,17 @@ async fn main() -> Result<()> {
             .app_data(web::Data::new(transaction_service.clone()))
             .app_data(web::Data::new(portfolio_service.clone()))
             .app_data(web::Data::new(snapshot_service.clone()))
+            .app_data(web::Data::new(watchlist_service.clone()))
+            .app_data(web::Data::new(tag_service.clone()))
+            .app_data(web::Data::new(dca_service.clone()))
+            .app_data(web::Data::new(backtest_service.clone()))
+            .app_data(web::Data::new(scenario_service.clone()))
+            .app_data(web::Data::new(price_refresh_service.clone()))
+            .app_data(web::Data::new(health_service.clone()))
+            .app_data(web::Data::new(metrics_service.clone()))
+            .app_data(web::Data::new(stream_service.clone()))
+            .app_data(web::Data::new(webhook_service.clone()))
+            .app_data(web::Data::new(idempotency_service.clone()))
             .app_data(
                 actix_web_validator::JsonConfig::default()
                     .error_handler(|err, _req| AppError::from(err).into()),
@@ -151,18 +431,110 @@ async fn main() -> Result<()> {
                 actix_web_validator::QueryConfig::default()
                     .error_handler(|err, _req| AppError::from(err).into()),
             )
+            .app_data(web::PathConfig::default().error_handler(|err, _req| {
+                AppError::validation(format!("Invalid path parameter: {}", err)).into()
+            }))
+            .wrap(actix_web::middleware::from_fn(
+                logging::request_id_middleware,
+            ))
+            // Record request count and latency per matched route
+            .wrap_fn({
+                let metrics_service = metrics_service.clone();
+                move |req, srv| {
+                    let metrics_service = metrics_service.clone();
+                    let method = req.method().to_string();
+                    let started = Instant::now();
+                    let response = srv.call(req);
+                    async move {
+                        let response = response.await?;
+                        let route = response
+                            .request()
+                            .match_pattern()
+                            .unwrap_or_else(|| "unmatched".to_string());
+                        metrics_service.observe_request(
+                            &method,
+                            &route,
+                            response.status().as_u16(),
+                            started.elapsed(),
+                        );
+                        Ok(response)
+                    }
+                }
+            })
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}")
                     .url("/api-docs/openapi.json", ApiDoc::openapi()),
             )
-            .configure(asset::configure)
-            .configure(wallet::configure)
-            .configure(transaction::configure)
-            .configure(snapshots::configure)
+            .configure(health::configure)
+            .configure(metrics::configure)
+            .service(
+                web::scope("/api/v1")
+                    .wrap(actix_web::middleware::from_fn(
+                        services::idempotency::idempotency_middleware,
+                    ))
+                    .configure(asset::configure)
+                    .configure(wallet::configure)
+                    .configure(transaction::configure)
+                    .configure(snapshots::configure)
+                    .configure(watchlist::configure)
+                    .configure(tag::configure)
+                    .configure(dca::configure)
+                    .configure(analytics::configure)
+                    .configure(stream::configure)
+                    .configure(webhook::configure),
+            )
+            .default_service(web::to(|| async {
+                Err::<HttpResponse, _>(AppError::not_found("Route not found"))
+            }))
     })
     .bind(("0.0.0.0", port))?
-    .run()
-    .await?;
+    .disable_signals()
+    .run();
 
+    // On SIGINT/SIGTERM, cancel background tasks and wait for in-flight writes, then let
+    // in-flight HTTP requests finish before the server stops
+    let server_handle = server.handle();
+    let shutdown_timeout = Duration::from_secs(config.app.shutdown_timeout_secs);
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        log::info!("Shutdown requested, stopping background tasks");
+        supervisor.shutdown(shutdown_timeout).await;
+        // Streams never complete on their own, so they are ended before waiting for requests
+        open_streams.close();
+        log::info!("Stopping HTTP server");
+        server_handle.stop(true).await;
+    });
+
+    server.await?;
+    log::info!("Shutdown complete");
     Ok(())
 }
+
+// Resolves when the process receives SIGINT or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            log::error!("Failed to listen for SIGINT: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut signal) => {
+                signal.recv().await;
+            }
+            Err(e) => {
+                log::error!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+}
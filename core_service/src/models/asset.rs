@@ -1,3 +1,4 @@
+use crate::models::pagination::{SortFields, SortOrder};
 use serde::{Deserialize, Serialize};
 use sqlx::types::time::PrimitiveDateTime;
 use sqlx::FromRow;
@@ -12,9 +13,63 @@ pub struct AssetDb {
     pub cmc_id: i32,
     pub decimals: Option<i32>,
     pub rank: Option<i32>,
+    pub slug: Option<String>,
+    pub tags: Vec<String>,
+    pub platform_name: Option<String>,
+    pub platform_symbol: Option<String>,
+    pub token_address: Option<String>,
+    pub circulating_supply: Option<f64>,
+    pub total_supply: Option<f64>,
+    pub max_supply: Option<f64>,
+    pub date_added: Option<PrimitiveDateTime>,
+    pub metadata_updated_at: Option<PrimitiveDateTime>,
     pub created_at: PrimitiveDateTime,
 }
 
+// Represents an asset sharing its symbol with other assets
+#[derive(Debug, Deserialize, Serialize, FromRow)]
+pub struct SymbolCollisionDb {
+    pub symbol: String,
+    pub asset_id: i32,
+    pub cmc_id: i32,
+    pub name: String,
+    pub rank: Option<i32>,
+    pub held_amount: f64,
+}
+
+// Query parameters for GET /assets
+#[derive(Debug, Deserialize, Validate)]
+pub struct AssetQueryParams {
+    #[validate(length(min = 1, max = 50, message = "Search must be 1-50 characters long"))]
+    pub search: Option<String>,
+    #[validate(range(min = 0, message = "Minimum rank must be non-negative"))]
+    pub min_rank: Option<i32>,
+    #[validate(range(min = 0, message = "Maximum rank must be non-negative"))]
+    pub max_rank: Option<i32>,
+    pub held: Option<bool>,
+    pub tracked: Option<bool>,
+}
+
+// Sort fields of GET /assets
+#[derive(Debug)]
+pub struct AssetSort;
+
+impl SortFields for AssetSort {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[
+        ("rank", "a.rank"),
+        ("symbol", "a.symbol"),
+        ("created_at", "a.created_at"),
+    ];
+    const DEFAULT_ORDER: SortOrder = SortOrder::Asc;
+    const TIEBREAKER: &'static str = "a.id";
+}
+
+// Query parameters for GET /assets/collisions
+#[derive(Debug, Deserialize, Validate)]
+pub struct CollisionQueryParams {
+    pub held: Option<bool>,
+}
+
 // Query parameters for GET /assets/prices
 #[derive(Debug, Deserialize, Validate)]
 pub struct PriceQueryParams {
@@ -28,3 +83,13 @@ pub struct HistoryQueryParams {
     pub start_date: String,
     pub end_date: Option<String>,
 }
+
+// Sort fields of GET /assets/prices/history
+#[derive(Debug)]
+pub struct PriceHistorySort;
+
+impl SortFields for PriceHistorySort {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[("timestamp", "ap.timestamp")];
+    const DEFAULT_ORDER: SortOrder = SortOrder::Asc;
+    const TIEBREAKER: &'static str = "ap.id";
+}
@@ -0,0 +1,68 @@
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::FromRow;
+use time::Duration;
+
+// Strategies the backtester can replay
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacktestStrategy {
+    Dca,       // Spends the capital in equal parts at a fixed frequency
+    LumpSum,   // Invests all capital at the start and holds
+    Rebalance, // Invests all capital at the start and restores the weights when they drift
+}
+
+impl BacktestStrategy {
+    pub const ALL: [Self; 3] = [Self::Dca, Self::LumpSum, Self::Rebalance];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Dca => "dca",
+            Self::LumpSum => "lump_sum",
+            Self::Rebalance => "rebalance",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|strategy| strategy.as_str() == value)
+    }
+}
+
+// Spacing of the points at which a backtest is evaluated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BacktestInterval {
+    Hourly,
+    Daily,
+}
+
+impl BacktestInterval {
+    pub const ALL: [Self; 2] = [Self::Hourly, Self::Daily];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Hourly => "hourly",
+            Self::Daily => "daily",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|interval| interval.as_str() == value)
+    }
+
+    pub fn step(&self) -> Duration {
+        match self {
+            Self::Hourly => Duration::HOUR,
+            Self::Daily => Duration::DAY,
+        }
+    }
+}
+
+// Latest stored price of an asset at a point of a backtest, None before its first price
+#[derive(Debug, FromRow)]
+pub struct PriceGridDb {
+    pub at: PrimitiveDateTime,
+    pub asset_id: i32,
+    pub price_usd: Option<f64>,
+}
@@ -19,6 +19,22 @@ pub struct CmcStatus {
     pub total_count: Option<i32>,
 }
 
+// Status-only view of any CoinMarketCap response, tolerant of missing fields in error bodies
+#[derive(Debug, Deserialize)]
+pub struct CmcStatusEnvelope {
+    pub status: CmcStatusSummary,
+}
+
+// Subset of CmcStatus needed for error handling and credit accounting
+#[derive(Debug, Deserialize)]
+pub struct CmcStatusSummary {
+    #[serde(default)]
+    pub error_code: i32,
+    pub error_message: Option<String>,
+    #[serde(default)]
+    pub credit_count: i32,
+}
+
 // Listing data for a cryptocurrency from CoinMarketCap
 #[derive(Debug, Deserialize, Serialize)]
 pub struct CmcListing {
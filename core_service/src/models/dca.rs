@@ -0,0 +1,193 @@
+use crate::models::pagination::{SortFields, SortOrder};
+use serde::Deserialize;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::FromRow;
+use time::{Date, Duration, Month};
+use validator::Validate;
+
+// Statuses of a DCA execution
+pub const EXECUTION_PENDING: &str = "pending";
+pub const EXECUTION_CONFIRMED: &str = "confirmed";
+pub const EXECUTION_SKIPPED: &str = "skipped";
+pub const EXECUTION_STATUSES: [&str; 3] =
+    [EXECUTION_PENDING, EXECUTION_CONFIRMED, EXECUTION_SKIPPED];
+
+// How often a DCA plan buys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DcaFrequency {
+    Daily,
+    Weekly,
+    Biweekly,
+    Monthly,
+}
+
+impl DcaFrequency {
+    pub const ALL: [Self; 4] = [Self::Daily, Self::Weekly, Self::Biweekly, Self::Monthly];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Daily => "daily",
+            Self::Weekly => "weekly",
+            Self::Biweekly => "biweekly",
+            Self::Monthly => "monthly",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|frequency| frequency.as_str() == value)
+    }
+
+    // Time of execution n of a plan starting at start, where execution 0 is the start itself.
+    // Monthly executions keep the day of month of the start, or the last day of shorter months.
+    pub fn occurrence(&self, start: PrimitiveDateTime, n: i64) -> Option<PrimitiveDateTime> {
+        match self.period_days() {
+            Some(days) => start.checked_add(Duration::days(days.checked_mul(n)?)),
+            None => add_months(start, n),
+        }
+    }
+
+    // First execution of a plan starting at start that is at or after t
+    pub fn first_from(
+        &self,
+        start: PrimitiveDateTime,
+        t: PrimitiveDateTime,
+    ) -> Option<PrimitiveDateTime> {
+        self.occurrence(start, self.first_index_from(start, t)?)
+    }
+
+    // First execution of a plan starting at start that is strictly after t
+    pub fn first_after(
+        &self,
+        start: PrimitiveDateTime,
+        t: PrimitiveDateTime,
+    ) -> Option<PrimitiveDateTime> {
+        let n = self.first_index_from(start, t)?;
+        match self.occurrence(start, n)? {
+            occurrence if occurrence > t => Some(occurrence),
+            _ => self.occurrence(start, n + 1),
+        }
+    }
+
+    fn period_days(&self) -> Option<i64> {
+        match self {
+            Self::Daily => Some(1),
+            Self::Weekly => Some(7),
+            Self::Biweekly => Some(14),
+            Self::Monthly => None,
+        }
+    }
+
+    // Index of the first execution at or after t
+    fn first_index_from(&self, start: PrimitiveDateTime, t: PrimitiveDateTime) -> Option<i64> {
+        if t <= start {
+            return Some(0);
+        }
+        match self.period_days() {
+            Some(days) => {
+                let elapsed = (t - start).whole_nanoseconds();
+                let period = Duration::days(days).whole_nanoseconds();
+                i64::try_from((elapsed + period - 1) / period).ok()
+            }
+            None => {
+                let months =
+                    (t.year() - start.year()) as i64 * 12 + t.month() as i64 - start.month() as i64;
+                if self.occurrence(start, months)? < t {
+                    Some(months + 1)
+                } else {
+                    Some(months)
+                }
+            }
+        }
+    }
+}
+
+// Adds months to a date and time, clamping the day to the length of the resulting month
+fn add_months(dt: PrimitiveDateTime, months: i64) -> Option<PrimitiveDateTime> {
+    let total = dt.year() as i64 * 12 + dt.month() as i64 - 1 + months;
+    let year = i32::try_from(total.div_euclid(12)).ok()?;
+    let month = Month::try_from(total.rem_euclid(12) as u8 + 1).ok()?;
+    let day = dt.day().min(month.length(year));
+    let date = Date::from_calendar_date(year, month, day).ok()?;
+    Some(PrimitiveDateTime::new(date, dt.time()))
+}
+
+// Represents a DCA plan joined with its asset, wallet and the latest stored price of the asset
+#[derive(Debug, FromRow)]
+pub struct DcaPlanDb {
+    pub id: i32,
+    pub asset_id: i32,
+    pub asset: String,
+    pub wallet_id: i32,
+    pub wallet: String,
+    pub amount_usd: f64,
+    pub frequency: String,
+    pub start_date: PrimitiveDateTime,
+    pub end_date: Option<PrimitiveDateTime>,
+    pub next_execution_at: Option<PrimitiveDateTime>,
+    pub active: bool,
+    pub notes: Option<String>,
+    pub pending_executions: i64,
+    pub latest_price_usd: Option<f64>,
+    pub created_at: PrimitiveDateTime,
+    pub updated_at: PrimitiveDateTime,
+}
+
+// Represents a DCA plan whose next execution is due
+#[derive(Debug, FromRow)]
+pub struct DueDcaPlanDb {
+    pub id: i32,
+    pub frequency: String,
+    pub start_date: PrimitiveDateTime,
+    pub end_date: Option<PrimitiveDateTime>,
+    pub next_execution_at: PrimitiveDateTime,
+}
+
+// Represents a DCA execution joined with the asset and wallet of its plan
+#[derive(Debug, FromRow)]
+pub struct DcaExecutionDb {
+    pub id: i32,
+    pub plan_id: i32,
+    pub asset_id: i32,
+    pub asset: String,
+    pub wallet_id: i32,
+    pub wallet: String,
+    pub scheduled_at: PrimitiveDateTime,
+    pub amount_usd: f64,
+    pub price: Option<f64>,
+    pub amount: Option<f64>,
+    pub status: String,
+    pub transaction_id: Option<i32>,
+    pub created_at: PrimitiveDateTime,
+    pub resolved_at: Option<PrimitiveDateTime>,
+}
+
+// Query parameters for GET /dca/executions
+#[derive(Debug, Deserialize, Validate)]
+pub struct ExecutionQueryParams {
+    #[validate(range(min = 1, message = "Plan ID must be positive"))]
+    pub plan_id: Option<i32>,
+    pub status: Option<String>,
+}
+
+// Sort fields of GET /dca/executions
+#[derive(Debug)]
+pub struct ExecutionSort;
+
+impl SortFields for ExecutionSort {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[("scheduled_at", "e.scheduled_at")];
+    const DEFAULT_ORDER: SortOrder = SortOrder::Desc;
+    const TIEBREAKER: &'static str = "e.id";
+}
+
+// Query parameters for GET /dca/upcoming
+#[derive(Debug, Deserialize, Validate)]
+pub struct UpcomingParams {
+    #[validate(range(min = 1, message = "Plan ID must be positive"))]
+    pub plan_id: Option<i32>,
+    #[validate(range(min = 1, max = 366, message = "Days must be between 1 and 366"))]
+    pub days: Option<i64>, // Default: 30
+    #[validate(range(min = 1, max = 1000, message = "Limit must be between 1 and 1000"))]
+    pub limit: Option<usize>, // Default: 100
+}
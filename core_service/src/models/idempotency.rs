@@ -0,0 +1,21 @@
+use sqlx::FromRow;
+use uuid::Uuid;
+
+// Represents an idempotency key record fetched from the database
+#[derive(Debug, FromRow)]
+pub struct IdempotencyKeyDb {
+    pub request_hash: String,
+    pub response_status: Option<i32>, // None while the first request is being processed
+    pub response_content_type: Option<String>,
+    pub response_body: Option<Vec<u8>>,
+}
+
+// Identifies a request claiming an idempotency key
+#[derive(Debug)]
+pub struct IdempotencyClaim<'a> {
+    pub key: &'a str,
+    pub token: Uuid, // Random per request, so only it can complete or release the key
+    pub request_hash: &'a str,
+    pub method: &'a str,
+    pub path: &'a str,
+}
@@ -1,5 +1,14 @@
 pub mod asset;
+pub mod backtest;
 pub mod cmc;
+pub mod dca;
+pub mod idempotency;
+pub mod pagination;
+pub mod portfolio;
 pub mod snapshot;
+pub mod stream;
+pub mod tag;
 pub mod transaction;
 pub mod wallet;
+pub mod watchlist;
+pub mod webhook;
@@ -0,0 +1,247 @@
+use crate::error::AppError;
+use actix_web::{dev::Payload, web, FromRequest, HttpRequest};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Deserialize;
+use sqlx::{Postgres, QueryBuilder};
+use std::future::{ready, Ready};
+use std::marker::PhantomData;
+
+// Default and maximum page sizes of list endpoints
+pub const DEFAULT_PAGE_LIMIT: i64 = 50;
+pub const MAX_PAGE_LIMIT: i64 = 500;
+
+// Direction of a sorted list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortOrder {
+    Asc,
+    Desc,
+}
+
+impl SortOrder {
+    fn as_sql(self) -> &'static str {
+        match self {
+            SortOrder::Asc => "ASC",
+            SortOrder::Desc => "DESC",
+        }
+    }
+}
+
+// Sortable fields of a list endpoint
+pub trait SortFields {
+    // Accepted `sort` values with the SQL expressions they order by; the first one is the default
+    const FIELDS: &'static [(&'static str, &'static str)];
+    // Order used when `order` is omitted
+    const DEFAULT_ORDER: SortOrder;
+    // Unique expression ordered by last, so rows with equal sort values never change pages
+    const TIEBREAKER: &'static str;
+}
+
+// Raw pagination and sort query parameters shared by list endpoints
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    limit: Option<i64>,
+    cursor: Option<String>,
+    sort: Option<String>,
+    order: Option<String>,
+}
+
+// Validated pagination and sort parameters of a list endpoint, extracted from the query string
+// next to the endpoint's own filters
+#[derive(Debug)]
+pub struct PageParams<S> {
+    pub limit: i64,
+    pub offset: i64,
+    sort: Option<&'static str>, // SQL expression of the requested sort field
+    order: SortOrder,
+    fields: PhantomData<S>,
+}
+
+impl<S: SortFields> PageParams<S> {
+    // Parses and validates the query string of a list request
+    fn from_query(query_string: &str) -> Result<Self, AppError> {
+        let query = web::Query::<PageQuery>::from_query(query_string)
+            .map_err(|e| AppError::validation(format!("Invalid query parameters: {}", e)))?
+            .into_inner();
+
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_LIMIT);
+        if !(1..=MAX_PAGE_LIMIT).contains(&limit) {
+            return Err(AppError::invalid_field(
+                "limit",
+                format!("Limit must be between 1 and {}", MAX_PAGE_LIMIT),
+            ));
+        }
+
+        let offset = match &query.cursor {
+            Some(cursor) => decode_cursor(cursor)
+                .ok_or_else(|| AppError::invalid_field("cursor", "Invalid cursor"))?,
+            None => 0,
+        };
+
+        let sort = match &query.sort {
+            Some(sort) => {
+                let expression = S::FIELDS
+                    .iter()
+                    .find(|(field, _)| field == sort)
+                    .map(|(_, expression)| *expression)
+                    .ok_or_else(|| {
+                        let fields = S::FIELDS
+                            .iter()
+                            .map(|(field, _)| format!("'{}'", field))
+                            .collect::<Vec<_>>();
+                        AppError::invalid_field(
+                            "sort",
+                            format!("Sort must be one of {}", fields.join(", ")),
+                        )
+                    })?;
+                Some(expression)
+            }
+            None => None,
+        };
+
+        let order = match query.order.as_deref() {
+            Some("asc") => SortOrder::Asc,
+            Some("desc") => SortOrder::Desc,
+            Some(_) => {
+                return Err(AppError::invalid_field(
+                    "order",
+                    "Order must be either 'asc' or 'desc'",
+                ))
+            }
+            None => S::DEFAULT_ORDER,
+        };
+
+        Ok(Self {
+            limit,
+            offset,
+            sort,
+            order,
+            fields: PhantomData,
+        })
+    }
+
+    // Whether the client chose the sort field rather than relying on the endpoint's default
+    pub fn is_sorted(&self) -> bool {
+        self.sort.is_some()
+    }
+
+    // Appends the sort terms of the page (without ORDER BY), ending with the tiebreaker
+    pub fn push_sort(&self, query_builder: &mut QueryBuilder<'_, Postgres>) {
+        let expression = self.sort.unwrap_or(S::FIELDS[0].1);
+        let direction = self.order.as_sql();
+        query_builder.push(format!(
+            "{} {} NULLS LAST, {} {}",
+            expression,
+            direction,
+            S::TIEBREAKER,
+            direction
+        ));
+    }
+
+    // Appends LIMIT and OFFSET of the page
+    pub fn push_limit(&self, query_builder: &mut QueryBuilder<'_, Postgres>) {
+        query_builder.push(" LIMIT ");
+        query_builder.push_bind(self.limit);
+        query_builder.push(" OFFSET ");
+        query_builder.push_bind(self.offset);
+    }
+
+    // Returns the cursor of the page following one of `count` items, if any items remain
+    pub fn next_cursor(&self, count: usize, total: i64) -> Option<String> {
+        let next_offset = self.offset + count as i64;
+        (count > 0 && next_offset < total).then(|| encode_cursor(next_offset))
+    }
+}
+
+impl<S: SortFields> FromRequest for PageParams<S> {
+    type Error = AppError;
+    type Future = Ready<Result<Self, AppError>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        ready(Self::from_query(req.query_string()))
+    }
+}
+
+// Cursors are opaque to clients; they currently carry the offset of the next page
+fn encode_cursor(offset: i64) -> String {
+    URL_SAFE_NO_PAD.encode(format!("o:{}", offset))
+}
+
+fn decode_cursor(cursor: &str) -> Option<i64> {
+    let decoded = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let offset = std::str::from_utf8(&decoded)
+        .ok()?
+        .strip_prefix("o:")?
+        .parse::<i64>()
+        .ok()?;
+    (offset >= 0).then_some(offset)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug)]
+    struct TestSort;
+
+    impl SortFields for TestSort {
+        const FIELDS: &'static [(&'static str, &'static str)] = &[("id", "id")];
+        const DEFAULT_ORDER: SortOrder = SortOrder::Desc;
+        const TIEBREAKER: &'static str = "id";
+    }
+
+    #[test]
+    fn cursor_round_trips_the_offset() {
+        for offset in [0, 1, 50, 12_345, i64::MAX] {
+            assert_eq!(decode_cursor(&encode_cursor(offset)), Some(offset));
+        }
+    }
+
+    #[test]
+    fn cursor_is_url_safe() {
+        let cursor = encode_cursor(i64::MAX);
+        assert!(cursor
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+    }
+
+    #[test]
+    fn malformed_cursors_are_rejected() {
+        let encoded = |raw: &[u8]| URL_SAFE_NO_PAD.encode(raw);
+        for cursor in [
+            String::new(),
+            "not a cursor!".to_string(),
+            format!("{}==", encode_cursor(10)), // Padding is not used
+            encoded(b"50"),                     // Missing prefix
+            encoded(b"x:50"),
+            encoded(b"o:"),
+            encoded(b"o:abc"),
+            encoded(b"o:1.5"),
+            encoded(b"o:-1"), // Negative offset
+            encoded(b"o:99999999999999999999"),
+            encoded(&[b'o', b':', 0xff, 0xfe]), // Not UTF-8
+        ] {
+            assert_eq!(decode_cursor(&cursor), None, "cursor {:?}", cursor);
+        }
+    }
+
+    #[test]
+    fn next_cursor_points_after_the_page() {
+        let page = PageParams::<TestSort>::from_query("limit=10").unwrap();
+        let cursor = page.next_cursor(10, 25).unwrap();
+        assert_eq!(decode_cursor(&cursor), Some(10));
+
+        let page =
+            PageParams::<TestSort>::from_query(&format!("limit=10&cursor={}", cursor)).unwrap();
+        assert_eq!(page.offset, 10);
+        assert_eq!(decode_cursor(&page.next_cursor(10, 25).unwrap()), Some(20));
+        assert_eq!(page.next_cursor(10, 20), None);
+        assert_eq!(page.next_cursor(0, 25), None);
+    }
+
+    #[test]
+    fn invalid_cursor_is_a_field_error() {
+        let error = PageParams::<TestSort>::from_query("cursor=bogus").unwrap_err();
+        assert!(error.to_string().contains("cursor"), "{}", error);
+    }
+}
@@ -0,0 +1,11 @@
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+// Represents the net holding of a single asset aggregated from transactions
+#[derive(Debug, Deserialize, Serialize, FromRow, Clone)]
+pub struct HoldingDb {
+    pub asset_id: i32,
+    pub symbol: String,
+    pub cmc_id: i32,
+    pub amount: f64,
+}
@@ -1,3 +1,4 @@
+use crate::models::pagination::{SortFields, SortOrder};
 use serde::{Deserialize, Serialize};
 use sqlx::types::time::PrimitiveDateTime;
 use sqlx::types::Json;
@@ -14,7 +15,19 @@ pub struct SnapshotDb {
 // Represents an asset in a snapshot stored in the database
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct SnapshotAssetDb {
+    #[serde(default)]
+    pub asset_id: Option<i32>, // Missing in legacy snapshots with ambiguous symbols
     pub symbol: String,
     pub amount: f64, // Positive or negative difference
     pub cmc_id: i32,
 }
+
+// Sort fields of GET /snapshots
+#[derive(Debug)]
+pub struct SnapshotSort;
+
+impl SortFields for SnapshotSort {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[("created_at", "created_at")];
+    const DEFAULT_ORDER: SortOrder = SortOrder::Desc;
+    const TIEBREAKER: &'static str = "id";
+}
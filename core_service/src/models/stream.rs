@@ -0,0 +1,39 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::Json;
+use sqlx::FromRow;
+use validator::Validate;
+
+// Kind of the event appended when new prices are saved
+pub const PRICES_EVENT: &str = "prices";
+// Kind of the event appended when transactions change holdings
+pub const HOLDINGS_EVENT: &str = "holdings";
+
+// Represents a stream event record fetched from the database
+#[derive(Debug, FromRow)]
+pub struct StreamEventDb {
+    pub id: i64,
+    pub kind: String,
+    pub payload: Json<serde_json::Value>, // Stored as JSONB in the database
+}
+
+// A saved price in the payload of a prices event
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct PriceEventDb {
+    pub asset_id: i32,
+    pub price_usd: f64,
+    pub timestamp: String,
+}
+
+// Payload of a holdings event
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct HoldingsEventDb {
+    pub asset_ids: Vec<i32>, // Assets whose holdings changed
+}
+
+// Query parameters for GET /stream
+#[derive(Debug, Deserialize, Validate)]
+pub struct StreamParams {
+    pub asset_ids: Option<String>,
+    pub portfolio: Option<bool>,
+    pub last_event_id: Option<i64>, // Alternative to the Last-Event-ID header
+}
@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::FromRow;
+use validator::Validate;
+
+// Represents a tag record with the number of tagged transactions fetched from the database
+#[derive(Debug, Deserialize, Serialize, FromRow)]
+pub struct TagDb {
+    pub id: i32,
+    pub name: String,
+    pub description: Option<String>,
+    pub transaction_count: i64,
+    pub created_at: PrimitiveDateTime,
+}
+
+// Trade totals of one asset within one tag, with the latest stored price of the asset. Rows of
+// untagged transactions have no tag.
+#[derive(Debug, FromRow)]
+pub struct TagAssetTotalsDb {
+    pub tag_id: Option<i32>,
+    pub tag: Option<String>,
+    pub asset_id: i32,
+    pub symbol: String,
+    pub bought: f64,   // Total amount bought
+    pub cost: f64,     // Total USD paid for the amount bought, including fees
+    pub sold: f64,     // Total amount sold
+    pub proceeds: f64, // Total USD received for the amount sold, net of fees
+    pub price_usd: Option<f64>,
+}
+
+// Query parameters for GET /tags/breakdown
+#[derive(Debug, Deserialize, Validate)]
+pub struct BreakdownParams {
+    pub tags: Option<String>, // Comma-separated tag names; all tags when omitted
+    pub include_untagged: Option<bool>,
+}
+
+// Parses a comma-separated list of tag names into lowercased names, ignoring empty entries
+pub fn parse_tag_names(names: &str) -> Vec<String> {
+    names
+        .split(',')
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
@@ -1,7 +1,10 @@
+use crate::models::pagination::{SortFields, SortOrder};
+use crate::models::tag::parse_tag_names;
 use chrono::{DateTime, NaiveDateTime};
 use serde::{Deserialize, Serialize};
 use sqlx::types::time::PrimitiveDateTime;
 use sqlx::FromRow;
+use std::collections::{HashMap, HashSet};
 use utoipa::ToSchema;
 use validator::{Validate, ValidationError};
 
@@ -9,32 +12,114 @@ use validator::{Validate, ValidationError};
 #[derive(Debug, FromRow, Deserialize, Serialize)]
 pub struct TransactionDb {
     pub id: i32,
+    pub asset_id: i32,
     pub asset: String,
+    pub wallet_id: i32,
     pub wallet: String,
     pub amount: f64,
     pub price: f64,
     pub transaction_type: String,
     pub fee: Option<f64>,
     pub notes: Option<String>,
+    pub tags: Vec<String>, // Names of the tags of the transaction, sorted
     pub created_at: PrimitiveDateTime,
 }
 
+// Assets and wallets referenced by a batch of transactions that exist, and the net holdings
+// of those wallets before the batch, keyed by (wallet_id, asset_id)
+#[derive(Debug)]
+pub struct BatchLookupDb {
+    pub asset_ids: HashSet<i32>,
+    pub wallet_ids: HashSet<i32>,
+    pub holdings: HashMap<(i32, i32), f64>,
+}
+
 // Represents query parameters for filtering transactions
 #[derive(Debug, Deserialize, ToSchema, Validate)]
 pub struct FilterParams {
     #[validate(range(min = 1, message = "Asset ID must be positive"))]
     pub asset_id: Option<i32>,
+    #[validate(custom(
+        function = "validate_id_list",
+        message = "Asset IDs must be comma-separated positive integers"
+    ))]
+    pub asset_ids: Option<String>, // Comma-separated, combined with asset_id
     #[validate(range(min = 1, message = "Wallet ID must be positive"))]
     pub wallet_id: Option<i32>,
+    #[validate(custom(
+        function = "validate_id_list",
+        message = "Wallet IDs must be comma-separated positive integers"
+    ))]
+    pub wallet_ids: Option<String>, // Comma-separated, combined with wallet_id
     #[validate(custom(
         function = "validate_date",
         message = "Invalid start_date format, expected ISO 8601 (e.g., '2024-01-01T00:00:00' or '2024-01-01T00:00:00Z')"
     ))]
     pub start_date: Option<String>,
-    #[validate(range(min = 1, message = "Limit must be positive"))]
-    pub limit: Option<i64>,
-    #[validate(range(min = 0, message = "Offset must be non-negative"))]
-    pub offset: Option<i64>,
+    #[validate(custom(
+        function = "validate_date",
+        message = "Invalid end_date format, expected ISO 8601 (e.g., '2024-01-01T00:00:00' or '2024-01-01T00:00:00Z')"
+    ))]
+    pub end_date: Option<String>,
+    #[validate(custom(
+        function = "validate_transaction_type",
+        message = "Transaction type must be either 'BUY' or 'SELL'"
+    ))]
+    pub transaction_type: Option<String>,
+    #[validate(range(min = 0.0, message = "Minimum amount must be non-negative"))]
+    pub min_amount: Option<f64>,
+    #[validate(range(min = 0.0, message = "Maximum amount must be non-negative"))]
+    pub max_amount: Option<f64>,
+    #[validate(range(min = 0.0, message = "Minimum price must be non-negative"))]
+    pub min_price: Option<f64>,
+    #[validate(range(min = 0.0, message = "Maximum price must be non-negative"))]
+    pub max_price: Option<f64>,
+    #[validate(length(
+        min = 1,
+        max = 100,
+        message = "Notes search must be 1 to 100 characters"
+    ))]
+    pub notes: Option<String>, // Case-insensitive substring of the notes
+    #[validate(length(min = 1, max = 500, message = "Tags must be 1 to 500 characters"))]
+    pub tags: Option<String>, // Comma-separated tag names; transactions with any of them match
+}
+
+impl FilterParams {
+    // Asset IDs to filter by, from asset_id and asset_ids
+    pub fn asset_ids(&self) -> Vec<i32> {
+        combine_ids(self.asset_id, self.asset_ids.as_deref())
+    }
+
+    // Wallet IDs to filter by, from wallet_id and wallet_ids
+    pub fn wallet_ids(&self) -> Vec<i32> {
+        combine_ids(self.wallet_id, self.wallet_ids.as_deref())
+    }
+
+    // Lowercased tag names to filter by
+    pub fn tag_names(&self) -> Vec<String> {
+        self.tags
+            .as_deref()
+            .map(parse_tag_names)
+            .unwrap_or_default()
+    }
+}
+
+// Sort fields of GET /transactions
+#[derive(Debug)]
+pub struct TransactionSort;
+
+impl SortFields for TransactionSort {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[
+        ("created_at", "t.created_at"),
+        ("amount", "t.amount"),
+        ("price", "t.price"),
+        ("fee", "t.fee"),
+        ("transaction_type", "t.type"),
+        ("asset", "a.symbol"),
+        ("wallet", "w.name"),
+    ];
+    const DEFAULT_ORDER: SortOrder = SortOrder::Desc;
+    const TIEBREAKER: &'static str = "t.id";
 }
 
 // Custom validation function for ISO 8601 date
@@ -46,3 +131,39 @@ fn validate_date(date: &str) -> Result<(), ValidationError> {
         .map(|_| ())
         .map_err(|_| ValidationError::new("start_date"))
 }
+
+// Custom validation function for comma-separated lists of IDs
+fn validate_id_list(ids: &str) -> Result<(), ValidationError> {
+    if parse_ids(ids).is_some() {
+        Ok(())
+    } else {
+        Err(ValidationError::new("ids"))
+    }
+}
+
+// Custom validation function for transaction_type
+pub fn validate_transaction_type(transaction_type: &str) -> Result<(), ValidationError> {
+    if transaction_type == "BUY" || transaction_type == "SELL" {
+        Ok(())
+    } else {
+        Err(ValidationError::new("transaction_type"))
+    }
+}
+
+// Parses a comma-separated list of positive IDs, ignoring empty entries
+fn parse_ids(ids: &str) -> Option<Vec<i32>> {
+    ids.split(',')
+        .map(str::trim)
+        .filter(|id| !id.is_empty())
+        .map(|id| id.parse::<i32>().ok().filter(|id| *id > 0))
+        .collect()
+}
+
+// Merges a single ID and a list of IDs into one deduplicated list
+fn combine_ids(id: Option<i32>, ids: Option<&str>) -> Vec<i32> {
+    let mut combined = ids.and_then(parse_ids).unwrap_or_default();
+    combined.extend(id);
+    combined.sort_unstable();
+    combined.dedup();
+    combined
+}
@@ -1,3 +1,4 @@
+use crate::models::pagination::{SortFields, SortOrder};
 use serde::{Deserialize, Serialize};
 use sqlx::types::time::PrimitiveDateTime;
 use sqlx::FromRow;
@@ -11,3 +12,14 @@ pub struct WalletDb {
     pub address: Option<String>,
     pub created_at: PrimitiveDateTime,
 }
+
+// Sort fields of GET /wallets
+#[derive(Debug)]
+pub struct WalletSort;
+
+impl SortFields for WalletSort {
+    const FIELDS: &'static [(&'static str, &'static str)] =
+        &[("created_at", "created_at"), ("name", "name")];
+    const DEFAULT_ORDER: SortOrder = SortOrder::Asc;
+    const TIEBREAKER: &'static str = "id";
+}
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::FromRow;
+
+// Represents a watchlist record with its asset IDs fetched from the database
+#[derive(Debug, Deserialize, Serialize, FromRow)]
+pub struct WatchlistDb {
+    pub id: i32,
+    pub name: String,
+    pub asset_ids: Vec<i32>,
+    pub created_at: PrimitiveDateTime,
+}
+
+// Represents a watchlist asset joined with its latest stored quote
+#[derive(Debug, Deserialize, Serialize, FromRow)]
+pub struct WatchlistAssetDb {
+    pub asset_id: i32,
+    pub cmc_id: i32,
+    pub symbol: String,
+    pub name: String,
+    pub price_usd: Option<f64>,
+    pub percent_change_24h: Option<f64>,
+    pub percent_change_7d: Option<f64>,
+    pub timestamp: Option<PrimitiveDateTime>,
+}
@@ -0,0 +1,97 @@
+use crate::models::pagination::{SortFields, SortOrder};
+use serde::{Deserialize, Serialize};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::types::{Json, Uuid};
+use sqlx::FromRow;
+use validator::Validate;
+
+// Domain events that can be delivered to webhook subscriptions. There are no transaction.updated
+// or transaction.deleted events because the API cannot update or delete transactions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WebhookEvent {
+    TransactionCreated,
+    SnapshotCreated,
+    AssetsSynced,
+    PricesUpdated,
+}
+
+impl WebhookEvent {
+    pub const ALL: [Self; 4] = [
+        Self::TransactionCreated,
+        Self::SnapshotCreated,
+        Self::AssetsSynced,
+        Self::PricesUpdated,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::TransactionCreated => "transaction.created",
+            Self::SnapshotCreated => "snapshot.created",
+            Self::AssetsSynced => "assets.synced",
+            Self::PricesUpdated => "prices.updated",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|event| event.as_str() == value)
+    }
+}
+
+// Represents a webhook subscription record fetched from the database, without its secret
+#[derive(Debug, FromRow)]
+pub struct WebhookSubscriptionDb {
+    pub id: i32,
+    pub url: String,
+    pub event_types: Vec<String>, // Empty means every event type
+    pub description: Option<String>,
+    pub active: bool,
+    pub created_at: PrimitiveDateTime,
+    pub updated_at: PrimitiveDateTime,
+}
+
+// Represents a webhook delivery record fetched from the database
+#[derive(Debug, FromRow)]
+pub struct WebhookDeliveryDb {
+    pub id: i64,
+    pub subscription_id: i32,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Json<serde_json::Value>, // Stored as JSONB in the database
+    pub status: String,                   // "pending", "delivered" or "failed"
+    pub attempts: i32,
+    pub next_attempt_at: PrimitiveDateTime,
+    pub last_attempt_at: Option<PrimitiveDateTime>,
+    pub response_status: Option<i32>,
+    pub last_error: Option<String>,
+    pub created_at: PrimitiveDateTime,
+    pub delivered_at: Option<PrimitiveDateTime>,
+}
+
+// Represents a due delivery claimed for sending, joined with its subscription
+#[derive(Debug, FromRow)]
+pub struct DueDeliveryDb {
+    pub id: i64,
+    pub event_id: Uuid,
+    pub event_type: String,
+    pub payload: Json<serde_json::Value>,
+    pub attempts: i32,
+    pub created_at: PrimitiveDateTime,
+    pub url: String,
+    pub secret: String,
+}
+
+// Query parameters for GET /webhooks/{id}/deliveries
+#[derive(Debug, Deserialize, Serialize, Validate)]
+pub struct DeliveryQueryParams {
+    pub status: Option<String>,
+}
+
+// Sort fields of GET /webhooks/{id}/deliveries
+#[derive(Debug)]
+pub struct DeliverySort;
+
+impl SortFields for DeliverySort {
+    const FIELDS: &'static [(&'static str, &'static str)] = &[("created_at", "created_at")];
+    const DEFAULT_ORDER: SortOrder = SortOrder::Desc;
+    const TIEBREAKER: &'static str = "id";
+}
@@ -1,6 +1,9 @@
-use crate::{dto::asset::CreateAssetDto, models::asset::AssetDb};
+use crate::models::asset::{AssetDb, AssetQueryParams, AssetSort, SymbolCollisionDb};
+use crate::models::cmc::CmcListing;
+use crate::models::pagination::PageParams;
+use crate::utils::datetime::parse_rfc3339_utc;
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 
 // Repository for asset-related database operations
 pub struct AssetRepository<'a> {
@@ -26,7 +29,11 @@ impl<'a> AssetRepository<'a> {
             r#"
             INSERT INTO assets (symbol, name, cmc_id, decimals, rank)
             VALUES ($1, $2, $3, $4, $5)
-            RETURNING id, symbol, name, cmc_id, decimals, rank, created_at
+            RETURNING
+                id, symbol, name, cmc_id, decimals, rank,
+                slug, tags, platform_name, platform_symbol, token_address,
+                circulating_supply, total_supply, max_supply, date_added, metadata_updated_at,
+                created_at
             "#,
             symbol,
             name,
@@ -39,19 +46,77 @@ impl<'a> AssetRepository<'a> {
         Ok(record)
     }
 
-    // Retrieves all assets from the database
-    pub async fn get_all(&self) -> Result<Vec<AssetDb>> {
-        let assets = sqlx::query_as!(
+    // Searches, filters, sorts and paginates assets, returning the page and the total count
+    pub async fn search(
+        &self,
+        params: &AssetQueryParams,
+        page: &PageParams<AssetSort>,
+    ) -> Result<(Vec<AssetDb>, i64)> {
+        let search = params
+            .search
+            .as_ref()
+            .map(|s| s.trim().to_lowercase())
+            .filter(|s| !s.is_empty());
+        let pattern = search.as_ref().map(|s| format!("{}%", escape_like(s)));
+
+        let mut count_builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("SELECT COUNT(*) FROM assets a WHERE 1=1");
+        push_asset_filters(&mut count_builder, params, pattern.as_deref());
+        let total = count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(self.pool)
+            .await?;
+
+        let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
+            r#"
+            SELECT
+                a.id, a.symbol, a.name, a.cmc_id, a.decimals, a.rank,
+                a.slug, a.tags, a.platform_name, a.platform_symbol, a.token_address,
+                a.circulating_supply, a.total_supply, a.max_supply, a.date_added,
+                a.metadata_updated_at, a.created_at
+            FROM assets a
+            WHERE 1=1
+            "#,
+        );
+        push_asset_filters(&mut query_builder, params, pattern.as_deref());
+
+        query_builder.push(" ORDER BY ");
+        // Without an explicit sort, search results are ranked by match quality first
+        if let (false, Some(search), Some(pattern)) = (page.is_sorted(), &search, &pattern) {
+            query_builder.push("CASE WHEN LOWER(a.symbol) = ");
+            query_builder.push_bind(search.clone());
+            query_builder.push(" THEN 0 WHEN LOWER(a.symbol) LIKE ");
+            query_builder.push_bind(pattern.clone());
+            query_builder.push(" THEN 1 ELSE 2 END, ");
+        }
+        page.push_sort(&mut query_builder);
+        page.push_limit(&mut query_builder);
+
+        let assets = query_builder
+            .build_query_as::<AssetDb>()
+            .fetch_all(self.pool)
+            .await?;
+        Ok((assets, total))
+    }
+
+    // Retrieves an asset with its metadata by ID
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<AssetDb>> {
+        let asset = sqlx::query_as!(
             AssetDb,
             r#"
-            SELECT id, symbol, name, cmc_id, decimals, rank, created_at
+            SELECT
+                id, symbol, name, cmc_id, decimals, rank,
+                slug, tags, platform_name, platform_symbol, token_address,
+                circulating_supply, total_supply, max_supply, date_added, metadata_updated_at,
+                created_at
             FROM assets
-            ORDER BY id ASC
+            WHERE id = $1
             "#,
+            id
         )
-        .fetch_all(self.pool)
+        .fetch_optional(self.pool)
         .await?;
-        Ok(assets)
+        Ok(asset)
     }
 
     // Checks if an asset with the given ID exists
@@ -64,21 +129,50 @@ impl<'a> AssetRepository<'a> {
         Ok(exists)
     }
 
-    // Updates assets in the database
-    pub async fn update_assets(&self, listings: Vec<CreateAssetDto>) -> Result<usize> {
+    // Upserts assets and their CoinMarketCap metadata from listings
+    pub async fn update_assets(&self, listings: &[CmcListing]) -> Result<usize> {
         let mut updated_count = 0;
         for listing in listings {
+            let date_added = parse_rfc3339_utc(&listing.date_added)
+                .map_err(|e| log::warn!("Skipping date_added for cmc_id {}: {}", listing.id, e))
+                .ok();
+            let platform = listing.platform.as_ref();
             let result = sqlx::query!(
                 r#"
-                INSERT INTO assets (symbol, name, cmc_id, rank)
-                VALUES ($1, $2, $3, $4)
+                INSERT INTO assets (
+                    symbol, name, cmc_id, rank,
+                    slug, tags, platform_name, platform_symbol, token_address,
+                    circulating_supply, total_supply, max_supply, date_added, metadata_updated_at
+                )
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, CURRENT_TIMESTAMP)
                 ON CONFLICT (cmc_id) DO UPDATE
-                SET symbol = EXCLUDED.symbol, name = EXCLUDED.name, rank = EXCLUDED.rank
+                SET symbol = EXCLUDED.symbol,
+                    name = EXCLUDED.name,
+                    rank = EXCLUDED.rank,
+                    slug = EXCLUDED.slug,
+                    tags = EXCLUDED.tags,
+                    platform_name = EXCLUDED.platform_name,
+                    platform_symbol = EXCLUDED.platform_symbol,
+                    token_address = EXCLUDED.token_address,
+                    circulating_supply = EXCLUDED.circulating_supply,
+                    total_supply = EXCLUDED.total_supply,
+                    max_supply = EXCLUDED.max_supply,
+                    date_added = EXCLUDED.date_added,
+                    metadata_updated_at = EXCLUDED.metadata_updated_at
                 "#,
                 listing.symbol,
                 listing.name,
-                listing.cmc_id,
-                listing.rank.unwrap_or(0),
+                listing.id,
+                listing.cmc_rank,
+                listing.slug,
+                &listing.tags,
+                platform.map(|p| p.name.clone()),
+                platform.map(|p| p.symbol.clone()),
+                platform.map(|p| p.token_address.clone()),
+                listing.circulating_supply,
+                listing.total_supply,
+                listing.max_supply,
+                date_added,
             )
             .execute(self.pool)
             .await?;
@@ -87,19 +181,151 @@ impl<'a> AssetRepository<'a> {
         Ok(updated_count)
     }
 
-    // Fetches all cmc_ids from the assets table
-    pub async fn get_all_cmc_ids(&self) -> Result<Vec<i32>> {
-        let cmc_ids = sqlx::query_scalar!("SELECT cmc_id FROM assets")
-            .fetch_all(self.pool)
-            .await?;
+    // Fetches cmc_ids of assets with a positive net amount across all transactions
+    pub async fn get_held_cmc_ids(&self) -> Result<Vec<i32>> {
+        let cmc_ids = sqlx::query_scalar!(
+            r#"
+            SELECT a.cmc_id
+            FROM assets a
+            JOIN transactions t ON t.asset_id = a.id
+            GROUP BY a.id, a.cmc_id
+            HAVING SUM(CASE WHEN t.type = 'BUY' THEN t.amount ELSE -t.amount END) > 0
+            ORDER BY a.cmc_id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
         Ok(cmc_ids)
     }
 
-    // Fetches cmc_id by asset_id
-    pub async fn get_cmc_id_by_asset_id(&self, asset_id: i32) -> Result<Option<i32>> {
-        let cmc_id = sqlx::query_scalar!("SELECT cmc_id FROM assets WHERE id = $1", asset_id)
-            .fetch_optional(self.pool)
-            .await?;
-        Ok(cmc_id)
+    // Fetches cmc_ids of assets on at least one watchlist that are not held
+    pub async fn get_tracked_cmc_ids(&self) -> Result<Vec<i32>> {
+        let cmc_ids = sqlx::query_scalar!(
+            r#"
+            SELECT a.cmc_id
+            FROM assets a
+            WHERE EXISTS (SELECT 1 FROM watchlist_assets wa WHERE wa.asset_id = a.id)
+              AND NOT EXISTS (
+                SELECT 1 FROM transactions t
+                WHERE t.asset_id = a.id
+                GROUP BY t.asset_id
+                HAVING SUM(CASE WHEN t.type = 'BUY' THEN t.amount ELSE -t.amount END) > 0
+              )
+            ORDER BY a.cmc_id
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(cmc_ids)
+    }
+
+    // Fetches (asset_id, cmc_id) pairs for the given asset_ids; unknown IDs are skipped
+    pub async fn get_cmc_ids(&self, asset_ids: &[i32]) -> Result<Vec<(i32, i32)>> {
+        let rows = sqlx::query!(
+            "SELECT id, cmc_id FROM assets WHERE id = ANY($1) ORDER BY id",
+            asset_ids
+        )
+        .fetch_all(self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.id, row.cmc_id))
+        .collect();
+        Ok(rows)
+    }
+
+    // Fetches (asset_id, symbol) pairs for the given asset_ids; unknown IDs are skipped
+    pub async fn get_symbols(&self, asset_ids: &[i32]) -> Result<Vec<(i32, String)>> {
+        let rows = sqlx::query!(
+            "SELECT id, symbol FROM assets WHERE id = ANY($1) ORDER BY id",
+            asset_ids
+        )
+        .fetch_all(self.pool)
+        .await?
+        .into_iter()
+        .map(|row| (row.id, row.symbol))
+        .collect();
+        Ok(rows)
+    }
+
+    // Fetches assets whose symbol is shared with other assets, with their net holdings
+    pub async fn get_symbol_collisions(&self) -> Result<Vec<SymbolCollisionDb>> {
+        let collisions = sqlx::query_as::<_, SymbolCollisionDb>(
+            r#"
+            SELECT
+                a.symbol,
+                a.id AS asset_id,
+                a.cmc_id,
+                a.name,
+                a.rank,
+                COALESCE(h.amount, 0) AS held_amount
+            FROM assets a
+            LEFT JOIN (
+                SELECT
+                    asset_id,
+                    SUM(CASE WHEN type = 'BUY' THEN amount ELSE -amount END) AS amount
+                FROM transactions
+                GROUP BY asset_id
+            ) h ON h.asset_id = a.id
+            WHERE a.symbol IN (
+                SELECT symbol FROM assets GROUP BY symbol HAVING COUNT(*) > 1
+            )
+            ORDER BY a.symbol ASC, a.rank ASC NULLS LAST, a.id ASC
+            "#,
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(collisions)
+    }
+}
+
+// Appends the WHERE conditions shared by the asset search and count queries
+fn push_asset_filters(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    params: &AssetQueryParams,
+    pattern: Option<&str>,
+) {
+    if let Some(pattern) = pattern {
+        query_builder.push(" AND (LOWER(a.symbol) LIKE ");
+        query_builder.push_bind(pattern.to_string());
+        query_builder.push(" OR LOWER(a.name) LIKE ");
+        query_builder.push_bind(pattern.to_string());
+        query_builder.push(")");
+    }
+
+    if let Some(min_rank) = params.min_rank {
+        query_builder.push(" AND a.rank >= ");
+        query_builder.push_bind(min_rank);
+    }
+
+    if let Some(max_rank) = params.max_rank {
+        query_builder.push(" AND a.rank <= ");
+        query_builder.push_bind(max_rank);
+    }
+
+    // Held assets have a positive net amount across all transactions
+    if let Some(held) = params.held {
+        query_builder.push(if held { " AND " } else { " AND NOT " });
+        query_builder.push(
+            r#"EXISTS (
+                SELECT 1 FROM transactions t
+                WHERE t.asset_id = a.id
+                GROUP BY t.asset_id
+                HAVING SUM(CASE WHEN t.type = 'BUY' THEN t.amount ELSE -t.amount END) > 0
+            )"#,
+        );
     }
+
+    // Tracked assets are on at least one watchlist
+    if let Some(tracked) = params.tracked {
+        query_builder.push(if tracked { " AND " } else { " AND NOT " });
+        query_builder.push("EXISTS (SELECT 1 FROM watchlist_assets wa WHERE wa.asset_id = a.id)");
+    }
+}
+
+// Escapes LIKE wildcards so user input is matched literally
+pub fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
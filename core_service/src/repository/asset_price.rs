@@ -1,5 +1,10 @@
+use crate::models::asset::PriceHistorySort;
+use crate::models::backtest::PriceGridDb;
 use crate::models::cmc::CmcQuote;
-use crate::services::redis::RedisService;
+use crate::models::pagination::PageParams;
+use crate::models::stream::{PriceEventDb, PRICES_EVENT};
+use crate::repository::stream_event::StreamEventRepository;
+use crate::services::redis::{CachedPrice, RedisService};
 use crate::utils::datetime::format_iso8601;
 use anyhow::Result;
 use sqlx::types::time::PrimitiveDateTime;
@@ -17,9 +22,11 @@ impl<'a> AssetPriceRepository<'a> {
         Self { pool, redis }
     }
 
-    // Saves asset prices into the asset_prices table and caches them in Redis
+    // Saves asset prices into the asset_prices table, caches them in Redis in one batch and
+    // publishes them to streaming clients
     pub async fn save_prices(&self, prices: Vec<(i32, CmcQuote)>) -> Result<usize> {
         let mut inserted_count = 0;
+        let mut cached_prices: Vec<(i32, CachedPrice)> = Vec::new();
 
         for (cmc_id, quote) in prices {
             if let Some(price_usd) = quote.price {
@@ -32,53 +39,139 @@ impl<'a> AssetPriceRepository<'a> {
                 if let Some(asset_id) = asset_id {
                     let result = sqlx::query!(
                         r#"
-                        INSERT INTO asset_prices (asset_id, price_usd)
-                        VALUES ($1, $2)
+                        INSERT INTO asset_prices
+                            (asset_id, price_usd, percent_change_24h, percent_change_7d)
+                        VALUES ($1, $2, $3, $4)
                         ON CONFLICT (asset_id, timestamp) DO NOTHING
                         RETURNING timestamp
                         "#,
                         asset_id,
-                        price_usd
+                        price_usd,
+                        quote.percent_change_24h,
+                        quote.percent_change_7d
                     )
                     .fetch_one(self.pool)
                     .await?;
 
                     inserted_count += 1;
 
-                    // Cache the price in Redis with the timestamp returned from DB
-                    self.redis
-                        .save_price(asset_id, price_usd, format_iso8601(result.timestamp))
-                        .await?;
+                    // Cache the price with the timestamp returned from DB
+                    cached_prices.push((
+                        asset_id,
+                        CachedPrice {
+                            price_usd,
+                            timestamp: format_iso8601(result.timestamp),
+                        },
+                    ));
                 }
             }
         }
 
+        self.redis.save_prices(&cached_prices).await;
+
+        // Streaming is best effort; the prices are saved either way
+        if !cached_prices.is_empty() {
+            let event: Vec<PriceEventDb> = cached_prices
+                .into_iter()
+                .map(|(asset_id, cached)| PriceEventDb {
+                    asset_id,
+                    price_usd: cached.price_usd,
+                    timestamp: cached.timestamp,
+                })
+                .collect();
+            if let Err(e) = StreamEventRepository::new(self.pool)
+                .append(PRICES_EVENT, serde_json::to_value(event)?)
+                .await
+            {
+                log::warn!("Failed to publish saved prices: {}", e);
+            }
+        }
         Ok(inserted_count)
     }
 
-    // Gets the latest prices for all assets from asset_prices
+    // Gets the latest prices for all assets from asset_prices, keyed by asset_id
     pub async fn get_latest_prices(&self) -> Result<Vec<(i32, f64, PrimitiveDateTime)>> {
         let prices = sqlx::query!(
             r#"
-            SELECT a.cmc_id, ap.price_usd, ap.timestamp
+            SELECT ap.asset_id AS "asset_id!", ap.price_usd, ap.timestamp
             FROM asset_prices ap
-            JOIN assets a ON a.id = ap.asset_id
-            WHERE ap.timestamp = (
+            WHERE ap.asset_id IS NOT NULL
+              AND ap.timestamp = (
                 SELECT MAX(timestamp)
                 FROM asset_prices
                 WHERE asset_id = ap.asset_id
-            )
+              )
             "#,
         )
         .fetch_all(self.pool)
         .await?
         .into_iter()
-        .map(|record| (record.cmc_id, record.price_usd, record.timestamp))
+        .map(|record| (record.asset_id, record.price_usd, record.timestamp))
         .collect();
 
         Ok(prices)
     }
 
+    // Gets the latest stored price of each asset at every step from start to end, plus end
+    // itself, sorted by time and asset_id
+    pub async fn get_price_grid(
+        &self,
+        asset_ids: &[i32],
+        start: PrimitiveDateTime,
+        end: PrimitiveDateTime,
+        step_seconds: f64,
+    ) -> Result<Vec<PriceGridDb>> {
+        let grid = sqlx::query_as::<_, PriceGridDb>(
+            r#"
+            SELECT g.at, ids.asset_id, lp.price_usd
+            FROM (
+                SELECT generate_series($2::TIMESTAMP, $3::TIMESTAMP, make_interval(secs => $4))
+                UNION
+                SELECT $3::TIMESTAMP
+            ) AS g(at)
+            CROSS JOIN UNNEST($1::INT[]) AS ids(asset_id)
+            LEFT JOIN LATERAL (
+                SELECT price_usd
+                FROM asset_prices
+                WHERE asset_id = ids.asset_id AND timestamp <= g.at
+                ORDER BY timestamp DESC
+                LIMIT 1
+            ) lp ON TRUE
+            ORDER BY g.at, ids.asset_id
+            "#,
+        )
+        .bind(asset_ids)
+        .bind(start)
+        .bind(end)
+        .bind(step_seconds)
+        .fetch_all(self.pool)
+        .await?;
+        Ok(grid)
+    }
+
+    // Gets the symbol and latest price timestamp of the asset, among the given cmc_ids, whose
+    // latest price is the oldest; assets without any stored price are skipped
+    pub async fn get_stalest_price(
+        &self,
+        cmc_ids: &[i32],
+    ) -> Result<Option<(String, PrimitiveDateTime)>> {
+        let row = sqlx::query!(
+            r#"
+            SELECT a.symbol, MAX(ap.timestamp) AS "newest!"
+            FROM assets a
+            JOIN asset_prices ap ON ap.asset_id = a.id
+            WHERE a.cmc_id = ANY($1)
+            GROUP BY a.id, a.symbol
+            ORDER BY 2
+            LIMIT 1
+            "#,
+            cmc_ids
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(row.map(|row| (row.symbol, row.newest)))
+    }
+
     // Gets the latest prices with asset details, optionally filtered by asset_ids, sorted by rank
     pub async fn get_latest_prices_with_assets(
         &self,
@@ -135,7 +228,18 @@ impl<'a> AssetPriceRepository<'a> {
         asset_ids: Option<Vec<i32>>,
         start_date: PrimitiveDateTime,
         end_date: Option<PrimitiveDateTime>,
-    ) -> Result<Vec<(i32, String, f64, PrimitiveDateTime)>> {
+        page: &PageParams<PriceHistorySort>,
+    ) -> Result<(Vec<(i32, String, f64, PrimitiveDateTime)>, i64)> {
+        let asset_ids = asset_ids.filter(|ids| !ids.is_empty());
+
+        let mut count_builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("SELECT COUNT(*) FROM asset_prices ap WHERE 1=1");
+        push_history_filters(&mut count_builder, start_date, end_date, &asset_ids);
+        let total = count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(self.pool)
+            .await?;
+
         let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
             r#"
             SELECT 
@@ -145,25 +249,14 @@ impl<'a> AssetPriceRepository<'a> {
                 ap.timestamp
             FROM asset_prices ap
             JOIN assets a ON ap.asset_id = a.id
-            WHERE ap.timestamp >= 
+            WHERE 1=1
             "#,
         );
-        query_builder.push_bind(start_date);
+        push_history_filters(&mut query_builder, start_date, end_date, &asset_ids);
 
-        if let Some(end) = end_date {
-            query_builder.push(" AND ap.timestamp <= ");
-            query_builder.push_bind(end);
-        }
-
-        if let Some(ids) = asset_ids {
-            if !ids.is_empty() {
-                query_builder.push(" AND a.id = ANY(");
-                query_builder.push_bind(ids);
-                query_builder.push(")");
-            }
-        }
-
-        query_builder.push(" ORDER BY ap.timestamp ASC");
+        query_builder.push(" ORDER BY ");
+        page.push_sort(&mut query_builder);
+        page.push_limit(&mut query_builder);
 
         let history = query_builder
             .build()
@@ -179,6 +272,28 @@ impl<'a> AssetPriceRepository<'a> {
                 ))
             })
             .collect::<Result<Vec<_>>>()?;
-        Ok(history)
+        Ok((history, total))
+    }
+}
+
+// Appends the WHERE conditions shared by the price history and count queries
+fn push_history_filters(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    start_date: PrimitiveDateTime,
+    end_date: Option<PrimitiveDateTime>,
+    asset_ids: &Option<Vec<i32>>,
+) {
+    query_builder.push(" AND ap.timestamp >= ");
+    query_builder.push_bind(start_date);
+
+    if let Some(end) = end_date {
+        query_builder.push(" AND ap.timestamp <= ");
+        query_builder.push_bind(end);
+    }
+
+    if let Some(ids) = asset_ids {
+        query_builder.push(" AND ap.asset_id = ANY(");
+        query_builder.push_bind(ids.clone());
+        query_builder.push(")");
     }
 }
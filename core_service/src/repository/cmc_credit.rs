@@ -0,0 +1,45 @@
+use anyhow::Result;
+use sqlx::PgPool;
+
+// Repository for tracking CoinMarketCap API credit usage per day
+pub struct CmcCreditRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> CmcCreditRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Records one API call and the credits it consumed for the current UTC day
+    pub async fn record(&self, credits: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO cmc_credit_usage (day, credits_used, calls)
+            VALUES ((NOW() AT TIME ZONE 'UTC')::DATE, $1, 1)
+            ON CONFLICT (day) DO UPDATE
+            SET credits_used = cmc_credit_usage.credits_used + EXCLUDED.credits_used,
+                calls = cmc_credit_usage.calls + 1,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+            credits
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Returns the credits consumed so far on the current UTC day
+    pub async fn get_today(&self) -> Result<i64> {
+        let used = sqlx::query_scalar!(
+            r#"
+            SELECT COALESCE(SUM(credits_used), 0) AS "used!"
+            FROM cmc_credit_usage
+            WHERE day = (NOW() AT TIME ZONE 'UTC')::DATE
+            "#
+        )
+        .fetch_one(self.pool)
+        .await?;
+        Ok(used)
+    }
+}
@@ -0,0 +1,421 @@
+use crate::dto::dca::CreateDcaPlanDto;
+use crate::dto::transaction::CreateTransactionDto;
+use crate::models::dca::{
+    DcaExecutionDb, DcaPlanDb, DueDcaPlanDb, ExecutionSort, EXECUTION_CONFIRMED, EXECUTION_PENDING,
+    EXECUTION_SKIPPED,
+};
+use crate::models::pagination::PageParams;
+use crate::models::transaction::TransactionDb;
+use crate::repository::transaction;
+use anyhow::Result;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+// Selects executions with the asset and wallet of their plan, for the dynamically sorted pages
+const SELECT_EXECUTIONS: &str = r#"
+    SELECT
+        e.id,
+        e.plan_id,
+        p.asset_id,
+        a.symbol AS asset,
+        p.wallet_id,
+        w.name AS wallet,
+        e.scheduled_at,
+        e.amount_usd,
+        e.price,
+        e.amount,
+        e.status,
+        e.transaction_id,
+        e.created_at,
+        e.resolved_at
+    FROM dca_executions e
+    JOIN dca_plans p ON p.id = e.plan_id
+    JOIN assets a ON a.id = p.asset_id
+    JOIN wallets w ON w.id = p.wallet_id
+"#;
+
+// Repository for DCA plans and the executions generated from them
+pub struct DcaRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> DcaRepository<'a> {
+    // Creates a new instance of DcaRepository
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Creates a new plan, returning its ID
+    pub async fn create_plan(
+        &self,
+        plan: &CreateDcaPlanDto,
+        start_date: PrimitiveDateTime,
+        end_date: Option<PrimitiveDateTime>,
+        next_execution_at: Option<PrimitiveDateTime>,
+    ) -> Result<i32> {
+        let id = sqlx::query_scalar!(
+            r#"
+            INSERT INTO dca_plans
+                (asset_id, wallet_id, amount_usd, frequency, start_date, end_date,
+                 next_execution_at, notes)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id
+            "#,
+            plan.asset_id,
+            plan.wallet_id,
+            plan.amount_usd,
+            plan.frequency,
+            start_date,
+            end_date,
+            next_execution_at,
+            plan.notes
+        )
+        .fetch_one(self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    // Retrieves all plans sorted by ID
+    pub async fn get_plans(&self) -> Result<Vec<DcaPlanDb>> {
+        let plans = sqlx::query_as!(
+            DcaPlanDb,
+            r#"
+            SELECT
+                p.id,
+                p.asset_id,
+                a.symbol AS asset,
+                p.wallet_id,
+                w.name AS wallet,
+                p.amount_usd,
+                p.frequency,
+                p.start_date,
+                p.end_date,
+                p.next_execution_at,
+                p.active,
+                p.notes,
+                (SELECT COUNT(*) FROM dca_executions e
+                 WHERE e.plan_id = p.id AND e.status = 'pending') AS "pending_executions!",
+                (SELECT ap.price_usd FROM asset_prices ap
+                 WHERE ap.asset_id = p.asset_id
+                 ORDER BY ap.timestamp DESC LIMIT 1) AS latest_price_usd,
+                p.created_at,
+                p.updated_at
+            FROM dca_plans p
+            JOIN assets a ON a.id = p.asset_id
+            JOIN wallets w ON w.id = p.wallet_id
+            ORDER BY p.id ASC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(plans)
+    }
+
+    // Retrieves a plan by ID
+    pub async fn get_plan(&self, id: i32) -> Result<Option<DcaPlanDb>> {
+        let plan = sqlx::query_as!(
+            DcaPlanDb,
+            r#"
+            SELECT
+                p.id,
+                p.asset_id,
+                a.symbol AS asset,
+                p.wallet_id,
+                w.name AS wallet,
+                p.amount_usd,
+                p.frequency,
+                p.start_date,
+                p.end_date,
+                p.next_execution_at,
+                p.active,
+                p.notes,
+                (SELECT COUNT(*) FROM dca_executions e
+                 WHERE e.plan_id = p.id AND e.status = 'pending') AS "pending_executions!",
+                (SELECT ap.price_usd FROM asset_prices ap
+                 WHERE ap.asset_id = p.asset_id
+                 ORDER BY ap.timestamp DESC LIMIT 1) AS latest_price_usd,
+                p.created_at,
+                p.updated_at
+            FROM dca_plans p
+            JOIN assets a ON a.id = p.asset_id
+            JOIN wallets w ON w.id = p.wallet_id
+            WHERE p.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(plan)
+    }
+
+    // Retrieves the active plans with a next execution, optionally only one of them
+    pub async fn get_scheduled_plans(&self, plan_id: Option<i32>) -> Result<Vec<DcaPlanDb>> {
+        let plans = sqlx::query_as!(
+            DcaPlanDb,
+            r#"
+            SELECT
+                p.id,
+                p.asset_id,
+                a.symbol AS asset,
+                p.wallet_id,
+                w.name AS wallet,
+                p.amount_usd,
+                p.frequency,
+                p.start_date,
+                p.end_date,
+                p.next_execution_at,
+                p.active,
+                p.notes,
+                (SELECT COUNT(*) FROM dca_executions e
+                 WHERE e.plan_id = p.id AND e.status = 'pending') AS "pending_executions!",
+                (SELECT ap.price_usd FROM asset_prices ap
+                 WHERE ap.asset_id = p.asset_id
+                 ORDER BY ap.timestamp DESC LIMIT 1) AS latest_price_usd,
+                p.created_at,
+                p.updated_at
+            FROM dca_plans p
+            JOIN assets a ON a.id = p.asset_id
+            JOIN wallets w ON w.id = p.wallet_id
+            WHERE p.active AND p.next_execution_at IS NOT NULL
+                AND ($1::INT IS NULL OR p.id = $1)
+            ORDER BY p.next_execution_at ASC, p.id ASC
+            "#,
+            plan_id
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(plans)
+    }
+
+    // Saves the settings and schedule of a plan, returning false if it does not exist
+    pub async fn update_plan(&self, plan: &DcaPlanDb) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE dca_plans
+            SET amount_usd = $2,
+                frequency = $3,
+                end_date = $4,
+                next_execution_at = $5,
+                active = $6,
+                notes = $7,
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            plan.id,
+            plan.amount_usd,
+            plan.frequency,
+            plan.end_date,
+            plan.next_execution_at,
+            plan.active,
+            plan.notes
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Deletes a plan and its executions, returning false if it does not exist. Confirmed
+    // transactions are kept.
+    pub async fn delete_plan(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM dca_plans WHERE id = $1", id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Retrieves the active plans whose next execution is at or before now
+    pub async fn get_due_plans(&self, now: PrimitiveDateTime) -> Result<Vec<DueDcaPlanDb>> {
+        let plans = sqlx::query_as!(
+            DueDcaPlanDb,
+            r#"
+            SELECT id, frequency, start_date, end_date, next_execution_at AS "next_execution_at!"
+            FROM dca_plans
+            WHERE active AND next_execution_at <= $1
+            ORDER BY next_execution_at ASC
+            "#,
+            now
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(plans)
+    }
+
+    // Generates the pending execution of a plan due at scheduled_at, priced at the latest stored
+    // price of its asset, and moves the plan on to next_execution_at. Does nothing if the plan
+    // was changed or already moved on meanwhile; returns whether the execution was generated.
+    pub async fn schedule_execution(
+        &self,
+        plan_id: i32,
+        scheduled_at: PrimitiveDateTime,
+        next_execution_at: Option<PrimitiveDateTime>,
+    ) -> Result<bool> {
+        let mut tx = self.pool.begin().await?;
+        let moved = sqlx::query!(
+            r#"
+            UPDATE dca_plans
+            SET next_execution_at = $3
+            WHERE id = $1 AND active AND next_execution_at = $2
+            "#,
+            plan_id,
+            scheduled_at,
+            next_execution_at
+        )
+        .execute(&mut *tx)
+        .await?
+        .rows_affected()
+            > 0;
+        if !moved {
+            return Ok(false);
+        }
+
+        sqlx::query!(
+            r#"
+            INSERT INTO dca_executions (plan_id, scheduled_at, amount_usd, price, amount)
+            SELECT p.id, $2, p.amount_usd, lp.price_usd, p.amount_usd / NULLIF(lp.price_usd, 0)
+            FROM dca_plans p
+            LEFT JOIN LATERAL (
+                SELECT price_usd
+                FROM asset_prices
+                WHERE asset_id = p.asset_id
+                ORDER BY timestamp DESC
+                LIMIT 1
+            ) lp ON TRUE
+            WHERE p.id = $1
+            ON CONFLICT (plan_id, scheduled_at) DO NOTHING
+            "#,
+            plan_id,
+            scheduled_at
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(true)
+    }
+
+    // Fetches a page of executions, optionally of one plan and with one status, returning the
+    // page and the total count
+    pub async fn get_executions(
+        &self,
+        plan_id: Option<i32>,
+        status: Option<String>,
+        page: &PageParams<ExecutionSort>,
+    ) -> Result<(Vec<DcaExecutionDb>, i64)> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM dca_executions
+            WHERE ($1::INT IS NULL OR plan_id = $1) AND ($2::TEXT IS NULL OR status = $2)
+            "#,
+            plan_id,
+            status
+        )
+        .fetch_one(self.pool)
+        .await?;
+
+        let mut query_builder: QueryBuilder<Postgres> =
+            QueryBuilder::new(format!("{} WHERE 1=1", SELECT_EXECUTIONS));
+        if let Some(plan_id) = plan_id {
+            query_builder.push(" AND e.plan_id = ");
+            query_builder.push_bind(plan_id);
+        }
+        if let Some(status) = status {
+            query_builder.push(" AND e.status = ");
+            query_builder.push_bind(status);
+        }
+        query_builder.push(" ORDER BY ");
+        page.push_sort(&mut query_builder);
+        page.push_limit(&mut query_builder);
+
+        let executions = query_builder
+            .build_query_as::<DcaExecutionDb>()
+            .fetch_all(self.pool)
+            .await?;
+        Ok((executions, total))
+    }
+
+    // Retrieves an execution by ID
+    pub async fn get_execution(&self, id: i32) -> Result<Option<DcaExecutionDb>> {
+        let execution = sqlx::query_as!(
+            DcaExecutionDb,
+            r#"
+            SELECT
+                e.id,
+                e.plan_id,
+                p.asset_id,
+                a.symbol AS asset,
+                p.wallet_id,
+                w.name AS wallet,
+                e.scheduled_at,
+                e.amount_usd,
+                e.price,
+                e.amount,
+                e.status,
+                e.transaction_id,
+                e.created_at,
+                e.resolved_at
+            FROM dca_executions e
+            JOIN dca_plans p ON p.id = e.plan_id
+            JOIN assets a ON a.id = p.asset_id
+            JOIN wallets w ON w.id = p.wallet_id
+            WHERE e.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(execution)
+    }
+
+    // Creates the transaction of a pending execution and marks it confirmed in a single database
+    // transaction. Returns None without writing anything if the execution is no longer pending.
+    pub async fn confirm_execution(
+        &self,
+        id: i32,
+        transaction: &CreateTransactionDto,
+    ) -> Result<Option<TransactionDb>> {
+        let mut tx = self.pool.begin().await?;
+        let pending = sqlx::query_scalar!(
+            "SELECT id FROM dca_executions WHERE id = $1 AND status = $2 FOR UPDATE",
+            id,
+            EXECUTION_PENDING
+        )
+        .fetch_optional(&mut *tx)
+        .await?;
+        if pending.is_none() {
+            return Ok(None);
+        }
+
+        let record = transaction::insert(&mut *tx, transaction).await?;
+        sqlx::query!(
+            r#"
+            UPDATE dca_executions
+            SET status = $2, transaction_id = $3, resolved_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            "#,
+            id,
+            EXECUTION_CONFIRMED,
+            record.id
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+        Ok(Some(record))
+    }
+
+    // Marks a pending execution skipped, returning false if it is no longer pending
+    pub async fn skip_execution(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE dca_executions
+            SET status = $2, resolved_at = CURRENT_TIMESTAMP
+            WHERE id = $1 AND status = $3
+            "#,
+            id,
+            EXECUTION_SKIPPED,
+            EXECUTION_PENDING
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -0,0 +1,145 @@
+use crate::models::idempotency::{IdempotencyClaim, IdempotencyKeyDb};
+use anyhow::Result;
+use sqlx::PgPool;
+use uuid::Uuid;
+
+// Repository for stored responses of requests sent with an Idempotency-Key header
+pub struct IdempotencyKeyRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> IdempotencyKeyRepository<'a> {
+    // Creates a new instance of IdempotencyKeyRepository
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Claims a key for a new request. A key already in use can be claimed again once it is older
+    // than ttl_secs, or when its request stopped sending heartbeats for stale_secs without
+    // storing a response. Returns whether the key was claimed.
+    pub async fn claim(
+        &self,
+        claim: &IdempotencyClaim<'_>,
+        ttl_secs: i64,
+        stale_secs: i64,
+    ) -> Result<bool> {
+        let claimed = sqlx::query_scalar!(
+            r#"
+            INSERT INTO idempotency_keys (key, claim_token, request_hash, method, path)
+            VALUES ($1, $7, $2, $3, $4)
+            ON CONFLICT (key) DO UPDATE
+            SET claim_token = EXCLUDED.claim_token,
+                request_hash = EXCLUDED.request_hash,
+                method = EXCLUDED.method,
+                path = EXCLUDED.path,
+                response_status = NULL,
+                response_content_type = NULL,
+                response_body = NULL,
+                created_at = NOW(),
+                heartbeat_at = NOW(),
+                completed_at = NULL
+            WHERE idempotency_keys.created_at < NOW() - make_interval(secs => $5)
+                OR (idempotency_keys.response_status IS NULL
+                    AND idempotency_keys.heartbeat_at < NOW() - make_interval(secs => $6))
+            RETURNING key
+            "#,
+            claim.key,
+            claim.request_hash,
+            claim.method,
+            claim.path,
+            ttl_secs as f64,
+            stale_secs as f64,
+            claim.token
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(claimed.is_some())
+    }
+
+    // Gets a key with its stored response, if any
+    pub async fn get(&self, key: &str) -> Result<Option<IdempotencyKeyDb>> {
+        let record = sqlx::query_as!(
+            IdempotencyKeyDb,
+            r#"
+            SELECT request_hash, response_status, response_content_type, response_body
+            FROM idempotency_keys
+            WHERE key = $1
+            "#,
+            key
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(record)
+    }
+
+    // Keeps a key claimed by the request identified by token while it runs. Returns false if
+    // the key was claimed by another request meanwhile.
+    pub async fn heartbeat(&self, key: &str, token: Uuid) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET heartbeat_at = NOW()
+            WHERE key = $1 AND claim_token = $2 AND response_status IS NULL
+            "#,
+            key,
+            token
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Stores the response of the request identified by token. Returns false if the key was
+    // claimed by another request meanwhile.
+    pub async fn complete(
+        &self,
+        key: &str,
+        token: Uuid,
+        status: i32,
+        content_type: Option<&str>,
+        body: &[u8],
+    ) -> Result<bool> {
+        let result = sqlx::query!(
+            r#"
+            UPDATE idempotency_keys
+            SET response_status = $3, response_content_type = $4, response_body = $5,
+                completed_at = NOW()
+            WHERE key = $1 AND claim_token = $2
+            "#,
+            key,
+            token,
+            status,
+            content_type,
+            body
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Deletes a key whose request, identified by token, did not complete, so it can be retried
+    pub async fn release(&self, key: &str, token: Uuid) -> Result<()> {
+        sqlx::query!(
+            r#"
+            DELETE FROM idempotency_keys
+            WHERE key = $1 AND claim_token = $2 AND response_status IS NULL
+            "#,
+            key,
+            token
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Deletes keys older than the given number of seconds, returning the number deleted
+    pub async fn delete_older_than(&self, max_age_secs: i64) -> Result<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM idempotency_keys WHERE created_at < NOW() - make_interval(secs => $1)",
+            max_age_secs as f64
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
@@ -1,5 +1,12 @@
 pub mod asset;
 pub mod asset_price;
+pub mod cmc_credit;
+pub mod dca;
+pub mod idempotency_key;
 pub mod snapshot;
+pub mod stream_event;
+pub mod tag;
 pub mod transaction;
 pub mod wallet;
+pub mod watchlist;
+pub mod webhook;
@@ -1,8 +1,9 @@
 use crate::dto::snapshot::SnapshotAssetDto;
 use crate::error::AppError;
-use crate::models::snapshot::SnapshotDb;
+use crate::models::pagination::PageParams;
+use crate::models::snapshot::{SnapshotDb, SnapshotSort};
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 
 // Repository for snapshot-related database operations
 pub struct SnapshotRepository<'a> {
@@ -25,23 +26,33 @@ impl<'a> SnapshotRepository<'a> {
         )
         .bind(sqlx::types::Json(&assets))
         .fetch_one(self.pool)
-        .await
-        .map_err(AppError::internal)?;
+        .await?;
         Ok(record)
     }
 
-    // Retrieves all snapshots from the database
-    pub async fn get_all(&self) -> Result<Vec<SnapshotDb>, AppError> {
-        let snapshots = sqlx::query_as::<_, SnapshotDb>(
+    // Retrieves a page of snapshots with the total number of snapshots
+    pub async fn get_page(
+        &self,
+        page: &PageParams<SnapshotSort>,
+    ) -> Result<(Vec<SnapshotDb>, i64), AppError> {
+        let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM portfolio_snapshots")
+            .fetch_one(self.pool)
+            .await?;
+
+        let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
             r#"
             SELECT id, created_at, assets
             FROM portfolio_snapshots
-            ORDER BY created_at DESC
+            ORDER BY 
             "#,
-        )
-        .fetch_all(self.pool)
-        .await
-        .map_err(AppError::internal)?;
-        Ok(snapshots)
+        );
+        page.push_sort(&mut query_builder);
+        page.push_limit(&mut query_builder);
+
+        let snapshots = query_builder
+            .build_query_as::<SnapshotDb>()
+            .fetch_all(self.pool)
+            .await?;
+        Ok((snapshots, total))
     }
 }
@@ -0,0 +1,84 @@
+use crate::models::stream::StreamEventDb;
+use anyhow::Result;
+use sqlx::PgPool;
+
+// Channel notified with the id of every appended stream event
+pub const STREAM_EVENTS_CHANNEL: &str = "stream_events";
+// Advisory lock serializing appends, so event ids commit in increasing order
+const APPEND_LOCK_KEY: i64 = 0x4155_5354_0000_0001; // "AUST" namespace, lock 1
+
+// Repository for the log of events pushed to streaming clients
+pub struct StreamEventRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> StreamEventRepository<'a> {
+    // Creates a new instance of StreamEventRepository
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Appends an event and notifies the listening instances, returning the event id. Appends
+    // are serialized so no event commits after one with a higher id, which readers following
+    // the log by id would otherwise skip; the notification is delivered on commit.
+    pub async fn append(&self, kind: &str, payload: serde_json::Value) -> Result<i64> {
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("SELECT pg_advisory_xact_lock($1)")
+            .bind(APPEND_LOCK_KEY)
+            .execute(&mut *tx)
+            .await?;
+        let id = sqlx::query_scalar!(
+            "INSERT INTO stream_events (kind, payload) VALUES ($1, $2) RETURNING id",
+            kind,
+            payload
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+        sqlx::query("SELECT pg_notify($1, $2)")
+            .bind(STREAM_EVENTS_CHANNEL)
+            .bind(id.to_string())
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    // Gets up to limit events appended after the given id, oldest first
+    pub async fn get_after(&self, after_id: i64, limit: i64) -> Result<Vec<StreamEventDb>> {
+        let events = sqlx::query_as!(
+            StreamEventDb,
+            r#"
+            SELECT id, kind, payload AS "payload: _"
+            FROM stream_events
+            WHERE id > $1
+            ORDER BY id
+            LIMIT $2
+            "#,
+            after_id,
+            limit
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(events)
+    }
+
+    // Gets the ids of the oldest and newest retained events
+    pub async fn get_id_range(&self) -> Result<Option<(i64, i64)>> {
+        let range =
+            sqlx::query!("SELECT MIN(id) AS first_id, MAX(id) AS last_id FROM stream_events")
+                .fetch_one(self.pool)
+                .await?;
+        Ok(range.first_id.zip(range.last_id))
+    }
+
+    // Deletes events older than the given number of seconds, returning the number deleted
+    pub async fn delete_older_than(&self, max_age_secs: i64) -> Result<u64> {
+        let result = sqlx::query!(
+            "DELETE FROM stream_events WHERE created_at < NOW() - make_interval(secs => $1)",
+            max_age_secs as f64
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+}
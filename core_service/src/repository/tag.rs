@@ -0,0 +1,179 @@
+use crate::models::tag::{TagAssetTotalsDb, TagDb};
+use anyhow::Result;
+use sqlx::PgPool;
+
+// Repository for tag-related database operations
+pub struct TagRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> TagRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Creates a new tag, returning its ID
+    pub async fn create(&self, name: &str, description: Option<&str>) -> Result<i32> {
+        let id = sqlx::query_scalar!(
+            "INSERT INTO tags (name, description) VALUES ($1, $2) RETURNING id",
+            name,
+            description
+        )
+        .fetch_one(self.pool)
+        .await?;
+        Ok(id)
+    }
+
+    // Retrieves all tags sorted by name
+    pub async fn get_all(&self) -> Result<Vec<TagDb>> {
+        let tags = sqlx::query_as!(
+            TagDb,
+            r#"
+            SELECT
+                tg.id,
+                tg.name,
+                tg.description,
+                (SELECT COUNT(*) FROM transaction_tags tt WHERE tt.tag_id = tg.id) AS "transaction_count!",
+                tg.created_at
+            FROM tags tg
+            ORDER BY LOWER(tg.name) ASC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(tags)
+    }
+
+    // Retrieves a tag by ID
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<TagDb>> {
+        let tag = sqlx::query_as!(
+            TagDb,
+            r#"
+            SELECT
+                tg.id,
+                tg.name,
+                tg.description,
+                (SELECT COUNT(*) FROM transaction_tags tt WHERE tt.tag_id = tg.id) AS "transaction_count!",
+                tg.created_at
+            FROM tags tg
+            WHERE tg.id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(tag)
+    }
+
+    // Updates the name and description of a tag, returning false if it does not exist
+    pub async fn update(&self, id: i32, name: &str, description: Option<&str>) -> Result<bool> {
+        let result = sqlx::query!(
+            "UPDATE tags SET name = $1, description = $2 WHERE id = $3",
+            name,
+            description,
+            id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Deletes a tag and its links to transactions, returning false if it does not exist
+    pub async fn delete(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM tags WHERE id = $1", id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Returns the given transaction IDs that do not exist
+    pub async fn get_missing_transactions(&self, transaction_ids: &[i32]) -> Result<Vec<i32>> {
+        let missing = sqlx::query_scalar!(
+            r#"
+            SELECT id AS "id!" FROM UNNEST($1::INT[]) AS ids(id)
+            WHERE NOT EXISTS (SELECT 1 FROM transactions t WHERE t.id = ids.id)
+            ORDER BY id
+            "#,
+            transaction_ids
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(missing)
+    }
+
+    // Tags transactions, ignoring those already tagged, and returns the number newly tagged
+    pub async fn tag_transactions(&self, tag_id: i32, transaction_ids: &[i32]) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO transaction_tags (transaction_id, tag_id)
+            SELECT DISTINCT UNNEST($1::INT[]), $2::INT
+            ON CONFLICT DO NOTHING
+            "#,
+            transaction_ids,
+            tag_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    // Removes a tag from a transaction, returning false if the transaction did not have it
+    pub async fn untag_transaction(&self, tag_id: i32, transaction_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM transaction_tags WHERE tag_id = $1 AND transaction_id = $2",
+            tag_id,
+            transaction_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Aggregates buys and sells per tag and asset, net of fees, with the latest stored price of
+    // each asset. A transaction with several tags counts towards each of them. Only the given
+    // tags (matched case-insensitively) are included when names is set, and untagged
+    // transactions are grouped in rows without a tag when include_untagged is set.
+    pub async fn get_asset_totals(
+        &self,
+        names: Option<&[String]>, // Lowercased
+        include_untagged: bool,
+    ) -> Result<Vec<TagAssetTotalsDb>> {
+        let totals = sqlx::query_as!(
+            TagAssetTotalsDb,
+            r#"
+            SELECT
+                tg.id AS "tag_id?",
+                tg.name AS "tag?",
+                a.id AS asset_id,
+                a.symbol,
+                COALESCE(SUM(t.amount) FILTER (WHERE t.type = 'BUY'), 0) AS "bought!",
+                COALESCE(SUM(t.amount * t.price + COALESCE(t.fee, 0))
+                    FILTER (WHERE t.type = 'BUY'), 0) AS "cost!",
+                COALESCE(SUM(t.amount) FILTER (WHERE t.type = 'SELL'), 0) AS "sold!",
+                COALESCE(SUM(t.amount * t.price - COALESCE(t.fee, 0))
+                    FILTER (WHERE t.type = 'SELL'), 0) AS "proceeds!",
+                lp.price_usd AS "price_usd?"
+            FROM transactions t
+            JOIN assets a ON a.id = t.asset_id
+            LEFT JOIN transaction_tags tt ON tt.transaction_id = t.id
+            LEFT JOIN tags tg ON tg.id = tt.tag_id
+            LEFT JOIN LATERAL (
+                SELECT price_usd
+                FROM asset_prices
+                WHERE asset_id = a.id
+                ORDER BY timestamp DESC
+                LIMIT 1
+            ) lp ON TRUE
+            WHERE (tg.id IS NOT NULL AND ($1::TEXT[] IS NULL OR LOWER(tg.name) = ANY($1)))
+               OR (tg.id IS NULL AND $2)
+            GROUP BY tg.id, tg.name, a.id, a.symbol, lp.price_usd
+            ORDER BY LOWER(tg.name) ASC NULLS LAST, a.symbol ASC
+            "#,
+            names,
+            include_untagged
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(totals)
+    }
+}
@@ -1,8 +1,11 @@
 use crate::dto::transaction::CreateTransactionDto;
-use crate::models::transaction::{FilterParams, TransactionDb};
-use crate::utils::datetime::parse_iso8601;
+use crate::models::pagination::PageParams;
+use crate::models::portfolio::HoldingDb;
+use crate::models::transaction::{BatchLookupDb, FilterParams, TransactionDb, TransactionSort};
+use crate::repository::asset::escape_like;
 use anyhow::Result;
-use sqlx::{PgPool, Postgres, QueryBuilder};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::{PgExecutor, PgPool, Postgres, QueryBuilder};
 
 // Repository for transaction-related database operations
 pub struct TransactionRepository<'a> {
@@ -15,51 +18,97 @@ impl<'a> TransactionRepository<'a> {
         Self { pool }
     }
 
-    // Creates a new transaction in the database
-    pub async fn create(&self, transaction: CreateTransactionDto) -> Result<TransactionDb> {
-        let record = sqlx::query_as!(
-            TransactionDb,
+    // Creates a batch of transactions in a single database transaction. The referenced wallets
+    // are locked, so concurrent batches see each other's holdings, and check is called with the
+    // lookup of the batch before inserting; nothing is written if it fails.
+    pub async fn create_batch(
+        &self,
+        transactions: &[CreateTransactionDto],
+        check: impl FnOnce(&BatchLookupDb) -> Result<()>,
+    ) -> Result<Vec<TransactionDb>> {
+        let asset_ids = transactions.iter().map(|t| t.asset_id).collect::<Vec<_>>();
+        let wallet_ids = transactions.iter().map(|t| t.wallet_id).collect::<Vec<_>>();
+
+        let mut tx = self.pool.begin().await?;
+        let existing_wallets = sqlx::query_scalar!(
+            "SELECT id FROM wallets WHERE id = ANY($1) ORDER BY id FOR UPDATE",
+            &wallet_ids
+        )
+        .fetch_all(&mut *tx)
+        .await?;
+        let existing_assets =
+            sqlx::query_scalar!("SELECT id FROM assets WHERE id = ANY($1)", &asset_ids)
+                .fetch_all(&mut *tx)
+                .await?;
+        let holdings = sqlx::query!(
             r#"
-            INSERT INTO transactions 
-                (asset_id, wallet_id, amount, price, type, fee, notes)
-            VALUES ($1, $2, $3, $4, $5, $6, $7)
-            RETURNING 
-                id, 
-                (SELECT symbol FROM assets WHERE id = $1) AS "asset!",
-                (SELECT name FROM wallets WHERE id = $2) AS "wallet!",
-                amount,
-                price,
-                type AS transaction_type,
-                fee,
-                notes,
-                created_at
+            SELECT
+                wallet_id AS "wallet_id!",
+                asset_id AS "asset_id!",
+                SUM(CASE WHEN type = 'BUY' THEN amount ELSE -amount END) AS "amount!"
+            FROM transactions
+            WHERE wallet_id = ANY($1) AND asset_id = ANY($2)
+            GROUP BY wallet_id, asset_id
             "#,
-            transaction.asset_id,
-            transaction.wallet_id,
-            transaction.amount,
-            transaction.price,
-            transaction.transaction_type,
-            transaction.fee,
-            transaction.notes,
+            &wallet_ids,
+            &asset_ids
         )
-        .fetch_one(self.pool)
+        .fetch_all(&mut *tx)
         .await?;
-        Ok(record)
+
+        check(&BatchLookupDb {
+            asset_ids: existing_assets.into_iter().collect(),
+            wallet_ids: existing_wallets.into_iter().collect(),
+            holdings: holdings
+                .into_iter()
+                .map(|row| ((row.wallet_id, row.asset_id), row.amount))
+                .collect(),
+        })?;
+
+        let mut records = Vec::with_capacity(transactions.len());
+        for transaction in transactions {
+            records.push(insert(&mut *tx, transaction).await?);
+        }
+        tx.commit().await?;
+        Ok(records)
     }
 
-    // Fetches transactions with optional filters
-    pub async fn get_transactions(&self, filters: FilterParams) -> Result<Vec<TransactionDb>> {
+    // Fetches a page of transactions with optional filters, returning the page and the total count
+    pub async fn get_transactions(
+        &self,
+        filters: &FilterParams,
+        start_date: Option<PrimitiveDateTime>,
+        end_date: Option<PrimitiveDateTime>,
+        page: &PageParams<TransactionSort>,
+    ) -> Result<(Vec<TransactionDb>, i64)> {
+        let mut count_builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("SELECT COUNT(*) FROM transactions t WHERE 1=1");
+        push_transaction_filters(&mut count_builder, filters, start_date, end_date);
+        let total = count_builder
+            .build_query_scalar::<i64>()
+            .fetch_one(self.pool)
+            .await?;
+
         let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
             r#"
             SELECT 
                 t.id, 
+                t.asset_id,
                 a.symbol AS asset, 
+                t.wallet_id,
                 w.name AS wallet,
                 t.amount,
                 t.price,
                 t.type AS transaction_type,
                 t.fee,
                 t.notes,
+                ARRAY(
+                    SELECT tg.name
+                    FROM transaction_tags tt
+                    JOIN tags tg ON tg.id = tt.tag_id
+                    WHERE tt.transaction_id = t.id
+                    ORDER BY LOWER(tg.name)
+                ) AS tags,
                 t.created_at
             FROM transactions t
             JOIN assets a ON t.asset_id = a.id
@@ -67,55 +116,144 @@ impl<'a> TransactionRepository<'a> {
             WHERE 1=1
             "#,
         );
+        push_transaction_filters(&mut query_builder, filters, start_date, end_date);
 
-        if let Some(asset_id) = filters.asset_id {
-            query_builder.push(" AND t.asset_id = ");
-            query_builder.push_bind(asset_id);
-        }
-
-        if let Some(wallet_id) = filters.wallet_id {
-            query_builder.push(" AND t.wallet_id = ");
-            query_builder.push_bind(wallet_id);
-        }
-
-        if let Some(start_date) = filters.start_date {
-            let parsed_date = parse_iso8601(&start_date)?;
-            query_builder.push(" AND t.created_at >= ");
-            query_builder.push_bind(parsed_date);
-        }
-
-        query_builder.push(" LIMIT ");
-        query_builder.push_bind(filters.limit.unwrap_or(10));
-
-        query_builder.push(" OFFSET ");
-        query_builder.push_bind(filters.offset.unwrap_or(0));
+        query_builder.push(" ORDER BY ");
+        page.push_sort(&mut query_builder);
+        page.push_limit(&mut query_builder);
 
         let query = query_builder.build_query_as::<TransactionDb>();
         let transactions = query.fetch_all(self.pool).await?;
-        Ok(transactions)
+        Ok((transactions, total))
     }
 
-    // Fetches all transactions without filters for portfolio calculations
-    pub async fn get_all_transactions(&self) -> Result<Vec<TransactionDb>> {
-        let transactions = sqlx::query_as::<_, TransactionDb>(
+    // Aggregates net holdings per asset from all transactions
+    pub async fn get_holdings(&self) -> Result<Vec<HoldingDb>> {
+        let holdings = sqlx::query_as::<_, HoldingDb>(
             r#"
-            SELECT 
-                t.id, 
-                a.symbol AS asset, 
-                w.name AS wallet,
-                t.amount,
-                t.price,
-                t.type AS transaction_type,
-                t.fee,
-                t.notes,
-                t.created_at
+            SELECT
+                a.id AS asset_id,
+                a.symbol,
+                a.cmc_id,
+                SUM(CASE WHEN t.type = 'BUY' THEN t.amount ELSE -t.amount END) AS amount
             FROM transactions t
             JOIN assets a ON t.asset_id = a.id
-            JOIN wallets w ON t.wallet_id = w.id
+            GROUP BY a.id, a.symbol, a.cmc_id
+            ORDER BY a.id ASC
             "#,
         )
         .fetch_all(self.pool)
         .await?;
-        Ok(transactions)
+        Ok(holdings)
+    }
+}
+
+// Inserts a transaction, returning it with the symbol of its asset and the name of its wallet
+pub async fn insert<'e>(
+    executor: impl PgExecutor<'e>,
+    transaction: &CreateTransactionDto,
+) -> Result<TransactionDb> {
+    let record = sqlx::query_as!(
+        TransactionDb,
+        r#"
+        INSERT INTO transactions 
+            (asset_id, wallet_id, amount, price, type, fee, notes)
+        VALUES ($1, $2, $3, $4, $5, $6, $7)
+        RETURNING 
+            id, 
+            asset_id AS "asset_id!",
+            (SELECT symbol FROM assets WHERE id = $1) AS "asset!",
+            wallet_id AS "wallet_id!",
+            (SELECT name FROM wallets WHERE id = $2) AS "wallet!",
+            amount,
+            price,
+            type AS transaction_type,
+            fee,
+            notes,
+            ARRAY[]::TEXT[] AS "tags!",
+            created_at
+        "#,
+        transaction.asset_id,
+        transaction.wallet_id,
+        transaction.amount,
+        transaction.price,
+        transaction.transaction_type,
+        transaction.fee,
+        transaction.notes,
+    )
+    .fetch_one(executor)
+    .await?;
+    Ok(record)
+}
+
+// Appends the WHERE conditions shared by the transaction list and count queries
+fn push_transaction_filters(
+    query_builder: &mut QueryBuilder<'_, Postgres>,
+    filters: &FilterParams,
+    start_date: Option<PrimitiveDateTime>,
+    end_date: Option<PrimitiveDateTime>,
+) {
+    let asset_ids = filters.asset_ids();
+    if !asset_ids.is_empty() {
+        query_builder.push(" AND t.asset_id = ANY(");
+        query_builder.push_bind(asset_ids);
+        query_builder.push(")");
+    }
+
+    let wallet_ids = filters.wallet_ids();
+    if !wallet_ids.is_empty() {
+        query_builder.push(" AND t.wallet_id = ANY(");
+        query_builder.push_bind(wallet_ids);
+        query_builder.push(")");
+    }
+
+    if let Some(start_date) = start_date {
+        query_builder.push(" AND t.created_at >= ");
+        query_builder.push_bind(start_date);
+    }
+
+    if let Some(end_date) = end_date {
+        query_builder.push(" AND t.created_at <= ");
+        query_builder.push_bind(end_date);
+    }
+
+    if let Some(transaction_type) = &filters.transaction_type {
+        query_builder.push(" AND t.type = ");
+        query_builder.push_bind(transaction_type.clone());
+    }
+
+    if let Some(min_amount) = filters.min_amount {
+        query_builder.push(" AND t.amount >= ");
+        query_builder.push_bind(min_amount);
+    }
+
+    if let Some(max_amount) = filters.max_amount {
+        query_builder.push(" AND t.amount <= ");
+        query_builder.push_bind(max_amount);
+    }
+
+    if let Some(min_price) = filters.min_price {
+        query_builder.push(" AND t.price >= ");
+        query_builder.push_bind(min_price);
+    }
+
+    if let Some(max_price) = filters.max_price {
+        query_builder.push(" AND t.price <= ");
+        query_builder.push_bind(max_price);
+    }
+
+    if let Some(notes) = &filters.notes {
+        query_builder.push(" AND t.notes ILIKE ");
+        query_builder.push_bind(format!("%{}%", escape_like(notes)));
+    }
+
+    let tag_names = filters.tag_names();
+    if !tag_names.is_empty() {
+        query_builder.push(
+            " AND EXISTS (SELECT 1 FROM transaction_tags tt JOIN tags tg ON tg.id = tt.tag_id \
+             WHERE tt.transaction_id = t.id AND LOWER(tg.name) = ANY(",
+        );
+        query_builder.push_bind(tag_names);
+        query_builder.push("))");
     }
 }
@@ -1,6 +1,7 @@
-use crate::models::wallet::WalletDb;
+use crate::models::pagination::PageParams;
+use crate::models::wallet::{WalletDb, WalletSort};
 use anyhow::Result;
-use sqlx::PgPool;
+use sqlx::{PgPool, Postgres, QueryBuilder};
 
 // Repository for wallet-related database operations
 pub struct WalletRepository<'a> {
@@ -44,17 +45,26 @@ impl<'a> WalletRepository<'a> {
         Ok(record)
     }
 
-    // Retrieves all wallets from the database
-    pub async fn get_all(&self) -> Result<Vec<WalletDb>> {
-        let wallets = sqlx::query_as!(
-            WalletDb,
+    // Retrieves a page of wallets with the total number of wallets
+    pub async fn get_page(&self, page: &PageParams<WalletSort>) -> Result<(Vec<WalletDb>, i64)> {
+        let total = sqlx::query_scalar::<_, i64>("SELECT COUNT(*) FROM wallets")
+            .fetch_one(self.pool)
+            .await?;
+
+        let mut query_builder: QueryBuilder<Postgres> = QueryBuilder::new(
             r#"
             SELECT id, name, type AS wallet_type, address, created_at
             FROM wallets
+            ORDER BY 
             "#,
-        )
-        .fetch_all(self.pool)
-        .await?;
-        Ok(wallets)
+        );
+        page.push_sort(&mut query_builder);
+        page.push_limit(&mut query_builder);
+
+        let wallets = query_builder
+            .build_query_as::<WalletDb>()
+            .fetch_all(self.pool)
+            .await?;
+        Ok((wallets, total))
     }
 }
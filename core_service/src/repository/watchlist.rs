@@ -0,0 +1,168 @@
+use crate::models::watchlist::{WatchlistAssetDb, WatchlistDb};
+use anyhow::Result;
+use sqlx::PgPool;
+
+// Repository for watchlist-related database operations
+pub struct WatchlistRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> WatchlistRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Creates a new watchlist with its initial assets in a single transaction
+    pub async fn create(&self, name: String, asset_ids: &[i32]) -> Result<i32> {
+        let mut tx = self.pool.begin().await?;
+        let id = sqlx::query_scalar!(
+            "INSERT INTO watchlists (name) VALUES ($1) RETURNING id",
+            name
+        )
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if !asset_ids.is_empty() {
+            sqlx::query!(
+                r#"
+                INSERT INTO watchlist_assets (watchlist_id, asset_id)
+                SELECT $1, UNNEST($2::INT[])
+                ON CONFLICT DO NOTHING
+                "#,
+                id,
+                asset_ids
+            )
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(id)
+    }
+
+    // Retrieves all watchlists from the database
+    pub async fn get_all(&self) -> Result<Vec<WatchlistDb>> {
+        let watchlists = sqlx::query_as!(
+            WatchlistDb,
+            r#"
+            SELECT
+                w.id,
+                w.name,
+                COALESCE(
+                    ARRAY_AGG(wa.asset_id ORDER BY wa.added_at) FILTER (WHERE wa.asset_id IS NOT NULL),
+                    '{}'
+                ) AS "asset_ids!",
+                w.created_at
+            FROM watchlists w
+            LEFT JOIN watchlist_assets wa ON wa.watchlist_id = w.id
+            GROUP BY w.id
+            ORDER BY w.id ASC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(watchlists)
+    }
+
+    // Retrieves a watchlist by ID
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<WatchlistDb>> {
+        let watchlist = sqlx::query_as!(
+            WatchlistDb,
+            r#"
+            SELECT
+                w.id,
+                w.name,
+                COALESCE(
+                    ARRAY_AGG(wa.asset_id ORDER BY wa.added_at) FILTER (WHERE wa.asset_id IS NOT NULL),
+                    '{}'
+                ) AS "asset_ids!",
+                w.created_at
+            FROM watchlists w
+            LEFT JOIN watchlist_assets wa ON wa.watchlist_id = w.id
+            WHERE w.id = $1
+            GROUP BY w.id
+            "#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(watchlist)
+    }
+
+    // Renames a watchlist, returning false if it does not exist
+    pub async fn rename(&self, id: i32, name: String) -> Result<bool> {
+        let result = sqlx::query!("UPDATE watchlists SET name = $1 WHERE id = $2", name, id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Deletes a watchlist, returning false if it does not exist
+    pub async fn delete(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM watchlists WHERE id = $1", id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Adds an asset to a watchlist, ignoring duplicates
+    pub async fn add_asset(&self, watchlist_id: i32, asset_id: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            INSERT INTO watchlist_assets (watchlist_id, asset_id)
+            VALUES ($1, $2)
+            ON CONFLICT DO NOTHING
+            "#,
+            watchlist_id,
+            asset_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Removes an asset from a watchlist, returning false if it was not there
+    pub async fn remove_asset(&self, watchlist_id: i32, asset_id: i32) -> Result<bool> {
+        let result = sqlx::query!(
+            "DELETE FROM watchlist_assets WHERE watchlist_id = $1 AND asset_id = $2",
+            watchlist_id,
+            asset_id
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Gets the assets of a watchlist with their latest stored price and changes, sorted by rank
+    pub async fn get_assets_with_prices(&self, watchlist_id: i32) -> Result<Vec<WatchlistAssetDb>> {
+        let assets = sqlx::query_as!(
+            WatchlistAssetDb,
+            r#"
+            SELECT
+                a.id AS asset_id,
+                a.cmc_id,
+                a.symbol,
+                a.name,
+                lp.price_usd AS "price_usd?",
+                lp.percent_change_24h AS "percent_change_24h?",
+                lp.percent_change_7d AS "percent_change_7d?",
+                lp.timestamp AS "timestamp?"
+            FROM watchlist_assets wa
+            JOIN assets a ON a.id = wa.asset_id
+            LEFT JOIN LATERAL (
+                SELECT price_usd, percent_change_24h, percent_change_7d, timestamp
+                FROM asset_prices
+                WHERE asset_id = a.id
+                ORDER BY timestamp DESC
+                LIMIT 1
+            ) lp ON TRUE
+            WHERE wa.watchlist_id = $1
+            ORDER BY a.rank ASC NULLS LAST, a.id ASC
+            "#,
+            watchlist_id
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(assets)
+    }
+}
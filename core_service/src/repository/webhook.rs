@@ -0,0 +1,289 @@
+use crate::models::pagination::PageParams;
+use crate::models::webhook::{
+    DeliverySort, DueDeliveryDb, WebhookDeliveryDb, WebhookSubscriptionDb,
+};
+use anyhow::Result;
+use sqlx::types::{Json, Uuid};
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+// Repository for webhook subscriptions and their delivery outbox
+pub struct WebhookRepository<'a> {
+    pool: &'a PgPool,
+}
+
+impl<'a> WebhookRepository<'a> {
+    pub fn new(pool: &'a PgPool) -> Self {
+        Self { pool }
+    }
+
+    // Creates a new subscription
+    pub async fn create(
+        &self,
+        url: String,
+        secret: String,
+        event_types: Vec<String>,
+        description: Option<String>,
+    ) -> Result<WebhookSubscriptionDb> {
+        // The secret is never read back
+        let subscription = sqlx::query_as!(
+            WebhookSubscriptionDb,
+            r#"
+            INSERT INTO webhook_subscriptions (url, secret, event_types, description)
+            VALUES ($1, $2, $3, $4)
+            RETURNING id, url, event_types, description, active, created_at, updated_at
+            "#,
+            url,
+            secret,
+            &event_types,
+            description
+        )
+        .fetch_one(self.pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    // Retrieves all subscriptions
+    pub async fn get_all(&self) -> Result<Vec<WebhookSubscriptionDb>> {
+        let subscriptions = sqlx::query_as!(
+            WebhookSubscriptionDb,
+            r#"
+            SELECT id, url, event_types, description, active, created_at, updated_at
+            FROM webhook_subscriptions
+            ORDER BY id ASC
+            "#
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(subscriptions)
+    }
+
+    // Retrieves a subscription by ID
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<WebhookSubscriptionDb>> {
+        let subscription = sqlx::query_as!(
+            WebhookSubscriptionDb,
+            r#"
+            SELECT id, url, event_types, description, active, created_at, updated_at
+            FROM webhook_subscriptions
+            WHERE id = $1
+            "#,
+            id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    // Updates the given fields of a subscription, returning None if it does not exist
+    pub async fn update(
+        &self,
+        id: i32,
+        url: Option<String>,
+        secret: Option<String>,
+        event_types: Option<Vec<String>>,
+        description: Option<String>,
+        active: Option<bool>,
+    ) -> Result<Option<WebhookSubscriptionDb>> {
+        let subscription = sqlx::query_as!(
+            WebhookSubscriptionDb,
+            r#"
+            UPDATE webhook_subscriptions
+            SET url = COALESCE($2, url),
+                secret = COALESCE($3, secret),
+                event_types = COALESCE($4, event_types),
+                description = COALESCE($5, description),
+                active = COALESCE($6, active),
+                updated_at = CURRENT_TIMESTAMP
+            WHERE id = $1
+            RETURNING id, url, event_types, description, active, created_at, updated_at
+            "#,
+            id,
+            url,
+            secret,
+            event_types.as_deref(),
+            description,
+            active
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(subscription)
+    }
+
+    // Deletes a subscription and its deliveries, returning false if it does not exist
+    pub async fn delete(&self, id: i32) -> Result<bool> {
+        let result = sqlx::query!("DELETE FROM webhook_subscriptions WHERE id = $1", id)
+            .execute(self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Queues a delivery of an event for every active subscription to its type, returning the
+    // number of deliveries queued
+    pub async fn enqueue(
+        &self,
+        event_id: Uuid,
+        event_type: &str,
+        payload: serde_json::Value,
+    ) -> Result<u64> {
+        let result = sqlx::query!(
+            r#"
+            INSERT INTO webhook_deliveries (subscription_id, event_id, event_type, payload)
+            SELECT id, $1::UUID, $2::TEXT, $3::JSONB
+            FROM webhook_subscriptions
+            WHERE active AND (CARDINALITY(event_types) = 0 OR $2::TEXT = ANY(event_types))
+            "#,
+            event_id,
+            event_type,
+            payload
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(result.rows_affected())
+    }
+
+    // Claims up to limit due deliveries of active subscriptions. Claimed deliveries are not due
+    // again for lease_secs, so a delivery interrupted by a crash is retried after the lease.
+    pub async fn claim_due(&self, limit: i64, lease_secs: i64) -> Result<Vec<DueDeliveryDb>> {
+        let deliveries = sqlx::query_as!(
+            DueDeliveryDb,
+            r#"
+            WITH due AS (
+                SELECT d.id
+                FROM webhook_deliveries d
+                JOIN webhook_subscriptions s ON s.id = d.subscription_id
+                WHERE d.status = 'pending' AND d.next_attempt_at <= CURRENT_TIMESTAMP AND s.active
+                ORDER BY d.next_attempt_at, d.id
+                LIMIT $1
+                FOR UPDATE OF d SKIP LOCKED
+            )
+            UPDATE webhook_deliveries d
+            SET next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => $2)
+            FROM due, webhook_subscriptions s
+            WHERE d.id = due.id AND s.id = d.subscription_id
+            RETURNING d.id, d.event_id, d.event_type,
+                d.payload AS "payload: Json<serde_json::Value>", d.attempts, d.created_at,
+                s.url, s.secret
+            "#,
+            limit,
+            lease_secs as f64
+        )
+        .fetch_all(self.pool)
+        .await?;
+        Ok(deliveries)
+    }
+
+    // Records a successful attempt
+    pub async fn mark_delivered(&self, id: i64, response_status: i32) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'delivered',
+                attempts = attempts + 1,
+                last_attempt_at = CURRENT_TIMESTAMP,
+                delivered_at = CURRENT_TIMESTAMP,
+                response_status = $2,
+                last_error = NULL
+            WHERE id = $1
+            "#,
+            id,
+            response_status
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Records a failed attempt, scheduling a retry after retry_in_secs or giving up when None
+    pub async fn mark_attempt_failed(
+        &self,
+        id: i64,
+        response_status: Option<i32>,
+        error: String,
+        retry_in_secs: Option<i64>,
+    ) -> Result<()> {
+        sqlx::query!(
+            r#"
+            UPDATE webhook_deliveries
+            SET status = CASE WHEN $4::FLOAT8 IS NULL THEN 'failed' ELSE 'pending' END,
+                attempts = attempts + 1,
+                last_attempt_at = CURRENT_TIMESTAMP,
+                next_attempt_at = CURRENT_TIMESTAMP + make_interval(secs => COALESCE($4, 0)),
+                response_status = $2,
+                last_error = $3
+            WHERE id = $1
+            "#,
+            id,
+            response_status,
+            error,
+            retry_in_secs.map(|secs| secs as f64)
+        )
+        .execute(self.pool)
+        .await?;
+        Ok(())
+    }
+
+    // Retrieves a page of the deliveries of a subscription with their total count, optionally
+    // filtered by status
+    pub async fn get_deliveries(
+        &self,
+        subscription_id: i32,
+        status: Option<String>,
+        page: &PageParams<DeliverySort>,
+    ) -> Result<(Vec<WebhookDeliveryDb>, i64)> {
+        let total = sqlx::query_scalar!(
+            r#"
+            SELECT COUNT(*) AS "count!"
+            FROM webhook_deliveries
+            WHERE subscription_id = $1 AND ($2::TEXT IS NULL OR status = $2)
+            "#,
+            subscription_id,
+            status
+        )
+        .fetch_one(self.pool)
+        .await?;
+
+        let mut query_builder: QueryBuilder<Postgres> =
+            QueryBuilder::new("SELECT * FROM webhook_deliveries WHERE subscription_id = ");
+        query_builder.push_bind(subscription_id);
+        if let Some(status) = status {
+            query_builder.push(" AND status = ");
+            query_builder.push_bind(status);
+        }
+        query_builder.push(" ORDER BY ");
+        page.push_sort(&mut query_builder);
+        page.push_limit(&mut query_builder);
+
+        let deliveries = query_builder
+            .build_query_as::<WebhookDeliveryDb>()
+            .fetch_all(self.pool)
+            .await?;
+        Ok((deliveries, total))
+    }
+
+    // Makes a delivery of a subscription due immediately with a fresh set of attempts,
+    // returning None if it does not exist
+    pub async fn retry(
+        &self,
+        subscription_id: i32,
+        delivery_id: i64,
+    ) -> Result<Option<WebhookDeliveryDb>> {
+        let delivery = sqlx::query_as!(
+            WebhookDeliveryDb,
+            r#"
+            UPDATE webhook_deliveries
+            SET status = 'pending',
+                attempts = 0,
+                next_attempt_at = CURRENT_TIMESTAMP,
+                delivered_at = NULL
+            WHERE id = $1 AND subscription_id = $2
+            RETURNING id, subscription_id, event_id, event_type,
+                payload AS "payload: Json<serde_json::Value>", status, attempts, next_attempt_at,
+                last_attempt_at, response_status, last_error, created_at, delivered_at
+            "#,
+            delivery_id,
+            subscription_id
+        )
+        .fetch_optional(self.pool)
+        .await?;
+        Ok(delivery)
+    }
+}
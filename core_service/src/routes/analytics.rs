@@ -0,0 +1,63 @@
+use crate::dto::backtest::{BacktestRequestDto, BacktestResultDto};
+use crate::dto::scenario::{ScenarioRequestDto, ScenarioResultDto};
+use crate::error::{AppError, ProblemDetails};
+use crate::services::backtest::BacktestService;
+use crate::services::scenario::ScenarioService;
+use actix_web::{web, HttpResponse, Responder};
+use actix_web_validator::Json;
+use anyhow::Result;
+
+// Configures routes for the /analytics scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/analytics")
+            .route("/backtest", web::post().to(run_backtest))
+            .route("/scenario", web::post().to(run_scenario)),
+    );
+}
+
+// Handles POST /analytics/backtest to replay a strategy against the stored price history
+#[utoipa::path(
+    post,
+    path = "/analytics/backtest",
+    request_body(
+        content = BacktestRequestDto,
+        description = "Strategy, assets with target weights, period, starting capital and fee assumptions. 'dca' buys at dca_frequency, 'lump_sum' invests everything at the start and 'rebalance' also restores the weights when one drifts by more than rebalance_threshold. Trades are simulated at the latest stored price at each point; no transactions are created.",
+        example = json!({"strategy": "rebalance", "assets": [{"asset_id": 1, "weight": 0.6}, {"asset_id": 2, "weight": 0.4}], "start_date": "2024-01-01T00:00:00", "end_date": "2025-01-01T00:00:00", "initial_capital_usd": 10000.0, "fee_rate": 0.001, "fixed_fee_usd": 0.0, "interval": "daily", "rebalance_threshold": 0.05})
+    ),
+    responses(
+        (status = 200, description = "Backtest completed successfully", body = BacktestResultDto, example = json!({"strategy": "rebalance", "start_date": "2024-01-01T00:00:00", "end_date": "2025-01-01T00:00:00", "initial_capital_usd": 10000.0, "final_value_usd": 21450.3, "cash_usd": 1.2, "invested_usd": 14820.5, "total_fees_usd": 14.8, "trades": 18, "rebalances": 8, "total_return_pct": 114.5, "annualized_return_pct": 114.2, "max_drawdown_pct": 21.7, "volatility_pct": 48.3, "assets": [{"asset_id": 1, "symbol": "BTC", "target_weight": 0.6, "amount": 0.1382, "price_usd": 93400.0, "value_usd": 12907.9, "weight": 0.6018}, {"asset_id": 2, "symbol": "ETH", "target_weight": 0.4, "amount": 2.5615, "price_usd": 3334.5, "value_usd": 8541.2, "weight": 0.3982}], "equity_curve": [{"timestamp": "2024-01-01T00:00:00", "value_usd": 9990.0, "cash_usd": 0.0}, {"timestamp": "2024-01-02T00:00:00", "value_usd": 10120.4, "cash_usd": 0.0}]})),
+        (status = 400, description = "Invalid request data (e.g., unknown asset, too many points or no stored prices)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: assets: No stored prices of SOL up to end_date", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "assets", "message": "No stored prices of SOL up to end_date"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn run_backtest(
+    backtest_service: web::Data<BacktestService>,
+    request: Json<BacktestRequestDto>,
+) -> Result<impl Responder, AppError> {
+    let response = backtest_service.run(request.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles POST /analytics/scenario to value the current holdings under hypothetical prices
+#[utoipa::path(
+    post,
+    path = "/analytics/scenario",
+    request_body(
+        content = ScenarioRequestDto,
+        description = "Per-asset overrides of the latest stored price, each either a percentage change_pct or an absolute price_usd, and an optional shock_pct applied to every other held asset",
+        example = json!({"overrides": [{"asset_id": 1, "change_pct": -30.0}, {"asset_id": 2, "change_pct": -40.0}], "shock_pct": -20.0})
+    ),
+    responses(
+        (status = 200, description = "Scenario valued successfully", body = ScenarioResultDto, example = json!({"current_value_usd": 50000.0, "scenario_value_usd": 34000.0, "change_usd": -16000.0, "change_pct": -32.0, "assets": [{"asset_id": 1, "symbol": "BTC", "amount": 0.5, "adjustment": "change_pct", "current_price_usd": 60000.0, "scenario_price_usd": 42000.0, "current_value_usd": 30000.0, "scenario_value_usd": 21000.0, "change_usd": -9000.0, "change_pct": -30.0, "current_weight": 0.6, "scenario_weight": 0.6176}, {"asset_id": 2, "symbol": "ETH", "amount": 5.0, "adjustment": "change_pct", "current_price_usd": 3000.0, "scenario_price_usd": 1800.0, "current_value_usd": 15000.0, "scenario_value_usd": 9000.0, "change_usd": -6000.0, "change_pct": -40.0, "current_weight": 0.3, "scenario_weight": 0.2647}, {"asset_id": 3, "symbol": "SOL", "amount": 40.0, "adjustment": "shock", "current_price_usd": 125.0, "scenario_price_usd": 100.0, "current_value_usd": 5000.0, "scenario_value_usd": 4000.0, "change_usd": -1000.0, "change_pct": -20.0, "current_weight": 0.1, "scenario_weight": 0.1176}]})),
+        (status = 400, description = "Invalid request data (e.g., asset not held or both change_pct and price_usd set)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: overrides[0].asset_id: Asset 7 is not held", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "overrides[0].asset_id", "message": "Asset 7 is not held"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn run_scenario(
+    scenario_service: web::Data<ScenarioService>,
+    request: Json<ScenarioRequestDto>,
+) -> Result<impl Responder, AppError> {
+    let response = scenario_service.run(request.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
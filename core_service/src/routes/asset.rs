@@ -1,9 +1,16 @@
 use crate::dto::asset::{
-    AssetDto, AssetPriceHistoryDto, AssetPriceWithDetailsDto, CreateAssetDto, UpdateAssetsResponse,
+    AssetDto, AssetPriceHistoryDto, AssetPriceWithDetailsDto, CreateAssetDto, RefreshPricesDto,
+    RefreshPricesResponse, SymbolCollisionDto, UpdateAssetsResponse,
 };
-use crate::error::AppError;
-use crate::models::asset::{HistoryQueryParams, PriceQueryParams};
+use crate::dto::pagination::PageDto;
+use crate::error::{AppError, ProblemDetails};
+use crate::models::asset::{
+    AssetQueryParams, AssetSort, CollisionQueryParams, HistoryQueryParams, PriceHistorySort,
+    PriceQueryParams,
+};
+use crate::models::pagination::PageParams;
 use crate::services::asset::AssetService;
+use crate::services::price_refresh::PriceRefreshService;
 use actix_web::{web, HttpResponse, Responder};
 use actix_web_validator::{Json, Query};
 use anyhow::Result;
@@ -16,24 +23,68 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
             .route("", web::post().to(create_asset))
             .route("/update", web::post().to(update_assets))
             .route("/prices", web::get().to(get_asset_prices))
-            .route("/prices/history", web::get().to(get_price_history)),
+            .route("/prices/history", web::get().to(get_price_history))
+            .route("/prices/refresh", web::post().to(refresh_asset_prices))
+            .route("/collisions", web::get().to(get_symbol_collisions))
+            .route("/{id}", web::get().to(get_asset)),
     );
 }
 
-// Handles GET /assets to retrieve all assets
+// Handles GET /assets to search, filter, sort and paginate assets
 #[utoipa::path(
     get,
     path = "/assets",
+    params(
+        ("search" = Option<String>, Query, description = "Case-insensitive symbol or name prefix; exact symbol matches rank first (e.g., 'bt')"),
+        ("min_rank" = Option<i32>, Query, description = "Minimum CoinMarketCap rank (inclusive)"),
+        ("max_rank" = Option<i32>, Query, description = "Maximum CoinMarketCap rank (inclusive)"),
+        ("held" = Option<bool>, Query, description = "Only assets with (true) or without (false) a positive holding"),
+        ("tracked" = Option<bool>, Query, description = "Only assets on (true) or off (false) any watchlist"),
+        ("sort" = Option<String>, Query, description = "Sort field: 'rank', 'symbol' or 'created_at' (default: match quality when searching, otherwise rank)"),
+        ("order" = Option<String>, Query, description = "Sort order: 'asc' or 'desc' (default: asc)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of assets to return (default: 50, max: 500)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the page to return, taken from `next_cursor` of the previous page")
+    ),
     responses(
-        (status = 200, description = "Successfully retrieved list of assets", body = Vec<AssetDto>, example = json!([{"id": 1, "symbol": "BTC", "name": "Bitcoin", "cmc_id": 1, "decimals": 8, "rank": 1, "created_at": "2024-01-01T00:00:00"}])),
-        (status = 500, description = "Internal server error (e.g., database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Database connection failed"}))
+        (status = 200, description = "Successfully retrieved page of assets", body = PageDto<AssetDto>, example = json!({"items": [{"id": 1, "symbol": "BTC", "name": "Bitcoin", "cmc_id": 1, "decimals": 8, "rank": 1, "slug": "bitcoin", "tags": ["mineable", "pow"], "platform": null, "circulating_supply": 19850000.0, "total_supply": 19850000.0, "max_supply": 21000000.0, "date_added": "2010-07-13T00:00:00Z", "metadata_updated_at": "2025-03-12T12:00:00Z", "created_at": "2024-01-01T00:00:00"}], "total": 120, "next_cursor": "bzo1MA"})),
+        (status = 400, description = "Invalid query parameters", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: sort: Sort must be one of 'rank', 'symbol', 'created_at'", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "sort", "message": "Sort must be one of 'rank', 'symbol', 'created_at'"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
-async fn get_assets(asset_service: web::Data<AssetService>) -> Result<impl Responder, AppError> {
-    let assets = asset_service.get_all().await.map_err(AppError::internal)?;
+async fn get_assets(
+    asset_service: web::Data<AssetService>,
+    query: Query<AssetQueryParams>,
+    page: PageParams<AssetSort>,
+) -> Result<impl Responder, AppError> {
+    let assets = asset_service.get_all(query.into_inner(), page).await?;
     Ok(HttpResponse::Ok().json(assets))
 }
 
+// Handles GET /assets/{id} to retrieve an asset with its CoinMarketCap metadata
+#[utoipa::path(
+    get,
+    path = "/assets/{id}",
+    params(
+        ("id" = i32, Path, description = "Asset ID")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved asset details", body = AssetDto, example = json!({"id": 7, "symbol": "UNI", "name": "Uniswap", "cmc_id": 7083, "decimals": null, "rank": 20, "slug": "uniswap", "tags": ["defi", "dex"], "platform": {"name": "Ethereum", "symbol": "ETH", "token_address": "0x1f9840a85d5af5bf1d1762f925bdaddc4201f984"}, "circulating_supply": 600000000.0, "total_supply": 1000000000.0, "max_supply": 1000000000.0, "date_added": "2020-09-17T00:00:00Z", "metadata_updated_at": "2025-03-12T12:00:00Z", "created_at": "2024-01-01T00:00:00"})),
+        (status = 404, description = "Asset not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Asset 7 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_asset(
+    asset_service: web::Data<AssetService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let id = path.into_inner();
+    let asset = asset_service
+        .get_by_id(id)
+        .await?
+        .ok_or_else(|| AppError::not_found(format!("Asset {} not found", id)))?;
+    Ok(HttpResponse::Ok().json(asset))
+}
+
 // Handles POST /assets to create a new asset
 #[utoipa::path(
     post,
@@ -45,18 +96,16 @@ async fn get_assets(asset_service: web::Data<AssetService>) -> Result<impl Respo
     ),
     responses(
         (status = 200, description = "Asset created successfully", body = AssetDto, example = json!({"id": 1, "symbol": "BTC", "name": "Bitcoin", "cmc_id": 1, "decimals": 8, "rank": 1, "created_at": "2024-01-01T00:00:00"})),
-        (status = 400, description = "Invalid request data (e.g., missing required fields)", body = String, example = json!({"status": 400, "error": "Bad Request", "message": "Validation error: Symbol must not be empty"})),
-        (status = 500, description = "Internal server error (e.g., database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Failed to insert asset into database"}))
+        (status = 400, description = "Invalid request data (e.g., missing required fields)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: symbol: Symbol must not be empty", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "symbol", "message": "Symbol must not be empty"}]})),
+        (status = 409, description = "An asset with the same CoinMarketCap ID already exists", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:conflict", "title": "Conflict", "status": 409, "detail": "Key (cmc_id)=(1) already exists.", "code": "conflict", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn create_asset(
     asset_service: web::Data<AssetService>,
     asset: Json<CreateAssetDto>,
 ) -> Result<impl Responder, AppError> {
-    let response = asset_service
-        .create(asset.into_inner())
-        .await
-        .map_err(AppError::internal)?;
+    let response = asset_service.create(asset.into_inner()).await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -66,11 +115,13 @@ async fn create_asset(
     path = "/assets/update",
     responses(
         (status = 200, description = "Assets updated successfully from CoinMarketCap", body = UpdateAssetsResponse, example = json!({"updated_count": 1000, "updated_at": "2025-03-07T12:00:00Z"})),
-        (status = 500, description = "Internal server error (e.g., CoinMarketCap API or database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Failed to fetch listings from CoinMarketCap"}))
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 502, description = "CoinMarketCap returned an error or an invalid response", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:upstream_failed", "title": "Bad Gateway", "status": 502, "detail": "CoinMarketCap API error 1002: API key missing.", "code": "upstream_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 503, description = "CoinMarketCap is unavailable, rate limited or the daily credit budget is exhausted", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:upstream_unavailable", "title": "Service Unavailable", "status": 503, "detail": "CoinMarketCap request timed out", "code": "upstream_unavailable", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn update_assets(asset_service: web::Data<AssetService>) -> Result<impl Responder, AppError> {
-    let response = asset_service.update().await.map_err(AppError::internal)?;
+    let response = asset_service.update().await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -83,43 +134,89 @@ async fn update_assets(asset_service: web::Data<AssetService>) -> Result<impl Re
     ),
     responses(
         (status = 200, description = "Successfully retrieved latest asset prices with details", body = Vec<AssetPriceWithDetailsDto>, example = json!([{"cmc_id": 1, "symbol": "BTC", "name": "Bitcoin", "price_usd": 60000.0, "timestamp": "2025-03-08T12:00:00Z"}, {"cmc_id": 1027, "symbol": "ETH", "name": "Ethereum", "price_usd": 3000.0, "timestamp": "2025-03-08T12:00:00Z"}])),
-        (status = 500, description = "Internal server error (e.g., database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Database connection failed"}))
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn get_asset_prices(
     asset_service: web::Data<AssetService>,
     query: Query<PriceQueryParams>,
 ) -> Result<impl Responder, AppError> {
-    let response = asset_service
-        .get_prices(query.into_inner())
-        .await
-        .map_err(AppError::internal)?;
+    let response = asset_service.get_prices(query.into_inner()).await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
-// Handles GET /assets/prices/history to retrieve history asset prices with details
+// Handles GET /assets/prices/history to retrieve a page of historical asset prices
 #[utoipa::path(
     get,
     path = "/assets/prices/history",
     params(
         ("asset_ids", Query, description = "Comma-separated list of asset IDs to filter by (e.g., 1,2)", example = "1,2"),
         ("start_date", Query, description = "Start date in ISO 8601 format (e.g., 2025-03-01T00:00:00Z)", example = "2025-03-01T00:00:00Z"),
-        ("end_date", Query, description = "End date in ISO 8601 format (optional, defaults to now)", example = "2025-03-08T00:00:00Z")
+        ("end_date", Query, description = "End date in ISO 8601 format (optional, defaults to now)", example = "2025-03-08T00:00:00Z"),
+        ("sort" = Option<String>, Query, description = "Sort field: 'timestamp' (default)"),
+        ("order" = Option<String>, Query, description = "Sort order: 'asc' or 'desc' (default: asc)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of prices to return (default: 50, max: 500)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the page to return, taken from `next_cursor` of the previous page")
     ),
     responses(
-        (status = 200, description = "Successfully retrieved historical asset prices", body = Vec<AssetPriceHistoryDto>, example = json!([{"cmc_id": 1, "symbol": "BTC", "price_usd": 59000.0, "timestamp": "2025-03-01T00:00:00Z"}, {"cmc_id": 1, "symbol": "BTC", "price_usd": 60000.0, "timestamp": "2025-03-08T00:00:00Z"}])),
-        (status = 400, description = "Invalid date format", body = String, example = json!({"status": 400, "error": "Bad Request", "message": "Invalid start_date format"})),
-        (status = 500, description = "Internal server error", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Database connection failed"}))
+        (status = 200, description = "Successfully retrieved page of historical asset prices", body = PageDto<AssetPriceHistoryDto>, example = json!({"items": [{"cmc_id": 1, "symbol": "BTC", "price_usd": 59000.0, "timestamp": "2025-03-01T00:00:00Z"}, {"cmc_id": 1, "symbol": "BTC", "price_usd": 60000.0, "timestamp": "2025-03-08T00:00:00Z"}], "total": 2, "next_cursor": null})),
+        (status = 400, description = "Invalid date format", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: start_date: Invalid ISO 8601 date format", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "start_date", "message": "Invalid ISO 8601 date format"}]})),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn get_price_history(
     asset_service: web::Data<AssetService>,
     query: Query<HistoryQueryParams>,
+    page: PageParams<PriceHistorySort>,
 ) -> Result<impl Responder, AppError> {
     let response = asset_service
-        .get_price_history(query.into_inner())
-        .await
-        .map_err(AppError::internal)?;
+        .get_price_history(query.into_inner(), page)
+        .await?;
+
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles POST /assets/prices/refresh to fetch the latest prices of specific assets immediately
+#[utoipa::path(
+    post,
+    path = "/assets/prices/refresh",
+    request_body = RefreshPricesDto,
+    responses(
+        (status = 200, description = "Prices refreshed from CoinMarketCap", body = RefreshPricesResponse, example = json!({"refreshed_count": 2, "unknown_asset_ids": [999], "missing_price_asset_ids": [], "refreshed_at": "2025-03-15T12:00:00Z"})),
+        (status = 400, description = "Invalid input data", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: asset_ids: Between 1 and 100 asset IDs are required", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "asset_ids", "message": "Between 1 and 100 asset IDs are required"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 502, description = "CoinMarketCap returned an error or an invalid response", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:upstream_failed", "title": "Bad Gateway", "status": 502, "detail": "CoinMarketCap API error 1002: API key missing.", "code": "upstream_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 503, description = "CoinMarketCap is unavailable, rate limited or the daily credit budget is exhausted", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:upstream_unavailable", "title": "Service Unavailable", "status": 503, "detail": "CoinMarketCap request timed out", "code": "upstream_unavailable", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn refresh_asset_prices(
+    price_refresh_service: web::Data<PriceRefreshService>,
+    body: Json<RefreshPricesDto>,
+) -> Result<impl Responder, AppError> {
+    let response = price_refresh_service
+        .refresh_assets(&body.asset_ids)
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
 
+// Handles GET /assets/collisions to list symbols shared by several assets
+#[utoipa::path(
+    get,
+    path = "/assets/collisions",
+    params(
+        ("held" = Option<bool>, Query, description = "Only return collisions involving at least one held asset")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved symbols shared by several assets", body = Vec<SymbolCollisionDto>, example = json!([{"symbol": "UNI", "assets": [{"asset_id": 7, "cmc_id": 7083, "name": "Uniswap", "rank": 20, "held_amount": 10.0}, {"asset_id": 912, "cmc_id": 9999, "name": "Universe", "rank": 900, "held_amount": 0.0}]}])),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_symbol_collisions(
+    asset_service: web::Data<AssetService>,
+    query: Query<CollisionQueryParams>,
+) -> Result<impl Responder, AppError> {
+    let response = asset_service
+        .get_symbol_collisions(query.into_inner())
+        .await?;
     Ok(HttpResponse::Ok().json(response))
 }
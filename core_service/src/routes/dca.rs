@@ -0,0 +1,244 @@
+use crate::dto::dca::{
+    ConfirmDcaExecutionDto, CreateDcaPlanDto, DcaExecutionDto, DcaPlanDto, DcaUpcomingExecutionDto,
+    UpdateDcaPlanDto,
+};
+use crate::dto::pagination::PageDto;
+use crate::error::{AppError, ProblemDetails};
+use crate::models::dca::{ExecutionQueryParams, ExecutionSort, UpcomingParams};
+use crate::models::pagination::PageParams;
+use crate::services::dca::DcaService;
+use actix_web::{web, HttpResponse, Responder};
+use actix_web_validator::{Json, Query};
+use anyhow::Result;
+
+// Configures routes for the /dca scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/dca")
+            .route("/plans", web::get().to(get_dca_plans))
+            .route("/plans", web::post().to(create_dca_plan))
+            .route("/plans/{id}", web::get().to(get_dca_plan))
+            .route("/plans/{id}", web::put().to(update_dca_plan))
+            .route("/plans/{id}", web::delete().to(delete_dca_plan))
+            .route("/executions", web::get().to(get_dca_executions))
+            .route(
+                "/executions/{id}/confirm",
+                web::post().to(confirm_dca_execution),
+            )
+            .route("/executions/{id}/skip", web::post().to(skip_dca_execution))
+            .route("/upcoming", web::get().to(get_upcoming_dca_executions)),
+    );
+}
+
+// Handles GET /dca/plans to retrieve all DCA plans
+#[utoipa::path(
+    get,
+    path = "/dca/plans",
+    responses(
+        (status = 200, description = "Successfully retrieved list of DCA plans", body = Vec<DcaPlanDto>, example = json!([{"id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount_usd": 100.0, "frequency": "weekly", "start_date": "2025-03-03T09:00:00", "end_date": null, "next_execution_at": "2025-03-24T09:00:00", "active": true, "notes": "Weekly BTC buy", "pending_executions": 1, "latest_price_usd": 84000.0, "created_at": "2025-03-01T12:00:00", "updated_at": "2025-03-01T12:00:00"}])),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_dca_plans(dca_service: web::Data<DcaService>) -> Result<impl Responder, AppError> {
+    let plans = dca_service.get_plans().await?;
+    Ok(HttpResponse::Ok().json(plans))
+}
+
+// Handles POST /dca/plans to create a DCA plan
+#[utoipa::path(
+    post,
+    path = "/dca/plans",
+    request_body(
+        content = CreateDcaPlanDto,
+        description = "Asset, wallet, USD amount and schedule of the plan. Executions scheduled before the plan is created are not generated.",
+        example = json!({"asset_id": 1, "wallet_id": 1, "amount_usd": 100.0, "frequency": "weekly", "start_date": "2025-03-03T09:00:00", "end_date": null, "notes": "Weekly BTC buy"})
+    ),
+    responses(
+        (status = 200, description = "DCA plan created successfully", body = DcaPlanDto, example = json!({"id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount_usd": 100.0, "frequency": "weekly", "start_date": "2025-03-03T09:00:00", "end_date": null, "next_execution_at": "2025-03-24T09:00:00", "active": true, "notes": "Weekly BTC buy", "pending_executions": 0, "latest_price_usd": 84000.0, "created_at": "2025-03-01T12:00:00", "updated_at": "2025-03-01T12:00:00"})),
+        (status = 400, description = "Invalid request data (e.g., unknown asset or frequency)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: frequency: Frequency must be one of 'daily', 'weekly', 'biweekly' or 'monthly'", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "frequency", "message": "Frequency must be one of 'daily', 'weekly', 'biweekly' or 'monthly'"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn create_dca_plan(
+    dca_service: web::Data<DcaService>,
+    plan: Json<CreateDcaPlanDto>,
+) -> Result<impl Responder, AppError> {
+    let response = dca_service.create_plan(plan.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles GET /dca/plans/{id} to retrieve a DCA plan
+#[utoipa::path(
+    get,
+    path = "/dca/plans/{id}",
+    params(
+        ("id" = i32, Path, description = "DCA plan ID")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved DCA plan", body = DcaPlanDto, example = json!({"id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount_usd": 100.0, "frequency": "weekly", "start_date": "2025-03-03T09:00:00", "end_date": null, "next_execution_at": "2025-03-24T09:00:00", "active": true, "notes": "Weekly BTC buy", "pending_executions": 1, "latest_price_usd": 84000.0, "created_at": "2025-03-01T12:00:00", "updated_at": "2025-03-01T12:00:00"})),
+        (status = 404, description = "DCA plan not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "DCA plan 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_dca_plan(
+    dca_service: web::Data<DcaService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let response = dca_service.get_plan(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles PUT /dca/plans/{id} to update, pause or resume a DCA plan
+#[utoipa::path(
+    put,
+    path = "/dca/plans/{id}",
+    params(
+        ("id" = i32, Path, description = "DCA plan ID")
+    ),
+    request_body(
+        content = UpdateDcaPlanDto,
+        description = "Fields to change; omitted fields are left unchanged, a null end_date makes the plan open-ended and null notes are removed. Changing the frequency or resuming a paused plan reschedules it from now on.",
+        example = json!({"amount_usd": 150.0})
+    ),
+    responses(
+        (status = 200, description = "DCA plan updated successfully", body = DcaPlanDto, example = json!({"id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount_usd": 150.0, "frequency": "weekly", "start_date": "2025-03-03T09:00:00", "end_date": null, "next_execution_at": "2025-03-24T09:00:00", "active": true, "notes": "Weekly BTC buy", "pending_executions": 1, "latest_price_usd": 84000.0, "created_at": "2025-03-01T12:00:00", "updated_at": "2025-03-18T12:00:00"})),
+        (status = 400, description = "Invalid request data", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: end_date: end_date must not be before start_date", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "end_date", "message": "end_date must not be before start_date"}]})),
+        (status = 404, description = "DCA plan not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "DCA plan 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn update_dca_plan(
+    dca_service: web::Data<DcaService>,
+    path: web::Path<i32>,
+    plan: Json<UpdateDcaPlanDto>,
+) -> Result<impl Responder, AppError> {
+    let response = dca_service
+        .update_plan(path.into_inner(), plan.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles DELETE /dca/plans/{id} to delete a DCA plan
+#[utoipa::path(
+    delete,
+    path = "/dca/plans/{id}",
+    params(
+        ("id" = i32, Path, description = "DCA plan ID")
+    ),
+    responses(
+        (status = 204, description = "DCA plan and its executions deleted; transactions of confirmed executions are kept"),
+        (status = 404, description = "DCA plan not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "DCA plan 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn delete_dca_plan(
+    dca_service: web::Data<DcaService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    dca_service.delete_plan(path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Handles GET /dca/executions to retrieve executions generated from DCA plans
+#[utoipa::path(
+    get,
+    path = "/dca/executions",
+    params(
+        ("plan_id" = Option<i32>, Query, description = "Filter by DCA plan ID"),
+        ("status" = Option<String>, Query, description = "Filter by status: pending, confirmed or skipped"),
+        ("sort" = Option<String>, Query, description = "Sort field: 'scheduled_at' (default)"),
+        ("order" = Option<String>, Query, description = "Sort order: 'asc' or 'desc' (default: desc)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of executions to return (default: 50, max: 500)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the page to return, taken from `next_cursor` of the previous page")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved page of executions", body = PageDto<DcaExecutionDto>, example = json!({"items": [{"id": 3, "plan_id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "scheduled_at": "2025-03-17T09:00:00", "amount_usd": 100.0, "price": 84000.0, "amount": 0.00119047619, "status": "pending", "transaction_id": null, "created_at": "2025-03-17T09:00:12", "resolved_at": null}], "total": 1, "next_cursor": null})),
+        (status = 400, description = "Invalid query parameters", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: status: Invalid status 'done', expected one of: pending, confirmed, skipped", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "status", "message": "Invalid status 'done', expected one of: pending, confirmed, skipped"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_dca_executions(
+    dca_service: web::Data<DcaService>,
+    query: Query<ExecutionQueryParams>,
+    page: PageParams<ExecutionSort>,
+) -> Result<impl Responder, AppError> {
+    let response = dca_service.get_executions(query.into_inner(), page).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles POST /dca/executions/{id}/confirm to record a pending execution as a buy transaction
+#[utoipa::path(
+    post,
+    path = "/dca/executions/{id}/confirm",
+    params(
+        ("id" = i32, Path, description = "DCA execution ID")
+    ),
+    request_body(
+        content = ConfirmDcaExecutionDto,
+        description = "Adjustments of the buy; send an empty object to confirm it as generated. Without an amount, the USD amount of the execution is bought at the price.",
+        example = json!({"price": 83500.0, "fee": 0.5})
+    ),
+    responses(
+        (status = 200, description = "Execution confirmed and its transaction created", body = DcaExecutionDto, example = json!({"id": 3, "plan_id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "scheduled_at": "2025-03-17T09:00:00", "amount_usd": 100.0, "price": 84000.0, "amount": 0.00119047619, "status": "confirmed", "transaction_id": 42, "created_at": "2025-03-17T09:00:12", "resolved_at": "2025-03-17T10:30:00"})),
+        (status = 400, description = "Invalid request data (e.g., no price known for the execution)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: price: No positive price of BTC was stored when the execution was generated, so a price is required", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "price", "message": "No positive price of BTC was stored when the execution was generated, so a price is required"}]})),
+        (status = 404, description = "DCA execution not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "DCA execution 3 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 409, description = "Execution already confirmed or skipped", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:conflict", "title": "Conflict", "status": 409, "detail": "DCA execution 3 is no longer pending", "code": "conflict", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn confirm_dca_execution(
+    dca_service: web::Data<DcaService>,
+    path: web::Path<i32>,
+    confirmation: Json<ConfirmDcaExecutionDto>,
+) -> Result<impl Responder, AppError> {
+    let response = dca_service
+        .confirm_execution(path.into_inner(), confirmation.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles POST /dca/executions/{id}/skip to skip a pending execution
+#[utoipa::path(
+    post,
+    path = "/dca/executions/{id}/skip",
+    params(
+        ("id" = i32, Path, description = "DCA execution ID")
+    ),
+    responses(
+        (status = 200, description = "Execution skipped without creating a transaction", body = DcaExecutionDto, example = json!({"id": 3, "plan_id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "scheduled_at": "2025-03-17T09:00:00", "amount_usd": 100.0, "price": 84000.0, "amount": 0.00119047619, "status": "skipped", "transaction_id": null, "created_at": "2025-03-17T09:00:12", "resolved_at": "2025-03-17T10:30:00"})),
+        (status = 404, description = "DCA execution not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "DCA execution 3 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 409, description = "Execution already confirmed or skipped", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:conflict", "title": "Conflict", "status": 409, "detail": "DCA execution 3 is no longer pending", "code": "conflict", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn skip_dca_execution(
+    dca_service: web::Data<DcaService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let response = dca_service.skip_execution(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles GET /dca/upcoming to list the upcoming executions of active DCA plans
+#[utoipa::path(
+    get,
+    path = "/dca/upcoming",
+    params(
+        ("plan_id" = Option<i32>, Query, description = "Only list executions of this DCA plan"),
+        ("days" = Option<i64>, Query, description = "Number of days ahead to list (default: 30, max: 366)"),
+        ("limit" = Option<usize>, Query, description = "Maximum number of executions to return (default: 100, max: 1000)")
+    ),
+    responses(
+        (status = 200, description = "Upcoming executions sorted by time, including due ones not generated yet, estimated at the latest stored prices", body = Vec<DcaUpcomingExecutionDto>, example = json!([{"plan_id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "scheduled_at": "2025-03-24T09:00:00", "amount_usd": 100.0, "estimated_price": 84000.0, "estimated_amount": 0.00119047619}, {"plan_id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "scheduled_at": "2025-03-31T09:00:00", "amount_usd": 100.0, "estimated_price": 84000.0, "estimated_amount": 0.00119047619}])),
+        (status = 400, description = "Invalid query parameters", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: days: Days must be between 1 and 366", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "days", "message": "Days must be between 1 and 366"}]})),
+        (status = 404, description = "DCA plan not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "DCA plan 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_upcoming_dca_executions(
+    dca_service: web::Data<DcaService>,
+    query: Query<UpcomingParams>,
+) -> Result<impl Responder, AppError> {
+    let response = dca_service.get_upcoming(&query).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
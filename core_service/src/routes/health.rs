@@ -0,0 +1,57 @@
+use crate::dto::health::{LivenessDto, ReadinessDto, TaskStatusDto};
+use crate::services::health::HealthService;
+use actix_web::{web, HttpResponse, Responder};
+
+// Configures routes for the /health scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/health")
+            .route("/live", web::get().to(get_liveness))
+            .route("/ready", web::get().to(get_readiness))
+            .route("/tasks", web::get().to(get_tasks)),
+    );
+}
+
+// Handles GET /health/live to report that the process is running
+#[utoipa::path(
+    get,
+    path = "/health/live",
+    responses(
+        (status = 200, description = "The process is running and serving requests", body = LivenessDto, example = json!({"status": "alive"}))
+    )
+)]
+async fn get_liveness() -> impl Responder {
+    HttpResponse::Ok().json(LivenessDto {
+        status: "alive".to_string(),
+    })
+}
+
+// Handles GET /health/ready to report whether the service and its dependencies can serve requests
+#[utoipa::path(
+    get,
+    path = "/health/ready",
+    responses(
+        (status = 200, description = "All critical components are up", body = ReadinessDto, example = json!({"status": "ready", "components": {"database": {"status": "up", "critical": true, "message": null, "latency_ms": 2}, "redis": {"status": "up", "critical": false, "message": null, "latency_ms": 1}, "prices": {"status": "up", "critical": true, "message": null, "latency_ms": null}, "held_price_refresh": {"status": "up", "critical": true, "message": "Last run succeeded at 2025-03-16T12:00:00+00:00", "latency_ms": null}, "tracked_price_refresh": {"status": "up", "critical": true, "message": "Not run yet", "latency_ms": null}}, "checked_at": "2025-03-16T12:00:05+00:00"})),
+        (status = 503, description = "At least one critical component is down", body = ReadinessDto, example = json!({"status": "not_ready", "components": {"database": {"status": "up", "critical": true, "message": null, "latency_ms": 2}, "redis": {"status": "down", "critical": false, "message": "Redis is unavailable, using in-process cache", "latency_ms": 0}, "prices": {"status": "down", "critical": true, "message": "Newest price from 2025-03-16T09:00:00Z is 10805s old (max 1800s)", "latency_ms": null}, "held_price_refresh": {"status": "down", "critical": true, "message": "CoinMarketCap request timed out", "latency_ms": null}, "tracked_price_refresh": {"status": "up", "critical": true, "message": "Not run yet", "latency_ms": null}}, "checked_at": "2025-03-16T12:00:05+00:00"}))
+    )
+)]
+async fn get_readiness(health_service: web::Data<HealthService>) -> impl Responder {
+    let readiness = health_service.check_readiness().await;
+    if readiness.status == "ready" {
+        HttpResponse::Ok().json(readiness)
+    } else {
+        HttpResponse::ServiceUnavailable().json(readiness)
+    }
+}
+
+// Handles GET /health/tasks to report the state of supervised background tasks
+#[utoipa::path(
+    get,
+    path = "/health/tasks",
+    responses(
+        (status = 200, description = "Status of every supervised background task", body = Vec<TaskStatusDto>, example = json!([{"name": "price_refresh_held", "state": "running", "restarts": 1, "started_at": "2025-03-17T12:00:01+00:00", "last_error": "panicked: connection reset", "last_error_at": "2025-03-17T12:00:00+00:00"}]))
+    )
+)]
+async fn get_tasks(health_service: web::Data<HealthService>) -> impl Responder {
+    HttpResponse::Ok().json(health_service.get_tasks())
+}
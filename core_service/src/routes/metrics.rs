@@ -0,0 +1,26 @@
+use crate::error::{AppError, ProblemDetails};
+use crate::services::metrics::MetricsService;
+use actix_web::{web, HttpResponse, Responder};
+
+// Configures the /metrics route
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.route("/metrics", web::get().to(get_metrics));
+}
+
+// Handles GET /metrics to expose metrics in the Prometheus text format
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    responses(
+        (status = 200, description = "Metrics in the Prometheus text exposition format", body = String, content_type = "text/plain", example = "# HELP austeris_portfolio_value_usd Total portfolio value in USD from the latest stored prices\n# TYPE austeris_portfolio_value_usd gauge\nausteris_portfolio_value_usd 25000\n"),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_metrics(
+    metrics_service: web::Data<MetricsService>,
+) -> Result<impl Responder, AppError> {
+    let body = metrics_service.render().await?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/plain; version=0.0.4; charset=utf-8")
+        .body(body))
+}
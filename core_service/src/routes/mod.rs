@@ -1,4 +1,12 @@
+pub mod analytics;
 pub mod asset;
+pub mod dca;
+pub mod health;
+pub mod metrics;
 pub mod snapshots;
+pub mod stream;
+pub mod tag;
 pub mod transaction;
 pub mod wallet;
+pub mod watchlist;
+pub mod webhook;
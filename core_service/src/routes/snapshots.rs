@@ -1,5 +1,8 @@
+use crate::dto::pagination::PageDto;
 use crate::dto::snapshot::SnapshotDto;
-use crate::error::AppError;
+use crate::error::{AppError, ProblemDetails};
+use crate::models::pagination::PageParams;
+use crate::models::snapshot::SnapshotSort;
 use crate::services::snapshot::SnapshotService;
 use actix_web::{web, HttpResponse, Responder};
 use anyhow::Result;
@@ -18,8 +21,8 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     post,
     path = "/snapshots",
     responses(
-        (status = 200, description = "Snapshot created successfully", body = SnapshotDto, example = json!({"id": 1, "created_at": "2025-03-06T14:00:00", "assets": [{"symbol": "BTC", "amount": 1.5, "cmc_id": 1}, {"symbol": "ETH", "amount": 10.0, "cmc_id": 1027}]})),
-        (status = 500, description = "Internal server error (e.g., database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Failed to save snapshot to database"}))
+        (status = 200, description = "Snapshot created successfully", body = SnapshotDto, example = json!({"id": 1, "created_at": "2025-03-06T14:00:00", "assets": [{"asset_id": 1, "symbol": "BTC", "amount": 1.5, "cmc_id": 1}, {"asset_id": 2, "symbol": "ETH", "amount": 10.0, "cmc_id": 1027}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn create_snapshot(
@@ -29,18 +32,26 @@ async fn create_snapshot(
     Ok(HttpResponse::Ok().json(response))
 }
 
-// Handles GET /snapshots to retrieve all snapshots with differences
+// Handles GET /snapshots to retrieve a page of snapshots with differences
 #[utoipa::path(
     get,
     path = "/snapshots",
+    params(
+        ("sort" = Option<String>, Query, description = "Sort field: 'created_at' (default)"),
+        ("order" = Option<String>, Query, description = "Sort order: 'asc' or 'desc' (default: desc)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of snapshots to return (default: 50, max: 500)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the page to return, taken from `next_cursor` of the previous page")
+    ),
     responses(
-        (status = 200, description = "Successfully retrieved list of snapshots with differences", body = Vec<SnapshotDto>, example = json!([{"id": 1, "created_at": "2025-03-06T14:00:00", "assets": [{"symbol": "BTC", "amount": 1.5, "cmc_id": 1}, {"symbol": "ETH", "amount": 10.0, "cmc_id": 1027}], "diff": [{"symbol": "BTC", "amount_diff": -0.5, "cmc_id": 1}, {"symbol": "ETH", "amount_diff": 2.0, "cmc_id": 1027}]}])),
-        (status = 500, description = "Internal server error (e.g., database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Failed to fetch snapshots from database"}))
+        (status = 200, description = "Successfully retrieved page of snapshots with differences", body = PageDto<SnapshotDto>, example = json!({"items": [{"id": 1, "created_at": "2025-03-06T14:00:00", "assets": [{"asset_id": 1, "symbol": "BTC", "amount": 1.5, "cmc_id": 1}, {"asset_id": 2, "symbol": "ETH", "amount": 10.0, "cmc_id": 1027}], "diff": [{"asset_id": 1, "symbol": "BTC", "amount_diff": -0.5, "cmc_id": 1}, {"asset_id": 2, "symbol": "ETH", "amount_diff": 2.0, "cmc_id": 1027}]}], "total": 1, "next_cursor": null})),
+        (status = 400, description = "Invalid query parameters", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: cursor: Invalid cursor", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "cursor", "message": "Invalid cursor"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn get_snapshots(
     snapshot_service: web::Data<SnapshotService>,
+    page: PageParams<SnapshotSort>,
 ) -> Result<impl Responder, AppError> {
-    let snapshots = snapshot_service.get_all().await?;
+    let snapshots = snapshot_service.get_all(page).await?;
     Ok(HttpResponse::Ok().json(snapshots))
 }
@@ -0,0 +1,68 @@
+use crate::error::{AppError, ProblemDetails};
+use crate::models::stream::StreamParams;
+use crate::services::stream::{StreamService, Subscription};
+use actix_web::http::header::{CacheControl, CacheDirective};
+use actix_web::{web, HttpRequest, HttpResponse, Responder};
+use actix_web_validator::Query;
+use std::collections::HashSet;
+
+// Configures routes for the /stream scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(web::scope("/stream").route("", web::get().to(get_stream)));
+}
+
+// Handles GET /stream to push price and portfolio updates as Server-Sent Events
+#[utoipa::path(
+    get,
+    path = "/stream",
+    params(
+        ("asset_ids" = Option<String>, Query, description = "Comma-separated list of asset IDs to receive prices for (e.g., 1,2)", example = "1,2"),
+        ("portfolio" = Option<bool>, Query, description = "Receive the portfolio value whenever prices or holdings change"),
+        ("last_event_id" = Option<i64>, Query, description = "Resume after this event id; the Last-Event-ID header sent by EventSource takes precedence"),
+        ("Last-Event-ID" = Option<i64>, Header, description = "Id of the last event received before reconnecting")
+    ),
+    responses(
+        (status = 200, description = "Stream of `prices`, `portfolio` and `heartbeat` events. Without a resumable event id the stream starts with the current prices and portfolio.", content_type = "text/event-stream", body = String, example = json!("retry: 3000\n\nid: 42\nevent: prices\ndata: {\"prices\":[{\"asset_id\":1,\"price_usd\":60000.0,\"timestamp\":\"2025-03-15T12:00:00Z\"}]}\n\nid: 42\nevent: portfolio\ndata: {\"total_value_usd\":30000.0,\"holdings\":[{\"asset_id\":1,\"symbol\":\"BTC\",\"amount\":0.5,\"price_usd\":60000.0,\"value_usd\":30000.0}],\"updated_at\":\"2025-03-15T12:00:01+00:00\"}\n\nevent: heartbeat\ndata: {\"timestamp\":\"2025-03-15T12:00:16+00:00\"}\n\n")),
+        (status = 400, description = "Invalid query parameters or nothing subscribed", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Subscribe to asset_ids and/or portfolio=true", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_stream(
+    stream_service: web::Data<StreamService>,
+    req: HttpRequest,
+    query: Query<StreamParams>,
+) -> Result<impl Responder, AppError> {
+    let query = query.into_inner();
+    let asset_ids = match &query.asset_ids {
+        Some(ids) => ids
+            .split(',')
+            .filter(|id| !id.trim().is_empty())
+            .map(|id| id.trim().parse::<i32>())
+            .collect::<Result<HashSet<i32>, _>>()
+            .map_err(|_| {
+                AppError::invalid_field("asset_ids", "Asset IDs must be comma-separated integers")
+            })?,
+        None => HashSet::new(),
+    };
+    let subscription = Subscription {
+        asset_ids,
+        portfolio: query.portfolio.unwrap_or(false),
+    };
+    if subscription.asset_ids.is_empty() && !subscription.portfolio {
+        return Err(AppError::validation(
+            "Subscribe to asset_ids and/or portfolio=true",
+        ));
+    }
+
+    let last_event_id = req
+        .headers()
+        .get("Last-Event-ID")
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<i64>().ok())
+        .or(query.last_event_id);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(stream_service.subscribe(subscription, last_event_id)))
+}
@@ -0,0 +1,212 @@
+use crate::dto::tag::{
+    CreateTagDto, TagBreakdownDto, TagDto, TagTransactionsDto, TagTransactionsResponse,
+};
+use crate::error::{AppError, ProblemDetails};
+use crate::models::tag::BreakdownParams;
+use crate::services::tag::TagService;
+use actix_web::{web, HttpResponse, Responder};
+use actix_web_validator::{Json, Query};
+use anyhow::Result;
+
+// Configures routes for the /tags scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/tags")
+            .route("", web::get().to(get_tags))
+            .route("", web::post().to(create_tag))
+            .route("/breakdown", web::get().to(get_tag_breakdown))
+            .route("/{id}", web::get().to(get_tag))
+            .route("/{id}", web::put().to(update_tag))
+            .route("/{id}", web::delete().to(delete_tag))
+            .route("/{id}/transactions", web::post().to(tag_transactions))
+            .route(
+                "/{id}/transactions/{transaction_id}",
+                web::delete().to(untag_transaction),
+            ),
+    );
+}
+
+// Handles GET /tags to retrieve all tags
+#[utoipa::path(
+    get,
+    path = "/tags",
+    responses(
+        (status = 200, description = "Successfully retrieved list of tags sorted by name", body = Vec<TagDto>, example = json!([{"id": 1, "name": "DCA", "description": "Weekly buys", "transaction_count": 12, "created_at": "2025-03-18T12:00:00"}])),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_tags(tag_service: web::Data<TagService>) -> Result<impl Responder, AppError> {
+    let tags = tag_service.get_all().await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+// Handles POST /tags to create a new tag
+#[utoipa::path(
+    post,
+    path = "/tags",
+    request_body(
+        content = CreateTagDto,
+        description = "Name and optional description of the tag",
+        example = json!({"name": "DCA", "description": "Weekly buys"})
+    ),
+    responses(
+        (status = 200, description = "Tag created successfully", body = TagDto, example = json!({"id": 1, "name": "DCA", "description": "Weekly buys", "transaction_count": 0, "created_at": "2025-03-18T12:00:00"})),
+        (status = 400, description = "Invalid request data", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: name: Name must be 1-50 letters, digits, spaces, '-', '_' or '.'", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "name", "message": "Name must be 1-50 letters, digits, spaces, '-', '_' or '.'"}]})),
+        (status = 409, description = "A tag with this name already exists", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:conflict", "title": "Conflict", "status": 409, "detail": "Key (lower(name::text))=(dca) already exists.", "code": "conflict", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn create_tag(
+    tag_service: web::Data<TagService>,
+    tag: Json<CreateTagDto>,
+) -> Result<impl Responder, AppError> {
+    let response = tag_service.create(tag.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles GET /tags/breakdown to calculate holdings and profit and loss per tag
+#[utoipa::path(
+    get,
+    path = "/tags/breakdown",
+    params(
+        ("tags" = Option<String>, Query, description = "Comma-separated tag names to include (case-insensitive); all tags when omitted", example = "DCA,bot"),
+        ("include_untagged" = Option<bool>, Query, description = "Include a group without tag for untagged transactions (default: true unless tags are given)")
+    ),
+    responses(
+        (status = 200, description = "Holdings and profit and loss per tag with the average cost method, valued at the latest stored prices. A transaction with several tags counts towards each of them.", body = Vec<TagBreakdownDto>, example = json!([{"tag_id": 1, "tag": "DCA", "value_usd": 30000.0, "cost_basis_usd": 25000.0, "realized_pnl_usd": 1200.0, "unrealized_pnl_usd": 5000.0, "assets": [{"asset_id": 1, "symbol": "BTC", "amount": 0.5, "average_cost_usd": 50000.0, "cost_basis_usd": 25000.0, "price_usd": 60000.0, "value_usd": 30000.0, "realized_pnl_usd": 1200.0, "unrealized_pnl_usd": 5000.0}]}, {"tag_id": null, "tag": null, "value_usd": 3000.0, "cost_basis_usd": 2500.0, "realized_pnl_usd": 0.0, "unrealized_pnl_usd": 500.0, "assets": [{"asset_id": 2, "symbol": "ETH", "amount": 1.0, "average_cost_usd": 2500.0, "cost_basis_usd": 2500.0, "price_usd": 3000.0, "value_usd": 3000.0, "realized_pnl_usd": 0.0, "unrealized_pnl_usd": 500.0}]}])),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_tag_breakdown(
+    tag_service: web::Data<TagService>,
+    query: Query<BreakdownParams>,
+) -> Result<impl Responder, AppError> {
+    let response = tag_service.get_breakdown(&query).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles GET /tags/{id} to retrieve a tag
+#[utoipa::path(
+    get,
+    path = "/tags/{id}",
+    params(
+        ("id" = i32, Path, description = "Tag ID")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved tag", body = TagDto, example = json!({"id": 1, "name": "DCA", "description": "Weekly buys", "transaction_count": 12, "created_at": "2025-03-18T12:00:00"})),
+        (status = 404, description = "Tag not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Tag 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_tag(
+    tag_service: web::Data<TagService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let response = tag_service.get(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles PUT /tags/{id} to rename a tag and replace its description
+#[utoipa::path(
+    put,
+    path = "/tags/{id}",
+    params(
+        ("id" = i32, Path, description = "Tag ID")
+    ),
+    request_body(
+        content = CreateTagDto,
+        description = "New name and description of the tag",
+        example = json!({"name": "dca-weekly", "description": "Weekly buys"})
+    ),
+    responses(
+        (status = 200, description = "Tag updated successfully", body = TagDto, example = json!({"id": 1, "name": "dca-weekly", "description": "Weekly buys", "transaction_count": 12, "created_at": "2025-03-18T12:00:00"})),
+        (status = 400, description = "Invalid request data", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: name: Name must be 1-50 letters, digits, spaces, '-', '_' or '.'", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "name", "message": "Name must be 1-50 letters, digits, spaces, '-', '_' or '.'"}]})),
+        (status = 404, description = "Tag not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Tag 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 409, description = "A tag with this name already exists", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:conflict", "title": "Conflict", "status": 409, "detail": "Key (lower(name::text))=(dca) already exists.", "code": "conflict", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn update_tag(
+    tag_service: web::Data<TagService>,
+    path: web::Path<i32>,
+    tag: Json<CreateTagDto>,
+) -> Result<impl Responder, AppError> {
+    let response = tag_service
+        .update(path.into_inner(), tag.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles DELETE /tags/{id} to delete a tag
+#[utoipa::path(
+    delete,
+    path = "/tags/{id}",
+    params(
+        ("id" = i32, Path, description = "Tag ID")
+    ),
+    responses(
+        (status = 204, description = "Tag deleted successfully; the tagged transactions are kept"),
+        (status = 404, description = "Tag not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Tag 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn delete_tag(
+    tag_service: web::Data<TagService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    tag_service.delete(path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Handles POST /tags/{id}/transactions to tag transactions in bulk
+#[utoipa::path(
+    post,
+    path = "/tags/{id}/transactions",
+    params(
+        ("id" = i32, Path, description = "Tag ID")
+    ),
+    request_body(
+        content = TagTransactionsDto,
+        description = "Transactions to tag; nothing is tagged if any of them does not exist",
+        example = json!({"transaction_ids": [1, 2, 3]})
+    ),
+    responses(
+        (status = 200, description = "Transactions tagged successfully", body = TagTransactionsResponse, example = json!({"tagged": 2, "already_tagged": 1})),
+        (status = 400, description = "Invalid request data (e.g., unknown transactions)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: transaction_ids: Transactions 42 not found", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "transaction_ids", "message": "Transactions 42 not found"}]})),
+        (status = 404, description = "Tag not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Tag 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn tag_transactions(
+    tag_service: web::Data<TagService>,
+    path: web::Path<i32>,
+    body: Json<TagTransactionsDto>,
+) -> Result<impl Responder, AppError> {
+    let response = tag_service
+        .tag_transactions(path.into_inner(), body.into_inner().transaction_ids)
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles DELETE /tags/{id}/transactions/{transaction_id} to remove a tag from a transaction
+#[utoipa::path(
+    delete,
+    path = "/tags/{id}/transactions/{transaction_id}",
+    params(
+        ("id" = i32, Path, description = "Tag ID"),
+        ("transaction_id" = i32, Path, description = "Transaction ID to untag")
+    ),
+    responses(
+        (status = 204, description = "Tag removed from the transaction successfully"),
+        (status = 404, description = "Tag not found or transaction not tagged with it", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Transaction 7 is not tagged with tag 1", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn untag_transaction(
+    tag_service: web::Data<TagService>,
+    path: web::Path<(i32, i32)>,
+) -> Result<impl Responder, AppError> {
+    let (id, transaction_id) = path.into_inner();
+    tag_service.untag_transaction(id, transaction_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
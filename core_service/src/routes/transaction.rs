@@ -1,13 +1,15 @@
-use crate::dto::transaction::{CreateTransactionDto, TransactionDto};
-use crate::error::AppError;
-use crate::models::transaction::FilterParams;
-use crate::repository::transaction::TransactionRepository;
+use crate::dto::pagination::PageDto;
+use crate::dto::transaction::{
+    CreateTransactionBatchDto, CreateTransactionDto, TransactionBatchDto, TransactionDto,
+};
+use crate::error::{AppError, ProblemDetails};
+use crate::models::pagination::PageParams;
+use crate::models::transaction::{FilterParams, TransactionSort};
 use crate::services::portfolio::PortfolioService;
 use crate::services::transaction::TransactionService;
 use actix_web::{web, HttpResponse, Responder};
 use actix_web_validator::{Json, Query};
 use anyhow::Result;
-use sqlx::PgPool;
 
 // Configures routes for the /transactions scope
 pub fn configure(cfg: &mut web::ServiceConfig) {
@@ -15,40 +17,46 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
         web::scope("/transactions")
             .route("", web::get().to(get_transactions))
             .route("", web::post().to(create_transaction))
+            .route("/batch", web::post().to(create_transactions))
             .route("/portfolio/value", web::get().to(get_portfolio_value)),
     );
 }
 
-// Handles GET /transactions to retrieve filtered transactions
+// Handles GET /transactions to retrieve a filtered, sorted page of transactions
 #[utoipa::path(
     get,
     path = "/transactions",
     responses(
-        (status = 200, description = "Successfully retrieved list of transactions", body = Vec<TransactionDto>, example = json!([{"id": 1, "asset": "BTC", "wallet": "Binance", "amount": 0.5, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": "First trade", "created_at": "2024-01-01T00:00:00"}])),
-        (status = 400, description = "Invalid query parameters", body = String, example = json!({"status": 400, "error": "Bad Request", "message": "Validation error: Invalid start_date format, expected ISO 8601 (e.g., '2024-01-01T00:00:00')"})),
-        (status = 500, description = "Internal server error", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Database connection failed"}))
+        (status = 200, description = "Successfully retrieved page of transactions", body = PageDto<TransactionDto>, example = json!({"items": [{"id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount": 0.5, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": "First trade", "tags": ["DCA"], "created_at": "2024-01-01T00:00:00"}], "total": 1, "next_cursor": null})),
+        (status = 400, description = "Invalid query parameters", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: start_date: Invalid start_date format, expected ISO 8601 (e.g., '2024-01-01T00:00:00' or '2024-01-01T00:00:00Z')", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "start_date", "message": "Invalid start_date format, expected ISO 8601 (e.g., '2024-01-01T00:00:00' or '2024-01-01T00:00:00Z')"}]})),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     ),
     params(
         ("asset_id" = Option<i32>, Query, description = "Filter transactions by asset ID (e.g., 1 for BTC)"),
+        ("asset_ids" = Option<String>, Query, description = "Filter transactions by a comma-separated list of asset IDs, combined with asset_id (e.g., 1,2)"),
         ("wallet_id" = Option<i32>, Query, description = "Filter transactions by wallet ID (e.g., 1 for Binance)"),
+        ("wallet_ids" = Option<String>, Query, description = "Filter transactions by a comma-separated list of wallet IDs, combined with wallet_id (e.g., 1,2)"),
         ("start_date" = Option<String>, Query, description = "Filter transactions starting from this date in ISO 8601 format (e.g., '2024-01-01T00:00:00')"),
-        ("limit" = Option<i64>, Query, description = "Maximum number of transactions to return (default: 10)"),
-        ("offset" = Option<i64>, Query, description = "Offset for pagination (default: 0)")
+        ("end_date" = Option<String>, Query, description = "Filter transactions up to this date (inclusive) in ISO 8601 format (e.g., '2024-12-31T23:59:59')"),
+        ("transaction_type" = Option<String>, Query, description = "Filter transactions by type: 'BUY' or 'SELL'"),
+        ("min_amount" = Option<f64>, Query, description = "Minimum amount (inclusive)"),
+        ("max_amount" = Option<f64>, Query, description = "Maximum amount (inclusive)"),
+        ("min_price" = Option<f64>, Query, description = "Minimum price (inclusive)"),
+        ("max_price" = Option<f64>, Query, description = "Maximum price (inclusive)"),
+        ("notes" = Option<String>, Query, description = "Case-insensitive text searched for in the notes"),
+        ("tags" = Option<String>, Query, description = "Filter transactions having any of a comma-separated list of tag names, matched case-insensitively (e.g., DCA,bot)"),
+        ("sort" = Option<String>, Query, description = "Sort field: 'created_at' (default), 'amount', 'price', 'fee', 'transaction_type', 'asset' or 'wallet'"),
+        ("order" = Option<String>, Query, description = "Sort order: 'asc' or 'desc' (default: desc)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of transactions to return (default: 50, max: 500)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the page to return, taken from `next_cursor` of the previous page")
     )
 )]
 async fn get_transactions(
-    pool: web::Data<PgPool>,
+    transaction_service: web::Data<TransactionService>,
     query: Query<FilterParams>,
+    page: PageParams<TransactionSort>,
 ) -> Result<impl Responder, AppError> {
-    let repo = TransactionRepository::new(pool.get_ref());
-    let transactions = repo
-        .get_transactions(query.into_inner())
-        .await
-        .map_err(AppError::internal)?;
-    let response = transactions
-        .into_iter()
-        .map(TransactionDto::from)
-        .collect::<Vec<_>>();
+    let response = transaction_service.get_page(&query, &page).await?;
     Ok(HttpResponse::Ok().json(response))
 }
 
@@ -62,9 +70,9 @@ async fn get_transactions(
         example = json!({"asset_id": 1, "wallet_id": 1, "amount": 0.5, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": "First trade"})
     ),
     responses(
-        (status = 200, description = "Transaction created successfully", body = TransactionDto, example = json!({"id": 1, "asset": "BTC", "wallet": "Binance", "amount": 0.5, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": "First trade", "created_at": "2025-03-07T12:00:00Z"})),
-        (status = 400, description = "Invalid request data (e.g., validation failed or invalid IDs)", body = String, example = json!({"status": 400, "error": "Bad Request", "message": "Wallet not found"})),
-        (status = 500, description = "Internal server error", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Failed to insert transaction into database"}))
+        (status = 200, description = "Transaction created successfully", body = TransactionDto, example = json!({"id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount": 0.5, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": "First trade", "tags": [], "created_at": "2025-03-07T12:00:00Z"})),
+        (status = 400, description = "Invalid request data (e.g., validation failed, invalid IDs or a sell exceeding the holdings of the wallet)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: wallet_id: Wallet 1 not found", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "wallet_id", "message": "Wallet 1 not found"}]})),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn create_transaction(
@@ -75,25 +83,43 @@ async fn create_transaction(
     Ok(HttpResponse::Ok().json(response))
 }
 
+// Handles POST /transactions/batch to create several transactions atomically
+#[utoipa::path(
+    post,
+    path = "/transactions/batch",
+    request_body(
+        content = CreateTransactionBatchDto,
+        description = "Transactions to create, applied in order; nothing is created if any of them is invalid",
+        example = json!({"transactions": [{"asset_id": 1, "wallet_id": 1, "amount": 0.3, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": "Order 42, fill 1"}, {"asset_id": 1, "wallet_id": 1, "amount": 0.2, "price": 50010.0, "transaction_type": "BUY", "notes": "Order 42, fill 2"}]})
+    ),
+    responses(
+        (status = 200, description = "All transactions created, in the order of the request", body = TransactionBatchDto, example = json!({"created": 2, "transactions": [{"id": 1, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount": 0.3, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": "Order 42, fill 1", "tags": [], "created_at": "2025-03-07T12:00:00Z"}, {"id": 2, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount": 0.2, "price": 50010.0, "transaction_type": "BUY", "fee": null, "notes": "Order 42, fill 2", "tags": [], "created_at": "2025-03-07T12:00:00Z"}]})),
+        (status = 400, description = "At least one transaction is invalid (validation failed, unknown asset or wallet, or insufficient balance); errors name the failing items and nothing is created", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: transactions[1].amount: Insufficient balance: wallet 1 holds 0.5 of asset 1", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "transactions[1].amount", "message": "Insufficient balance: wallet 1 holds 0.5 of asset 1"}]})),
+        (status = 500, description = "Internal server error", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn create_transactions(
+    transaction_service: web::Data<TransactionService>,
+    batch: Json<CreateTransactionBatchDto>,
+) -> Result<impl Responder, AppError> {
+    let response = transaction_service.create_batch(batch.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
 // Handles GET /transactions/portfolio/value to calculate portfolio value
 #[utoipa::path(
     get,
     path = "/transactions/portfolio/value",
     responses(
         (status = 200, description = "Successfully calculated portfolio value in USD", body = serde_json::Value, example = json!({"total_value_usd": 25000.0})),
-        (status = 500, description = "Internal server error (e.g., database or CoinMarketCap API failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Failed to fetch price data from CoinMarketCap"})),
-        (status = 503, description = "Price data unavailable for some assets", body = String, example = json!({"status": 503, "error": "Service Unavailable", "message": "Price unavailable for symbol BTC"}))
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 502, description = "CoinMarketCap returned an error or an invalid response", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:upstream_failed", "title": "Bad Gateway", "status": 502, "detail": "CoinMarketCap API error 1002: API key missing.", "code": "upstream_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 503, description = "Prices of held assets are outdated and could not be refreshed from CoinMarketCap", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:stale_price", "title": "Service Unavailable", "status": 503, "detail": "Prices of assets 1, 2 are missing or older than 1 hour and could not be refreshed: CoinMarketCap request timed out", "code": "stale_price", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "asset_ids": [1, 2]}))
     )
 )]
 async fn get_portfolio_value(
     portfolio: web::Data<PortfolioService>,
 ) -> Result<impl Responder, AppError> {
-    let total_value = portfolio.get_portfolio_value().await.map_err(|e| {
-        if e.to_string().contains("No quote data") {
-            AppError::service_unavailable(e)
-        } else {
-            AppError::internal(e)
-        }
-    })?;
+    let total_value = portfolio.get_portfolio_value().await?;
     Ok(HttpResponse::Ok().json(serde_json::json!({"total_value_usd": total_value})))
 }
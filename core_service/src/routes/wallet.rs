@@ -1,5 +1,8 @@
+use crate::dto::pagination::PageDto;
 use crate::dto::wallet::{CreateWalletDto, WalletDto};
-use crate::error::AppError;
+use crate::error::{AppError, ProblemDetails};
+use crate::models::pagination::PageParams;
+use crate::models::wallet::WalletSort;
 use crate::services::wallet::WalletService;
 use actix_web::{web, HttpResponse, Responder};
 use actix_web_validator::Json;
@@ -14,17 +17,27 @@ pub fn configure(cfg: &mut web::ServiceConfig) {
     );
 }
 
-// Handles GET /wallets to retrieve all wallets
+// Handles GET /wallets to retrieve a page of wallets
 #[utoipa::path(
     get,
     path = "/wallets",
+    params(
+        ("sort" = Option<String>, Query, description = "Sort field: 'created_at' (default) or 'name'"),
+        ("order" = Option<String>, Query, description = "Sort order: 'asc' or 'desc' (default: asc)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of wallets to return (default: 50, max: 500)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the page to return, taken from `next_cursor` of the previous page")
+    ),
     responses(
-        (status = 200, description = "Successfully retrieved list of wallets", body = Vec<WalletDto>, example = json!([{"id": 1, "name": "Binance", "wallet_type": "Hot", "address": "0x1234", "created_at": "2024-01-01T00:00:00"}])),
-        (status = 500, description = "Internal server error (e.g., database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Database connection failed"}))
+        (status = 200, description = "Successfully retrieved page of wallets", body = PageDto<WalletDto>, example = json!({"items": [{"id": 1, "name": "Binance", "wallet_type": "Hot", "address": "0x1234", "created_at": "2024-01-01T00:00:00"}], "total": 1, "next_cursor": null})),
+        (status = 400, description = "Invalid query parameters", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: sort: Sort must be one of 'created_at', 'name'", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "sort", "message": "Sort must be one of 'created_at', 'name'"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
-async fn get_wallets(wallet_service: web::Data<WalletService>) -> Result<impl Responder, AppError> {
-    let wallets = wallet_service.get_all().await.map_err(AppError::internal)?;
+async fn get_wallets(
+    wallet_service: web::Data<WalletService>,
+    page: PageParams<WalletSort>,
+) -> Result<impl Responder, AppError> {
+    let wallets = wallet_service.get_all(page).await?;
     Ok(HttpResponse::Ok().json(wallets))
 }
 
@@ -39,17 +52,14 @@ async fn get_wallets(wallet_service: web::Data<WalletService>) -> Result<impl Re
     ),
     responses(
         (status = 200, description = "Wallet created successfully", body = WalletDto, example = json!({"id": 1, "name": "Binance", "wallet_type": "Hot", "address": "0x1234", "created_at": "2024-01-01T00:00:00"})),
-        (status = 400, description = "Invalid request data (e.g., missing required fields)", body = String, example = json!({"status": 400, "error": "Bad Request", "message": "Validation error: Name must not be empty"})),
-        (status = 500, description = "Internal server error (e.g., database failure)", body = String, example = json!({"status": 500, "error": "Internal Server Error", "message": "Failed to insert wallet into database"}))
+        (status = 400, description = "Invalid request data (e.g., missing required fields)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: name: Name must not be empty", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "name", "message": "Name must not be empty"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
     )
 )]
 async fn create_wallet(
     wallet_service: web::Data<WalletService>,
     wallet: Json<CreateWalletDto>,
 ) -> Result<impl Responder, AppError> {
-    let response = wallet_service
-        .create(wallet.into_inner())
-        .await
-        .map_err(AppError::internal)?;
+    let response = wallet_service.create(wallet.into_inner()).await?;
     Ok(HttpResponse::Ok().json(response))
 }
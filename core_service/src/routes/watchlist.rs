@@ -0,0 +1,189 @@
+use crate::dto::watchlist::{
+    AddWatchlistAssetDto, CreateWatchlistDto, UpdateWatchlistDto, WatchlistDetailDto, WatchlistDto,
+};
+use crate::error::{AppError, ProblemDetails};
+use crate::services::watchlist::WatchlistService;
+use actix_web::{web, HttpResponse, Responder};
+use actix_web_validator::Json;
+use anyhow::Result;
+
+// Configures routes for the /watchlists scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/watchlists")
+            .route("", web::get().to(get_watchlists))
+            .route("", web::post().to(create_watchlist))
+            .route("/{id}", web::get().to(get_watchlist))
+            .route("/{id}", web::put().to(update_watchlist))
+            .route("/{id}", web::delete().to(delete_watchlist))
+            .route("/{id}/assets", web::post().to(add_watchlist_asset))
+            .route(
+                "/{id}/assets/{asset_id}",
+                web::delete().to(remove_watchlist_asset),
+            ),
+    );
+}
+
+// Handles GET /watchlists to retrieve all watchlists
+#[utoipa::path(
+    get,
+    path = "/watchlists",
+    responses(
+        (status = 200, description = "Successfully retrieved list of watchlists", body = Vec<WatchlistDto>, example = json!([{"id": 1, "name": "L2s", "asset_ids": [11841, 4], "created_at": "2025-03-10T12:00:00"}])),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_watchlists(
+    watchlist_service: web::Data<WatchlistService>,
+) -> Result<impl Responder, AppError> {
+    let watchlists = watchlist_service.get_all().await?;
+    Ok(HttpResponse::Ok().json(watchlists))
+}
+
+// Handles POST /watchlists to create a new watchlist
+#[utoipa::path(
+    post,
+    path = "/watchlists",
+    request_body(
+        content = CreateWatchlistDto,
+        description = "Name and optional initial assets of the watchlist",
+        example = json!({"name": "L2s", "asset_ids": [11841, 4]})
+    ),
+    responses(
+        (status = 200, description = "Watchlist created successfully", body = WatchlistDto, example = json!({"id": 1, "name": "L2s", "asset_ids": [11841, 4], "created_at": "2025-03-10T12:00:00"})),
+        (status = 400, description = "Invalid request data (e.g., unknown asset)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: asset_id: Asset 42 not found", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "asset_id", "message": "Asset 42 not found"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn create_watchlist(
+    watchlist_service: web::Data<WatchlistService>,
+    watchlist: Json<CreateWatchlistDto>,
+) -> Result<impl Responder, AppError> {
+    let response = watchlist_service.create(watchlist.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles GET /watchlists/{id} to retrieve a watchlist with latest prices
+#[utoipa::path(
+    get,
+    path = "/watchlists/{id}",
+    params(
+        ("id" = i32, Path, description = "Watchlist ID")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved watchlist with latest prices and 24h/7d changes", body = WatchlistDetailDto, example = json!({"id": 1, "name": "L2s", "created_at": "2025-03-10T12:00:00", "assets": [{"asset_id": 4, "cmc_id": 11841, "symbol": "ARB", "name": "Arbitrum", "price_usd": 0.42, "percent_change_24h": -1.3, "percent_change_7d": 5.8, "timestamp": "2025-03-10T12:00:00Z"}]})),
+        (status = 404, description = "Watchlist not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Watchlist 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_watchlist(
+    watchlist_service: web::Data<WatchlistService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let response = watchlist_service.get_details(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles PUT /watchlists/{id} to rename a watchlist
+#[utoipa::path(
+    put,
+    path = "/watchlists/{id}",
+    params(
+        ("id" = i32, Path, description = "Watchlist ID")
+    ),
+    request_body(
+        content = UpdateWatchlistDto,
+        description = "New name of the watchlist",
+        example = json!({"name": "Layer 2"})
+    ),
+    responses(
+        (status = 200, description = "Watchlist updated successfully", body = WatchlistDto, example = json!({"id": 1, "name": "Layer 2", "asset_ids": [11841, 4], "created_at": "2025-03-10T12:00:00"})),
+        (status = 400, description = "Invalid request data", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: name: Name must be 1-50 characters long", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "name", "message": "Name must be 1-50 characters long"}]})),
+        (status = 404, description = "Watchlist not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Watchlist 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn update_watchlist(
+    watchlist_service: web::Data<WatchlistService>,
+    path: web::Path<i32>,
+    watchlist: Json<UpdateWatchlistDto>,
+) -> Result<impl Responder, AppError> {
+    let response = watchlist_service
+        .update(path.into_inner(), watchlist.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles DELETE /watchlists/{id} to delete a watchlist
+#[utoipa::path(
+    delete,
+    path = "/watchlists/{id}",
+    params(
+        ("id" = i32, Path, description = "Watchlist ID")
+    ),
+    responses(
+        (status = 204, description = "Watchlist deleted successfully"),
+        (status = 404, description = "Watchlist not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Watchlist 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn delete_watchlist(
+    watchlist_service: web::Data<WatchlistService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    watchlist_service.delete(path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Handles POST /watchlists/{id}/assets to add an asset to a watchlist
+#[utoipa::path(
+    post,
+    path = "/watchlists/{id}/assets",
+    params(
+        ("id" = i32, Path, description = "Watchlist ID")
+    ),
+    request_body(
+        content = AddWatchlistAssetDto,
+        description = "Asset to add to the watchlist",
+        example = json!({"asset_id": 42})
+    ),
+    responses(
+        (status = 200, description = "Asset added successfully", body = WatchlistDto, example = json!({"id": 1, "name": "L2s", "asset_ids": [11841, 4, 42], "created_at": "2025-03-10T12:00:00"})),
+        (status = 400, description = "Asset not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: asset_id: Asset 42 not found", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "asset_id", "message": "Asset 42 not found"}]})),
+        (status = 404, description = "Watchlist not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Watchlist 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn add_watchlist_asset(
+    watchlist_service: web::Data<WatchlistService>,
+    path: web::Path<i32>,
+    asset: Json<AddWatchlistAssetDto>,
+) -> Result<impl Responder, AppError> {
+    let response = watchlist_service
+        .add_asset(path.into_inner(), asset.asset_id)
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles DELETE /watchlists/{id}/assets/{asset_id} to remove an asset from a watchlist
+#[utoipa::path(
+    delete,
+    path = "/watchlists/{id}/assets/{asset_id}",
+    params(
+        ("id" = i32, Path, description = "Watchlist ID"),
+        ("asset_id" = i32, Path, description = "Asset ID to remove")
+    ),
+    responses(
+        (status = 204, description = "Asset removed successfully"),
+        (status = 404, description = "Watchlist or asset entry not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Asset 42 is not in watchlist 1", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn remove_watchlist_asset(
+    watchlist_service: web::Data<WatchlistService>,
+    path: web::Path<(i32, i32)>,
+) -> Result<impl Responder, AppError> {
+    let (id, asset_id) = path.into_inner();
+    watchlist_service.remove_asset(id, asset_id).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
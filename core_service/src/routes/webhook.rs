@@ -0,0 +1,191 @@
+use crate::dto::pagination::PageDto;
+use crate::dto::webhook::{CreateWebhookDto, UpdateWebhookDto, WebhookDeliveryDto, WebhookDto};
+use crate::error::{AppError, ProblemDetails};
+use crate::models::pagination::PageParams;
+use crate::models::webhook::{DeliveryQueryParams, DeliverySort};
+use crate::services::webhook::WebhookService;
+use actix_web::{web, HttpResponse, Responder};
+use actix_web_validator::{Json, Query};
+use anyhow::Result;
+
+// Configures routes for the /webhooks scope
+pub fn configure(cfg: &mut web::ServiceConfig) {
+    cfg.service(
+        web::scope("/webhooks")
+            .route("", web::get().to(get_webhooks))
+            .route("", web::post().to(create_webhook))
+            .route("/{id}", web::get().to(get_webhook))
+            .route("/{id}", web::put().to(update_webhook))
+            .route("/{id}", web::delete().to(delete_webhook))
+            .route("/{id}/deliveries", web::get().to(get_webhook_deliveries))
+            .route(
+                "/{id}/deliveries/{delivery_id}/retry",
+                web::post().to(retry_webhook_delivery),
+            ),
+    );
+}
+
+// Handles GET /webhooks to retrieve all webhook subscriptions
+#[utoipa::path(
+    get,
+    path = "/webhooks",
+    responses(
+        (status = 200, description = "Successfully retrieved list of webhook subscriptions", body = Vec<WebhookDto>, example = json!([{"id": 1, "url": "https://bot.example.com/hooks/austeris", "event_types": ["transaction.created", "snapshot.created"], "description": "Accounting bot", "active": true, "created_at": "2025-03-16T12:00:00", "updated_at": "2025-03-16T12:00:00"}])),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_webhooks(
+    webhook_service: web::Data<WebhookService>,
+) -> Result<impl Responder, AppError> {
+    let webhooks = webhook_service.get_all().await?;
+    Ok(HttpResponse::Ok().json(webhooks))
+}
+
+// Handles POST /webhooks to create a webhook subscription
+#[utoipa::path(
+    post,
+    path = "/webhooks",
+    request_body(
+        content = CreateWebhookDto,
+        description = "Target URL and event types of the subscription; the signing secret is generated when omitted",
+        example = json!({"url": "https://bot.example.com/hooks/austeris", "event_types": ["transaction.created", "snapshot.created"], "description": "Accounting bot"})
+    ),
+    responses(
+        (status = 200, description = "Webhook subscription created; the secret is only returned here and when it is changed", body = WebhookDto, example = json!({"id": 1, "url": "https://bot.example.com/hooks/austeris", "event_types": ["transaction.created", "snapshot.created"], "description": "Accounting bot", "active": true, "secret": "6f1c0d8e4b2a49f7a3e5c9d1b7f2e4a86b0e2d4f6a8c4e1b9d3f5a7c2e4b6d8f", "created_at": "2025-03-16T12:00:00", "updated_at": "2025-03-16T12:00:00"})),
+        (status = 400, description = "Invalid request data (e.g., unknown event type)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: event_types: Unknown event type 'transaction.removed', expected one of: transaction.created, snapshot.created, assets.synced, prices.updated", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "event_types", "message": "Unknown event type 'transaction.removed', expected one of: transaction.created, snapshot.created, assets.synced, prices.updated"}]})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn create_webhook(
+    webhook_service: web::Data<WebhookService>,
+    webhook: Json<CreateWebhookDto>,
+) -> Result<impl Responder, AppError> {
+    let response = webhook_service.create(webhook.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles GET /webhooks/{id} to retrieve a webhook subscription
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}",
+    params(
+        ("id" = i32, Path, description = "Webhook subscription ID")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved webhook subscription", body = WebhookDto, example = json!({"id": 1, "url": "https://bot.example.com/hooks/austeris", "event_types": [], "description": null, "active": true, "created_at": "2025-03-16T12:00:00", "updated_at": "2025-03-16T12:00:00"})),
+        (status = 404, description = "Webhook subscription not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Webhook 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_webhook(
+    webhook_service: web::Data<WebhookService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    let response = webhook_service.get(path.into_inner()).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles PUT /webhooks/{id} to update a webhook subscription
+#[utoipa::path(
+    put,
+    path = "/webhooks/{id}",
+    params(
+        ("id" = i32, Path, description = "Webhook subscription ID")
+    ),
+    request_body(
+        content = UpdateWebhookDto,
+        description = "Fields to change; omitted fields are left unchanged",
+        example = json!({"active": false})
+    ),
+    responses(
+        (status = 200, description = "Webhook subscription updated successfully", body = WebhookDto, example = json!({"id": 1, "url": "https://bot.example.com/hooks/austeris", "event_types": ["transaction.created"], "description": "Accounting bot", "active": false, "created_at": "2025-03-16T12:00:00", "updated_at": "2025-03-16T13:00:00"})),
+        (status = 400, description = "Invalid request data", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: url: URL must be a valid http(s) URL", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "url", "message": "URL must be a valid http(s) URL"}]})),
+        (status = 404, description = "Webhook subscription not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Webhook 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn update_webhook(
+    webhook_service: web::Data<WebhookService>,
+    path: web::Path<i32>,
+    webhook: Json<UpdateWebhookDto>,
+) -> Result<impl Responder, AppError> {
+    let response = webhook_service
+        .update(path.into_inner(), webhook.into_inner())
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles DELETE /webhooks/{id} to delete a webhook subscription and its delivery log
+#[utoipa::path(
+    delete,
+    path = "/webhooks/{id}",
+    params(
+        ("id" = i32, Path, description = "Webhook subscription ID")
+    ),
+    responses(
+        (status = 204, description = "Webhook subscription deleted successfully"),
+        (status = 404, description = "Webhook subscription not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Webhook 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn delete_webhook(
+    webhook_service: web::Data<WebhookService>,
+    path: web::Path<i32>,
+) -> Result<impl Responder, AppError> {
+    webhook_service.delete(path.into_inner()).await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+// Handles GET /webhooks/{id}/deliveries to inspect the delivery log of a subscription
+#[utoipa::path(
+    get,
+    path = "/webhooks/{id}/deliveries",
+    params(
+        ("id" = i32, Path, description = "Webhook subscription ID"),
+        ("status" = Option<String>, Query, description = "Filter by delivery status: pending, delivered or failed"),
+        ("sort" = Option<String>, Query, description = "Sort field: 'created_at' (default)"),
+        ("order" = Option<String>, Query, description = "Sort order: 'asc' or 'desc' (default: desc)"),
+        ("limit" = Option<i64>, Query, description = "Maximum number of deliveries to return (default: 50, max: 500)"),
+        ("cursor" = Option<String>, Query, description = "Cursor of the page to return, taken from `next_cursor` of the previous page")
+    ),
+    responses(
+        (status = 200, description = "Successfully retrieved page of deliveries", body = PageDto<WebhookDeliveryDto>, example = json!({"items": [{"id": 12, "subscription_id": 1, "event_id": "5b0e8a52-3f0c-4b8e-9a55-0f7a1c2d3e4f", "event_type": "transaction.created", "status": "pending", "attempts": 2, "next_attempt_at": "2025-03-16T12:01:30", "last_attempt_at": "2025-03-16T12:00:30", "response_status": 502, "last_error": "HTTP 502 Bad Gateway: upstream unavailable", "payload": {"id": 7, "asset_id": 1, "asset": "BTC", "wallet_id": 1, "wallet": "Binance", "amount": 0.5, "price": 50000.0, "transaction_type": "BUY", "fee": 0.001, "notes": null, "tags": [], "created_at": "2025-03-16T12:00:00"}, "created_at": "2025-03-16T12:00:00", "delivered_at": null}], "total": 1, "next_cursor": null})),
+        (status = 400, description = "Invalid query parameters", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:validation_failed", "title": "Bad Request", "status": 400, "detail": "Validation error: status: Invalid status 'sent', expected one of: pending, delivered, failed", "code": "validation_failed", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f", "errors": [{"field": "status", "message": "Invalid status 'sent', expected one of: pending, delivered, failed"}]})),
+        (status = 404, description = "Webhook subscription not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Webhook 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn get_webhook_deliveries(
+    webhook_service: web::Data<WebhookService>,
+    path: web::Path<i32>,
+    query: Query<DeliveryQueryParams>,
+    page: PageParams<DeliverySort>,
+) -> Result<impl Responder, AppError> {
+    let response = webhook_service
+        .get_deliveries(path.into_inner(), query.into_inner(), page)
+        .await?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+// Handles POST /webhooks/{id}/deliveries/{delivery_id}/retry to send a delivery again
+#[utoipa::path(
+    post,
+    path = "/webhooks/{id}/deliveries/{delivery_id}/retry",
+    params(
+        ("id" = i32, Path, description = "Webhook subscription ID"),
+        ("delivery_id" = i64, Path, description = "Delivery ID")
+    ),
+    responses(
+        (status = 200, description = "Delivery scheduled with a fresh set of attempts", body = WebhookDeliveryDto, example = json!({"id": 12, "subscription_id": 1, "event_id": "5b0e8a52-3f0c-4b8e-9a55-0f7a1c2d3e4f", "event_type": "transaction.created", "status": "pending", "attempts": 0, "next_attempt_at": "2025-03-16T14:00:00", "last_attempt_at": "2025-03-16T13:10:00", "response_status": 502, "last_error": "HTTP 502 Bad Gateway: upstream unavailable", "payload": {"id": 7}, "created_at": "2025-03-16T12:00:00", "delivered_at": null})),
+        (status = 404, description = "Delivery not found", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:not_found", "title": "Not Found", "status": 404, "detail": "Delivery 12 of webhook 1 not found", "code": "not_found", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"})),
+        (status = 500, description = "Internal server error (e.g., database failure)", body = ProblemDetails, content_type = "application/problem+json", example = json!({"type": "urn:austeris:error:internal_error", "title": "Internal Server Error", "status": 500, "detail": "An unexpected error occurred", "code": "internal_error", "request_id": "0b6f5c1e-8d2a-4f3b-9c7e-1a2b3c4d5e6f"}))
+    )
+)]
+async fn retry_webhook_delivery(
+    webhook_service: web::Data<WebhookService>,
+    path: web::Path<(i32, i64)>,
+) -> Result<impl Responder, AppError> {
+    let (id, delivery_id) = path.into_inner();
+    let response = webhook_service.retry_delivery(id, delivery_id).await?;
+    Ok(HttpResponse::Ok().json(response))
+}
@@ -1,12 +1,20 @@
 use crate::dto::asset::{
-    AssetDto, AssetPriceHistoryDto, AssetPriceWithDetailsDto, CreateAssetDto, UpdateAssetsResponse,
+    AssetDto, AssetPriceHistoryDto, AssetPriceWithDetailsDto, CollidingAssetDto, CreateAssetDto,
+    SymbolCollisionDto, UpdateAssetsResponse,
 };
+use crate::dto::pagination::PageDto;
 use crate::error::AppError;
-use crate::models::asset::{HistoryQueryParams, PriceQueryParams};
+use crate::models::asset::{
+    AssetQueryParams, AssetSort, CollisionQueryParams, HistoryQueryParams, PriceHistorySort,
+    PriceQueryParams,
+};
+use crate::models::pagination::PageParams;
+use crate::models::webhook::WebhookEvent;
 use crate::repository::asset::AssetRepository;
 use crate::repository::asset_price::AssetPriceRepository;
 use crate::services::cmc::CmcService;
 use crate::services::redis::RedisService;
+use crate::services::webhook::WebhookService;
 use crate::utils::datetime::{format_iso8601, parse_iso8601};
 use actix_web::web;
 use anyhow::{Ok, Result};
@@ -18,6 +26,7 @@ pub struct AssetService {
     pool: web::Data<PgPool>,
     cmc_service: web::Data<CmcService>,
     redis_service: web::Data<RedisService>,
+    webhook_service: web::Data<WebhookService>,
 }
 
 impl AssetService {
@@ -26,11 +35,13 @@ impl AssetService {
         pool: web::Data<PgPool>,
         cmc_service: web::Data<CmcService>,
         redis_service: web::Data<RedisService>,
+        webhook_service: web::Data<WebhookService>,
     ) -> Self {
         Self {
             pool,
             cmc_service,
             redis_service,
+            webhook_service,
         }
     }
 
@@ -49,35 +60,69 @@ impl AssetService {
         Ok(record.into())
     }
 
-    // Retrieves all assets
-    pub async fn get_all(&self) -> Result<Vec<AssetDto>> {
+    // Retrieves a filtered, sorted page of assets
+    pub async fn get_all(
+        &self,
+        query: AssetQueryParams,
+        page: PageParams<AssetSort>,
+    ) -> Result<PageDto<AssetDto>> {
+        let repo = AssetRepository::new(self.pool.as_ref());
+        let (assets, total) = repo.search(&query, &page).await?;
+        let items = assets.into_iter().map(AssetDto::from).collect();
+        Ok(PageDto::new(items, total, &page))
+    }
+
+    // Retrieves an asset with its metadata by ID
+    pub async fn get_by_id(&self, id: i32) -> Result<Option<AssetDto>> {
         let repo = AssetRepository::new(self.pool.as_ref());
-        let assets = repo.get_all().await?;
-        Ok(assets.into_iter().map(AssetDto::from).collect())
+        let asset = repo.get_by_id(id).await?;
+        Ok(asset.map(AssetDto::from))
     }
 
-    // Updates the assets table with data from CoinMarketCap and returns the number of updated assets
+    // Lists symbols shared by several assets, optionally only those involving a held asset
+    pub async fn get_symbol_collisions(
+        &self,
+        query: CollisionQueryParams,
+    ) -> Result<Vec<SymbolCollisionDto>> {
+        let repo = AssetRepository::new(self.pool.as_ref());
+        let mut collisions: Vec<SymbolCollisionDto> = Vec::new();
+
+        // Rows are ordered by symbol, so consecutive rows form one collision group
+        for record in repo.get_symbol_collisions().await? {
+            let asset = CollidingAssetDto {
+                asset_id: record.asset_id,
+                cmc_id: record.cmc_id,
+                name: record.name,
+                rank: record.rank,
+                held_amount: record.held_amount,
+            };
+            match collisions.last_mut() {
+                Some(group) if group.symbol == record.symbol => group.assets.push(asset),
+                _ => collisions.push(SymbolCollisionDto {
+                    symbol: record.symbol,
+                    assets: vec![asset],
+                }),
+            }
+        }
+
+        if query.held == Some(true) {
+            collisions.retain(|group| group.assets.iter().any(|a| a.held_amount > 0.0));
+        }
+        Ok(collisions)
+    }
+
+    // Updates the assets table and metadata with data from CoinMarketCap and returns the number of updated assets
     pub async fn update(&self) -> Result<UpdateAssetsResponse> {
         let listings = self.cmc_service.fetch_cmc_listings().await?;
         let repo = AssetRepository::new(self.pool.as_ref());
-        let updated_count = repo
-            .update_assets(
-                listings
-                    .into_iter()
-                    .map(|l| CreateAssetDto {
-                        symbol: l.symbol,
-                        name: l.name,
-                        cmc_id: l.id,
-                        decimals: None,
-                        rank: Some(l.cmc_rank),
-                    })
-                    .collect(),
-            )
-            .await?;
+        let updated_count = repo.update_assets(&listings).await?;
         let response = UpdateAssetsResponse {
             updated_count,
             updated_at: chrono::Utc::now().to_rfc3339(),
         };
+        self.webhook_service
+            .emit(WebhookEvent::AssetsSynced, &response)
+            .await;
         Ok(response)
     }
 
@@ -114,7 +159,8 @@ impl AssetService {
     pub async fn get_price_history(
         &self,
         query: HistoryQueryParams,
-    ) -> Result<Vec<AssetPriceHistoryDto>> {
+        page: PageParams<PriceHistorySort>,
+    ) -> Result<PageDto<AssetPriceHistoryDto>> {
         let price_repo =
             AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone());
         let asset_ids = query.asset_ids.as_ref().map(|ids| {
@@ -123,19 +169,21 @@ impl AssetService {
                 .collect::<Vec<i32>>()
         });
 
-        let start_date = parse_iso8601(&query.start_date).map_err(|e| AppError::bad_request(e))?;
+        let start_date = parse_iso8601(&query.start_date)
+            .map_err(|e| AppError::invalid_field("start_date", e.to_string()))?;
         let end_date = query
             .end_date
             .as_ref()
-            .map(|end| parse_iso8601(end).map_err(|e| AppError::bad_request(e)))
+            .map(|end| {
+                parse_iso8601(end).map_err(|e| AppError::invalid_field("end_date", e.to_string()))
+            })
             .transpose()?;
 
-        let history = price_repo
-            .get_price_history(asset_ids, start_date, end_date)
-            .await
-            .map_err(AppError::internal)?;
+        let (history, total) = price_repo
+            .get_price_history(asset_ids, start_date, end_date, &page)
+            .await?;
 
-        let response = history
+        let items = history
             .into_iter()
             .map(
                 |(cmc_id, symbol, price_usd, timestamp)| AssetPriceHistoryDto {
@@ -146,6 +194,6 @@ impl AssetService {
                 },
             )
             .collect::<Vec<_>>();
-        Ok(response)
+        Ok(PageDto::new(items, total, &page))
     }
 }
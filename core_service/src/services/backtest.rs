@@ -0,0 +1,719 @@
+use crate::dto::backtest::{
+    BacktestAssetResultDto, BacktestPointDto, BacktestRequestDto, BacktestResultDto,
+};
+use crate::error::{field_errors, AppError, FieldError};
+use crate::models::backtest::{BacktestInterval, BacktestStrategy};
+use crate::models::dca::DcaFrequency;
+use crate::repository::asset::AssetRepository;
+use crate::repository::asset_price::AssetPriceRepository;
+use crate::services::redis::RedisService;
+use crate::utils::datetime::{format_iso8601, now_utc, parse_iso8601};
+use actix_web::web;
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use validator::Validate;
+
+// Most points a backtest may be evaluated at, bounding the size of the price query
+const MAX_GRID_POINTS: i64 = 10_000;
+// Default weight drift that triggers a rebalance
+const DEFAULT_REBALANCE_THRESHOLD: f64 = 0.05;
+// Trades below this notional are not made, so rounding noise does not pay fixed fees
+const MIN_TRADE_USD: f64 = 0.01;
+// Shortest period for which an annualized return is given
+const MIN_ANNUALIZED_DAYS: f64 = 30.0;
+const DAYS_PER_YEAR: f64 = 365.25;
+
+// Service replaying investment strategies against the stored price history. Backtests run
+// entirely in memory and never create transactions.
+#[derive(Clone)]
+pub struct BacktestService {
+    pool: web::Data<PgPool>,
+    redis_service: web::Data<RedisService>,
+}
+
+impl BacktestService {
+    // Creates a new instance of BacktestService
+    pub fn new(pool: web::Data<PgPool>, redis_service: web::Data<RedisService>) -> Self {
+        Self {
+            pool,
+            redis_service,
+        }
+    }
+
+    // Replays a strategy over the period of the request and returns its equity curve and metrics
+    pub async fn run(&self, request: BacktestRequestDto) -> Result<BacktestResultDto, AppError> {
+        let strategy = BacktestStrategy::parse(&request.strategy).ok_or_else(|| {
+            AppError::invalid_field(
+                "strategy",
+                "Strategy must be one of 'dca', 'lump_sum' or 'rebalance'",
+            )
+        })?;
+        let interval = match request.interval.as_deref() {
+            Some(interval) => BacktestInterval::parse(interval).ok_or_else(|| {
+                AppError::invalid_field("interval", "Interval must be one of 'hourly' or 'daily'")
+            })?,
+            None => BacktestInterval::Daily,
+        };
+        let dca_frequency = match request.dca_frequency.as_deref() {
+            Some(frequency) => DcaFrequency::parse(frequency).ok_or_else(|| {
+                AppError::invalid_field(
+                    "dca_frequency",
+                    "DCA frequency must be one of 'daily', 'weekly', 'biweekly' or 'monthly'",
+                )
+            })?,
+            None => DcaFrequency::Weekly,
+        };
+
+        let now = now_utc();
+        let start = parse_date("start_date", &request.start_date)?;
+        let end = match request.end_date.as_deref() {
+            Some(date) => parse_date("end_date", date)?.min(now),
+            None => now,
+        };
+        if end <= start {
+            return Err(AppError::invalid_field(
+                "end_date",
+                "end_date must be after start_date and before now",
+            ));
+        }
+        let step = interval.step();
+        let points = (end - start).whole_seconds() / step.whole_seconds() + 2;
+        if points > MAX_GRID_POINTS {
+            return Err(AppError::invalid_field(
+                "interval",
+                format!(
+                    "The backtest would have {} points, at most {} are allowed; shorten the period or use a longer interval",
+                    points, MAX_GRID_POINTS
+                ),
+            ));
+        }
+
+        let errors: Vec<FieldError> = request
+            .assets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, asset)| {
+                asset.validate().err().map(|e| {
+                    field_errors(&e).into_iter().map(move |error| FieldError {
+                        field: format!("assets[{}].{}", index, error.field),
+                        message: error.message,
+                    })
+                })
+            })
+            .flatten()
+            .collect();
+        if !errors.is_empty() {
+            return Err(AppError::invalid_fields(errors));
+        }
+
+        let mut seen = HashSet::new();
+        if let Some(duplicate) = request
+            .assets
+            .iter()
+            .find(|asset| !seen.insert(asset.asset_id))
+        {
+            return Err(AppError::invalid_field(
+                "assets",
+                format!("Asset {} is listed more than once", duplicate.asset_id),
+            ));
+        }
+        if strategy == BacktestStrategy::Rebalance && request.assets.len() < 2 {
+            return Err(AppError::invalid_field(
+                "assets",
+                "Rebalancing needs at least 2 assets",
+            ));
+        }
+
+        let asset_ids: Vec<i32> = request.assets.iter().map(|asset| asset.asset_id).collect();
+        let symbols: HashMap<i32, String> = AssetRepository::new(self.pool.as_ref())
+            .get_symbols(&asset_ids)
+            .await?
+            .into_iter()
+            .collect();
+        if let Some(missing) = asset_ids.iter().find(|id| !symbols.contains_key(id)) {
+            return Err(AppError::invalid_field(
+                "assets",
+                format!("Asset {} not found", missing),
+            ));
+        }
+        let weight_sum: f64 = request
+            .assets
+            .iter()
+            .map(|asset| asset.weight.unwrap_or(1.0))
+            .sum();
+        let targets: Vec<f64> = request
+            .assets
+            .iter()
+            .map(|asset| asset.weight.unwrap_or(1.0) / weight_sum)
+            .collect();
+
+        // Rows come sorted by time and asset_id; reorder each point to the order of the request
+        let rows =
+            AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone())
+                .get_price_grid(&asset_ids, start, end, step.as_seconds_f64())
+                .await?;
+        let position: HashMap<i32, usize> = asset_ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| (*id, index))
+            .collect();
+        let grid: Vec<(PrimitiveDateTime, Vec<Option<f64>>)> = rows
+            .chunks(asset_ids.len())
+            .map(|chunk| {
+                let mut prices = vec![None; asset_ids.len()];
+                for row in chunk {
+                    prices[position[&row.asset_id]] = row.price_usd.filter(|price| *price > 0.0);
+                }
+                (chunk[0].at, prices)
+            })
+            .collect();
+
+        // The backtest starts once every asset has a price
+        let first = grid
+            .iter()
+            .position(|(_, prices)| prices.iter().all(Option::is_some));
+        let Some(first) = first else {
+            let unpriced: Vec<&str> = grid
+                .last()
+                .map(|(_, prices)| {
+                    prices
+                        .iter()
+                        .zip(&asset_ids)
+                        .filter(|(price, _)| price.is_none())
+                        .map(|(_, id)| symbols[id].as_str())
+                        .collect()
+                })
+                .unwrap_or_default();
+            return Err(AppError::invalid_field(
+                "assets",
+                format!("No stored prices of {} up to end_date", unpriced.join(", ")),
+            ));
+        };
+        let grid: Vec<(PrimitiveDateTime, Vec<f64>)> = grid
+            .into_iter()
+            .skip(first)
+            .map(|(at, prices)| (at, prices.into_iter().flatten().collect()))
+            .collect();
+
+        let mut simulation = Simulation::new(
+            request.initial_capital_usd,
+            asset_ids.len(),
+            request.fee_rate.unwrap_or(0.0),
+            request.fixed_fee_usd.unwrap_or(0.0),
+        );
+        let curve = match strategy {
+            BacktestStrategy::LumpSum => simulation.lump_sum(&grid, &targets),
+            BacktestStrategy::Rebalance => simulation.rebalance(
+                &grid,
+                &targets,
+                request
+                    .rebalance_threshold
+                    .unwrap_or(DEFAULT_REBALANCE_THRESHOLD),
+            ),
+            BacktestStrategy::Dca => {
+                simulation.dca(&grid, &targets, dca_frequency, request.dca_amount_usd)
+            }
+        };
+
+        let start_at = grid[0].0;
+        let (end_at, end_prices) = &grid[grid.len() - 1];
+        let final_value = simulation.value(end_prices);
+        let initial = request.initial_capital_usd;
+        let days = (*end_at - start_at).as_seconds_f64() / 86_400.0;
+        let annualized_return_pct = (days >= MIN_ANNUALIZED_DAYS)
+            .then(|| ((final_value / initial).powf(DAYS_PER_YEAR / days) - 1.0) * 100.0);
+        let periods_per_year = DAYS_PER_YEAR * 86_400.0 / step.as_seconds_f64();
+
+        let assets = asset_ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| {
+                let amount = simulation.amounts[index];
+                let value_usd = amount * end_prices[index];
+                BacktestAssetResultDto {
+                    asset_id: *id,
+                    symbol: symbols[id].clone(),
+                    target_weight: targets[index],
+                    amount,
+                    price_usd: end_prices[index],
+                    value_usd,
+                    weight: if final_value > 0.0 {
+                        value_usd / final_value
+                    } else {
+                        0.0
+                    },
+                }
+            })
+            .collect();
+
+        Ok(BacktestResultDto {
+            strategy: strategy.as_str().to_string(),
+            start_date: format_iso8601(start_at),
+            end_date: format_iso8601(*end_at),
+            initial_capital_usd: initial,
+            final_value_usd: final_value,
+            cash_usd: simulation.cash,
+            invested_usd: simulation.invested,
+            total_fees_usd: simulation.fees,
+            trades: simulation.trades,
+            rebalances: simulation.rebalances,
+            total_return_pct: (final_value / initial - 1.0) * 100.0,
+            annualized_return_pct,
+            max_drawdown_pct: max_drawdown_pct(&curve),
+            volatility_pct: volatility_pct(&curve, periods_per_year),
+            assets,
+            equity_curve: curve,
+        })
+    }
+}
+
+// State of a simulated portfolio. Every trade pays fee_rate of its notional plus fixed_fee.
+struct Simulation {
+    cash: f64,
+    amounts: Vec<f64>,
+    fee_rate: f64,
+    fixed_fee: f64,
+    invested: f64,
+    fees: f64,
+    trades: u32,
+    rebalances: u32,
+}
+
+impl Simulation {
+    fn new(capital: f64, assets: usize, fee_rate: f64, fixed_fee: f64) -> Self {
+        Self {
+            cash: capital,
+            amounts: vec![0.0; assets],
+            fee_rate,
+            fixed_fee,
+            invested: 0.0,
+            fees: 0.0,
+            trades: 0,
+            rebalances: 0,
+        }
+    }
+
+    fn value(&self, prices: &[f64]) -> f64 {
+        self.cash
+            + self
+                .amounts
+                .iter()
+                .zip(prices)
+                .map(|(amount, price)| amount * price)
+                .sum::<f64>()
+    }
+
+    // Spends up to budget of the cash on an asset, fees included
+    fn buy(&mut self, index: usize, budget: f64, price: f64) {
+        let budget = budget.min(self.cash);
+        let notional = (budget - self.fixed_fee) / (1.0 + self.fee_rate);
+        if notional < MIN_TRADE_USD {
+            return;
+        }
+        let fee = budget - notional;
+        self.cash -= budget;
+        self.amounts[index] += notional / price;
+        self.invested += budget;
+        self.fees += fee;
+        self.trades += 1;
+    }
+
+    // Sells notional worth of an asset, crediting the proceeds after fees to the cash
+    fn sell(&mut self, index: usize, notional: f64, price: f64) {
+        let notional = notional.min(self.amounts[index] * price);
+        let fee = notional * self.fee_rate + self.fixed_fee;
+        if notional < MIN_TRADE_USD || fee >= notional {
+            return;
+        }
+        self.amounts[index] = (self.amounts[index] - notional / price).max(0.0);
+        self.cash += notional - fee;
+        self.fees += fee;
+        self.trades += 1;
+    }
+
+    // Splits a budget over the assets by their target weights
+    fn buy_weighted(&mut self, budget: f64, prices: &[f64], targets: &[f64]) {
+        let budget = budget.min(self.cash);
+        for (index, target) in targets.iter().enumerate() {
+            self.buy(index, budget * target, prices[index]);
+        }
+    }
+
+    fn point(&self, at: PrimitiveDateTime, prices: &[f64]) -> BacktestPointDto {
+        BacktestPointDto {
+            timestamp: format_iso8601(at),
+            value_usd: self.value(prices),
+            cash_usd: self.cash,
+        }
+    }
+
+    // Invests all capital at the first point and holds
+    fn lump_sum(
+        &mut self,
+        grid: &[(PrimitiveDateTime, Vec<f64>)],
+        targets: &[f64],
+    ) -> Vec<BacktestPointDto> {
+        let mut curve = Vec::with_capacity(grid.len());
+        for (index, (at, prices)) in grid.iter().enumerate() {
+            if index == 0 {
+                self.buy_weighted(self.cash, prices, targets);
+            }
+            curve.push(self.point(*at, prices));
+        }
+        curve
+    }
+
+    // Invests all capital at the first point and trades back to the target weights whenever
+    // the weight of an asset drifts from its target by more than threshold. Overweight assets
+    // are sold first so their proceeds fund the underweight ones.
+    fn rebalance(
+        &mut self,
+        grid: &[(PrimitiveDateTime, Vec<f64>)],
+        targets: &[f64],
+        threshold: f64,
+    ) -> Vec<BacktestPointDto> {
+        let mut curve = Vec::with_capacity(grid.len());
+        for (index, (at, prices)) in grid.iter().enumerate() {
+            if index == 0 {
+                self.buy_weighted(self.cash, prices, targets);
+            } else {
+                let total = self.value(prices);
+                let drifted = total > 0.0
+                    && targets.iter().enumerate().any(|(asset, target)| {
+                        (self.amounts[asset] * prices[asset] / total - target).abs() > threshold
+                    });
+                if drifted {
+                    for (asset, target) in targets.iter().enumerate() {
+                        let excess = self.amounts[asset] * prices[asset] - total * target;
+                        if excess > 0.0 {
+                            self.sell(asset, excess, prices[asset]);
+                        }
+                    }
+                    let deficits: Vec<f64> = targets
+                        .iter()
+                        .enumerate()
+                        .map(|(asset, target)| {
+                            (total * target - self.amounts[asset] * prices[asset]).max(0.0)
+                        })
+                        .collect();
+                    let needed: f64 = deficits.iter().sum();
+                    let scale = if needed > 0.0 {
+                        (self.cash / needed).min(1.0)
+                    } else {
+                        0.0
+                    };
+                    for (asset, deficit) in deficits.iter().enumerate() {
+                        self.buy(asset, deficit * scale, prices[asset]);
+                    }
+                    self.rebalances += 1;
+                }
+            }
+            curve.push(self.point(*at, prices));
+        }
+        curve
+    }
+
+    // Buys amount worth of the assets at every occurrence of frequency from the first point,
+    // filled at the first point at or after the occurrence, until the cash runs out. Without an
+    // amount the capital is split equally over all occurrences up to the last point.
+    fn dca(
+        &mut self,
+        grid: &[(PrimitiveDateTime, Vec<f64>)],
+        targets: &[f64],
+        frequency: DcaFrequency,
+        amount: Option<f64>,
+    ) -> Vec<BacktestPointDto> {
+        let (Some((start, _)), Some((end, _))) = (grid.first(), grid.last()) else {
+            return Vec::new();
+        };
+        let (start, end) = (*start, *end);
+        let amount = amount.unwrap_or_else(|| {
+            let mut occurrences = 0;
+            while frequency
+                .occurrence(start, occurrences)
+                .is_some_and(|occurrence| occurrence <= end)
+            {
+                occurrences += 1;
+            }
+            self.cash / occurrences.max(1) as f64
+        });
+
+        let mut curve = Vec::with_capacity(grid.len());
+        let mut next = 0;
+        for (at, prices) in grid {
+            let mut budget = 0.0;
+            while frequency
+                .occurrence(start, next)
+                .is_some_and(|occurrence| occurrence <= *at)
+            {
+                budget += amount;
+                next += 1;
+            }
+            if budget > 0.0 {
+                self.buy_weighted(budget, prices, targets);
+            }
+            curve.push(self.point(*at, prices));
+        }
+        curve
+    }
+}
+
+// Largest fall from a previous peak of the equity curve, in percent
+fn max_drawdown_pct(curve: &[BacktestPointDto]) -> f64 {
+    let mut peak = f64::MIN;
+    let mut drawdown: f64 = 0.0;
+    for point in curve {
+        peak = peak.max(point.value_usd);
+        if peak > 0.0 {
+            drawdown = drawdown.max((peak - point.value_usd) / peak);
+        }
+    }
+    drawdown * 100.0
+}
+
+// Annualized standard deviation of the returns between points of the equity curve, in percent
+fn volatility_pct(curve: &[BacktestPointDto], periods_per_year: f64) -> Option<f64> {
+    let returns: Vec<f64> = curve
+        .windows(2)
+        .filter(|pair| pair[0].value_usd > 0.0)
+        .map(|pair| pair[1].value_usd / pair[0].value_usd - 1.0)
+        .collect();
+    if returns.len() < 2 {
+        return None;
+    }
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns
+        .iter()
+        .map(|value| (value - mean).powi(2))
+        .sum::<f64>()
+        / (returns.len() - 1) as f64;
+    Some(variance.sqrt() * periods_per_year.sqrt() * 100.0)
+}
+
+fn parse_date(field: &str, date: &str) -> Result<PrimitiveDateTime, AppError> {
+    parse_iso8601(date).map_err(|e| AppError::invalid_field(field, e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use time::{Date, Duration, Month, Time};
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn day(n: i64) -> PrimitiveDateTime {
+        PrimitiveDateTime::new(
+            Date::from_calendar_date(2025, Month::January, 1).unwrap(),
+            Time::MIDNIGHT,
+        ) + Duration::days(n)
+    }
+
+    // Daily grid with one point per entry of prices
+    fn grid(prices: &[&[f64]]) -> Vec<(PrimitiveDateTime, Vec<f64>)> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(index, prices)| (day(index as i64), prices.to_vec()))
+            .collect()
+    }
+
+    fn curve(values: &[f64]) -> Vec<BacktestPointDto> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| BacktestPointDto {
+                timestamp: format_iso8601(day(index as i64)),
+                value_usd: *value,
+                cash_usd: 0.0,
+            })
+            .collect()
+    }
+
+    fn values(curve: &[BacktestPointDto]) -> Vec<f64> {
+        curve.iter().map(|point| point.value_usd).collect()
+    }
+
+    #[test]
+    fn lump_sum_invests_everything_at_the_first_point() {
+        let mut simulation = Simulation::new(1000.0, 2, 0.0, 0.0);
+        let curve = simulation.lump_sum(
+            &grid(&[&[10.0, 20.0], &[20.0, 20.0], &[5.0, 10.0]]),
+            &[0.5, 0.5],
+        );
+
+        assert_eq!(values(&curve), vec![1000.0, 1500.0, 500.0]);
+        assert_eq!(simulation.amounts, vec![50.0, 25.0]);
+        assert_eq!(simulation.cash, 0.0);
+        assert_eq!(simulation.invested, 1000.0);
+        assert_eq!(simulation.trades, 2);
+    }
+
+    #[test]
+    fn lump_sum_pays_proportional_and_fixed_fees() {
+        let mut simulation = Simulation::new(1000.0, 2, 0.01, 1.0);
+        let curve = simulation.lump_sum(&grid(&[&[10.0, 10.0]]), &[0.5, 0.5]);
+
+        // Each buy spends 500: 1 fixed, and 1% of the notional of 499 / 1.01
+        let notional = 499.0 / 1.01;
+        assert_close(simulation.amounts[0], notional / 10.0);
+        assert_close(simulation.fees, 2.0 * (500.0 - notional));
+        assert_close(curve[0].value_usd, 2.0 * notional);
+        assert_close(simulation.invested, 1000.0);
+    }
+
+    #[test]
+    fn rebalance_restores_drifted_weights() {
+        let mut simulation = Simulation::new(1000.0, 2, 0.0, 0.0);
+        let curve = simulation.rebalance(
+            &grid(&[&[10.0, 10.0], &[30.0, 10.0], &[30.0, 10.0]]),
+            &[0.5, 0.5],
+            0.05,
+        );
+
+        // At 30 the first asset weighs 0.75, so 500 of it is sold to buy the second
+        assert_eq!(values(&curve), vec![1000.0, 2000.0, 2000.0]);
+        assert_close(simulation.amounts[0], 1000.0 / 30.0);
+        assert_close(simulation.amounts[1], 100.0);
+        assert_close(simulation.cash, 0.0);
+        assert_eq!(simulation.rebalances, 1);
+        assert_eq!(simulation.trades, 4);
+    }
+
+    #[test]
+    fn rebalance_ignores_drift_within_the_threshold() {
+        let mut simulation = Simulation::new(1000.0, 2, 0.0, 0.0);
+        // At 11 the first asset weighs 550 / 1050, about 0.524
+        simulation.rebalance(&grid(&[&[10.0, 10.0], &[11.0, 10.0]]), &[0.5, 0.5], 0.05);
+
+        assert_eq!(simulation.rebalances, 0);
+        assert_eq!(simulation.amounts, vec![50.0, 50.0]);
+    }
+
+    #[test]
+    fn dca_splits_the_capital_over_all_occurrences() {
+        let mut simulation = Simulation::new(300.0, 1, 0.0, 0.0);
+        let curve = simulation.dca(
+            &grid(&[&[10.0], &[20.0], &[30.0]]),
+            &[1.0],
+            DcaFrequency::Daily,
+            None,
+        );
+
+        // 100 buys 10, 5 and 10 / 3 units
+        assert_close(simulation.amounts[0], 10.0 + 5.0 + 10.0 / 3.0);
+        assert_close(curve[2].value_usd, 550.0);
+        assert_eq!(curve[0].cash_usd, 200.0);
+        assert_eq!(simulation.trades, 3);
+    }
+
+    #[test]
+    fn dca_stops_buying_when_the_cash_runs_out() {
+        let mut simulation = Simulation::new(300.0, 1, 0.0, 0.0);
+        simulation.dca(
+            &grid(&[&[10.0], &[10.0], &[10.0]]),
+            &[1.0],
+            DcaFrequency::Daily,
+            Some(200.0),
+        );
+
+        assert_eq!(simulation.amounts, vec![30.0]);
+        assert_eq!(simulation.cash, 0.0);
+        assert_eq!(simulation.trades, 2);
+    }
+
+    #[test]
+    fn dca_fills_occurrences_at_the_next_point() {
+        let mut simulation = Simulation::new(200.0, 1, 0.0, 0.0);
+        // Weekly buys on days 0 and 7 over daily points up to day 8
+        let prices: Vec<&[f64]> = vec![&[10.0]; 9];
+        let curve = simulation.dca(&grid(&prices), &[1.0], DcaFrequency::Weekly, None);
+
+        assert_eq!(curve[6].cash_usd, 100.0);
+        assert_eq!(curve[7].cash_usd, 0.0);
+        assert_eq!(simulation.trades, 2);
+    }
+
+    #[test]
+    fn strategies_handle_empty_and_single_point_grids() {
+        let mut simulation = Simulation::new(100.0, 1, 0.0, 0.0);
+        assert!(simulation
+            .dca(&[], &[1.0], DcaFrequency::Daily, None)
+            .is_empty());
+        assert!(simulation.lump_sum(&[], &[1.0]).is_empty());
+        assert!(simulation.rebalance(&[], &[1.0], 0.05).is_empty());
+        assert_eq!(simulation.trades, 0);
+
+        let single = grid(&[&[4.0]]);
+        let mut simulation = Simulation::new(100.0, 1, 0.0, 0.0);
+        let curve = simulation.dca(&single, &[1.0], DcaFrequency::Monthly, None);
+        assert_eq!(values(&curve), vec![100.0]);
+        assert_eq!(simulation.amounts, vec![25.0]);
+
+        let mut simulation = Simulation::new(100.0, 1, 0.0, 0.0);
+        let curve = simulation.rebalance(&single, &[1.0], 0.05);
+        assert_eq!(values(&curve), vec![100.0]);
+        assert_eq!(simulation.rebalances, 0);
+    }
+
+    #[test]
+    fn trades_below_the_minimum_are_skipped() {
+        let mut simulation = Simulation::new(1.0, 1, 0.0, 1.0);
+        simulation.lump_sum(&grid(&[&[10.0]]), &[1.0]);
+
+        // The fixed fee leaves nothing to invest
+        assert_eq!(simulation.cash, 1.0);
+        assert_eq!(simulation.trades, 0);
+        assert_eq!(simulation.fees, 0.0);
+    }
+
+    #[test]
+    fn max_drawdown_is_the_largest_fall_from_a_peak() {
+        assert_close(
+            max_drawdown_pct(&curve(&[100.0, 120.0, 90.0, 130.0, 65.0])),
+            50.0,
+        );
+        assert_close(max_drawdown_pct(&curve(&[100.0, 110.0, 120.0])), 0.0);
+    }
+
+    #[test]
+    fn max_drawdown_handles_short_and_zero_curves() {
+        assert_eq!(max_drawdown_pct(&[]), 0.0);
+        assert_eq!(max_drawdown_pct(&curve(&[100.0])), 0.0);
+        assert_eq!(max_drawdown_pct(&curve(&[0.0, 0.0])), 0.0);
+        assert_close(max_drawdown_pct(&curve(&[0.0, 100.0, 50.0])), 50.0);
+    }
+
+    #[test]
+    fn volatility_is_the_annualized_sample_deviation_of_returns() {
+        // Returns of +10% and -10% have a sample deviation of sqrt(0.02)
+        let series = curve(&[100.0, 110.0, 99.0]);
+        assert_close(
+            volatility_pct(&series, 1.0).unwrap(),
+            0.02f64.sqrt() * 100.0,
+        );
+        assert_close(
+            volatility_pct(&series, 4.0).unwrap(),
+            0.02f64.sqrt() * 200.0,
+        );
+    }
+
+    #[test]
+    fn volatility_needs_two_returns_from_a_positive_value() {
+        assert_eq!(volatility_pct(&[], 365.25), None);
+        assert_eq!(volatility_pct(&curve(&[100.0]), 365.25), None);
+        assert_eq!(volatility_pct(&curve(&[100.0, 110.0]), 365.25), None);
+        assert_eq!(volatility_pct(&curve(&[0.0, 100.0, 110.0]), 365.25), None);
+        assert_close(
+            volatility_pct(&curve(&[0.0, 100.0, 110.0, 121.0]), 365.25).unwrap(),
+            0.0,
+        );
+    }
+}
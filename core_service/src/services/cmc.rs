@@ -1,54 +1,205 @@
-use crate::models::cmc::{CmcListing, CmcQuote, CmcQuoteResponse, CmcResponse};
-use crate::repository::asset::AssetRepository;
-use anyhow::Result;
+use crate::config::CmcConfig;
+use crate::models::cmc::{CmcListing, CmcQuote, CmcQuoteResponse, CmcResponse, CmcStatusEnvelope};
+use crate::repository::cmc_credit::CmcCreditRepository;
+use actix_web::web;
+use reqwest::StatusCode;
+use serde::de::DeserializeOwned;
 use sqlx::PgPool;
-use std::env;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+// Longest Retry-After delay we are willing to wait for before giving up
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+// Longest exponential backoff between retries, however many retries are configured
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+// Typed failures of CoinMarketCap API calls
+#[derive(Debug)]
+pub enum CmcError {
+    // The request did not complete within the configured timeout
+    Timeout,
+    // The request could not be sent or the response could not be read
+    Network(String),
+    // CoinMarketCap rejected the request because of rate limits (HTTP 429)
+    RateLimited {
+        retry_after: Option<Duration>,
+        message: String,
+    },
+    // CoinMarketCap failed with a server error (HTTP 5xx)
+    Server {
+        status: u16,
+        message: String,
+    },
+    // CoinMarketCap returned a non-zero error_code in the response status
+    Api {
+        error_code: i32,
+        message: String,
+    },
+    // The response body did not match the expected structure
+    Decode(String),
+    // Calls are suspended after repeated failures
+    CircuitOpen {
+        retry_in: Duration,
+    },
+    // The daily credit budget has been used up
+    CreditBudgetExceeded {
+        used: i64,
+        budget: i64,
+    },
+}
+
+impl CmcError {
+    // Whether the call may succeed if retried
+    fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            Self::Timeout | Self::Network(_) | Self::RateLimited { .. } | Self::Server { .. }
+        )
+    }
+
+    // Whether the error indicates CoinMarketCap is unhealthy and counts towards the circuit breaker
+    fn trips_circuit(&self) -> bool {
+        self.is_retryable()
+    }
+
+    // Whether the error means CoinMarketCap data is temporarily unavailable rather than wrong
+    pub fn is_unavailable(&self) -> bool {
+        self.is_retryable()
+            || matches!(
+                self,
+                Self::CircuitOpen { .. } | Self::CreditBudgetExceeded { .. }
+            )
+    }
+
+    // Short label of the error variant, used in metrics
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Timeout => "timeout",
+            Self::Network(_) => "network",
+            Self::RateLimited { .. } => "rate_limited",
+            Self::Server { .. } => "server",
+            Self::Api { .. } => "api",
+            Self::Decode(_) => "decode",
+            Self::CircuitOpen { .. } => "circuit_open",
+            Self::CreditBudgetExceeded { .. } => "credit_budget_exceeded",
+        }
+    }
+
+    fn from_reqwest(err: reqwest::Error) -> Self {
+        if err.is_timeout() {
+            Self::Timeout
+        } else {
+            Self::Network(err.to_string())
+        }
+    }
+}
+
+impl fmt::Display for CmcError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Timeout => write!(f, "CoinMarketCap request timed out"),
+            Self::Network(msg) => write!(f, "CoinMarketCap request failed: {}", msg),
+            Self::RateLimited { message, .. } => {
+                write!(f, "CoinMarketCap rate limit exceeded: {}", message)
+            }
+            Self::Server { status, message } => {
+                write!(f, "CoinMarketCap server error ({}): {}", status, message)
+            }
+            Self::Api {
+                error_code,
+                message,
+            } => write!(f, "CoinMarketCap API error {}: {}", error_code, message),
+            Self::Decode(msg) => write!(f, "Invalid CoinMarketCap response: {}", msg),
+            Self::CircuitOpen { retry_in } => write!(
+                f,
+                "CoinMarketCap calls suspended after repeated failures, retrying in {}s",
+                retry_in.as_secs()
+            ),
+            Self::CreditBudgetExceeded { used, budget } => write!(
+                f,
+                "CoinMarketCap daily credit budget exhausted ({} of {} credits used)",
+                used, budget
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CmcError {}
+
+// State of the circuit breaker shared by all clones of the service
+#[derive(Default)]
+struct CircuitState {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+}
+
+// Call, error and credit counters of the CoinMarketCap client
+#[derive(Debug, Clone)]
+pub struct CmcStats {
+    pub requests: u64,
+    pub credits_used: u64,
+    pub errors: HashMap<&'static str, u64>, // Failed attempts and rejected calls by error kind
+    pub circuit_open: bool,
+}
 
 // Represents a service for interacting with the CoinMarketCap API
 #[derive(Clone)]
 pub struct CmcService {
     client: reqwest::Client,
-    api_key: String,
+    pool: web::Data<PgPool>,
+    config: CmcConfig,
+    circuit: Arc<Mutex<CircuitState>>,
+    requests: Arc<AtomicU64>,
+    credits_used: Arc<AtomicU64>,
+    errors: Arc<Mutex<HashMap<&'static str, u64>>>,
 }
 
 impl CmcService {
-    // Creates a new instance of CmcService with API key from environment
-    pub fn new() -> Self {
-        let api_key = env::var("COINMARKETCAP_API_KEY").expect("COINMARKETCAP_API_KEY must be set");
+    // Creates a new instance of CmcService from the CoinMarketCap configuration
+    pub fn new(pool: web::Data<PgPool>, config: CmcConfig) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(config.timeout())
+            .build()
+            .expect("Failed to build CoinMarketCap HTTP client");
         Self {
-            client: reqwest::Client::new(),
-            api_key,
+            client,
+            pool,
+            config,
+            circuit: Arc::new(Mutex::new(CircuitState::default())),
+            requests: Arc::new(AtomicU64::new(0)),
+            credits_used: Arc::new(AtomicU64::new(0)),
+            errors: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // Returns the counters recorded since startup and whether the circuit breaker is open
+    pub fn stats(&self) -> CmcStats {
+        CmcStats {
+            requests: self.requests.load(Ordering::Relaxed),
+            credits_used: self.credits_used.load(Ordering::Relaxed),
+            errors: self.errors.lock().unwrap().clone(),
+            circuit_open: self.check_circuit().is_err(),
         }
     }
 
     // Fetches the latest cryptocurrency listings from CoinMarketCap
-    pub async fn fetch_cmc_listings(&self) -> Result<Vec<CmcListing>> {
-        let response = self
-            .client
-            .get("https://pro-api.coinmarketcap.com/v1/cryptocurrency/listings/latest")
-            .header("X-CMC_PRO_API_KEY", &self.api_key)
-            .query(&[("start", "1"), ("limit", "1000"), ("convert", "USD")])
-            .send()
+    pub async fn fetch_cmc_listings(&self) -> Result<Vec<CmcListing>, CmcError> {
+        let cmc_response: CmcResponse = self
+            .get(
+                "/v1/cryptocurrency/listings/latest",
+                &[("start", "1"), ("limit", "1000"), ("convert", "USD")],
+            )
             .await?;
-
-        // Parse the response into CmcResponse structure
-        let cmc_response: CmcResponse = response.json().await?;
         Ok(cmc_response.data)
     }
 
-    // Fetches quotes for all assets using their cmc_id
-    pub async fn fetch_quotes_for_assets(&self, pool: &PgPool) -> Result<Vec<(i32, CmcQuote)>> {
-        let asset_repo = AssetRepository::new(pool);
-        let cmc_ids = asset_repo.get_all_cmc_ids().await?;
-
-        if cmc_ids.is_empty() {
-            return Ok(Vec::new());
-        }
-
-        // Batch cmc_ids into chunks of 100 (API limit)
+    // Fetches quotes for the given cmc_ids in batches of 100 (API limit)
+    pub async fn fetch_quotes(&self, cmc_ids: &[i32]) -> Result<Vec<(i32, CmcQuote)>, CmcError> {
         const BATCH_SIZE: usize = 100;
         let mut quotes = Vec::new();
-        let usd = String::from("USD");
 
         for chunk in cmc_ids.chunks(BATCH_SIZE) {
             let ids_str = chunk
@@ -56,21 +207,207 @@ impl CmcService {
                 .map(|id| id.to_string())
                 .collect::<Vec<String>>()
                 .join(",");
-            let response = self
-                .client
-                .get("https://pro-api.coinmarketcap.com/v2/cryptocurrency/quotes/latest")
-                .header("X-CMC_PRO_API_KEY", &self.api_key)
-                .query(&[("id", &ids_str), ("convert", &usd)])
-                .send()
+            let quote_response: CmcQuoteResponse = self
+                .get(
+                    "/v2/cryptocurrency/quotes/latest",
+                    &[("id", &ids_str), ("convert", "USD")],
+                )
                 .await?;
 
-            let quote_response: CmcQuoteResponse = response.json().await?;
             for (cmc_id_str, listing) in quote_response.data {
-                let cmc_id: i32 = cmc_id_str.parse().expect("CMC ID should be an integer");
-                quotes.push((cmc_id, listing.quote.usd.clone()));
+                let cmc_id: i32 = cmc_id_str.parse().map_err(|_| {
+                    CmcError::Decode(format!("CMC ID '{}' is not an integer", cmc_id_str))
+                })?;
+                quotes.push((cmc_id, listing.quote.usd));
             }
         }
 
         Ok(quotes)
     }
+
+    // Performs a GET request with budget and circuit breaker checks, retrying transient failures
+    // with exponential backoff
+    async fn get<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, CmcError> {
+        if let Err(e) = self.check_circuit() {
+            self.count_error(&e);
+            return Err(e);
+        }
+        if let Err(e) = self.check_budget().await {
+            self.count_error(&e);
+            return Err(e);
+        }
+
+        let mut attempt: u32 = 0;
+        loop {
+            let result = self.send(path, query).await;
+            if let Err(e) = &result {
+                self.count_error(e);
+            }
+            match result {
+                Ok(value) => {
+                    self.record_success();
+                    return Ok(value);
+                }
+                Err(e) if e.is_retryable() && attempt < self.config.max_retries => {
+                    let backoff = self
+                        .config
+                        .retry_base_delay()
+                        .saturating_mul(2u32.saturating_pow(attempt))
+                        .min(MAX_BACKOFF);
+                    let delay = match &e {
+                        CmcError::RateLimited {
+                            retry_after: Some(retry_after),
+                            ..
+                        } => (*retry_after).min(MAX_RETRY_AFTER),
+                        _ => backoff,
+                    };
+                    attempt += 1;
+                    log::warn!(
+                        "{} (attempt {}/{}), retrying in {}ms",
+                        e,
+                        attempt,
+                        self.config.max_retries + 1,
+                        delay.as_millis()
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => {
+                    if e.trips_circuit() {
+                        self.record_failure();
+                    }
+                    return Err(e);
+                }
+            }
+        }
+    }
+
+    // Sends a single request, records consumed credits and maps failures to CmcError
+    async fn send<T: DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, &str)],
+    ) -> Result<T, CmcError> {
+        self.requests.fetch_add(1, Ordering::Relaxed);
+        let response = self
+            .client
+            .get(format!("{}{}", self.config.base_url, path))
+            .header("X-CMC_PRO_API_KEY", self.config.api_key())
+            .query(query)
+            .send()
+            .await
+            .map_err(CmcError::from_reqwest)?;
+
+        let status = response.status();
+        let retry_after = response
+            .headers()
+            .get(reqwest::header::RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<u64>().ok())
+            .map(Duration::from_secs);
+        let body = response.text().await.map_err(CmcError::from_reqwest)?;
+
+        let envelope = serde_json::from_str::<CmcStatusEnvelope>(&body).ok();
+        if let Some(envelope) = &envelope {
+            self.record_credits(envelope.status.credit_count).await;
+        }
+        let message = envelope
+            .as_ref()
+            .and_then(|e| e.status.error_message.clone())
+            .unwrap_or_else(|| status.to_string());
+
+        if status == StatusCode::TOO_MANY_REQUESTS {
+            return Err(CmcError::RateLimited {
+                retry_after,
+                message,
+            });
+        }
+        if status.is_server_error() {
+            return Err(CmcError::Server {
+                status: status.as_u16(),
+                message,
+            });
+        }
+        let error_code = envelope.as_ref().map(|e| e.status.error_code).unwrap_or(0);
+        if !status.is_success() || error_code != 0 {
+            return Err(CmcError::Api {
+                error_code: if error_code != 0 {
+                    error_code
+                } else {
+                    status.as_u16() as i32
+                },
+                message,
+            });
+        }
+
+        serde_json::from_str::<T>(&body).map_err(|e| CmcError::Decode(e.to_string()))
+    }
+
+    // Fails fast while the circuit breaker is open
+    fn check_circuit(&self) -> Result<(), CmcError> {
+        let circuit = self.circuit.lock().unwrap();
+        match circuit.open_until {
+            Some(open_until) if Instant::now() < open_until => Err(CmcError::CircuitOpen {
+                retry_in: open_until - Instant::now(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    // Counts a failed call and opens the circuit once the threshold is reached
+    fn record_failure(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+        circuit.consecutive_failures += 1;
+        if circuit.consecutive_failures >= self.config.circuit_breaker_threshold {
+            circuit.open_until = Some(Instant::now() + self.config.circuit_breaker_cooldown());
+            log::error!(
+                "CoinMarketCap circuit breaker opened after {} consecutive failures",
+                circuit.consecutive_failures
+            );
+        }
+    }
+
+    // Closes the circuit after a successful call
+    fn record_success(&self) {
+        let mut circuit = self.circuit.lock().unwrap();
+        if circuit.open_until.is_some() {
+            log::info!("CoinMarketCap circuit breaker closed");
+        }
+        *circuit = CircuitState::default();
+    }
+
+    // Rejects the call when today's credit usage has reached the configured budget
+    async fn check_budget(&self) -> Result<(), CmcError> {
+        let budget = self.config.daily_credit_budget;
+        if budget == 0 {
+            return Ok(());
+        }
+        let repo = CmcCreditRepository::new(self.pool.as_ref());
+        match repo.get_today().await {
+            Ok(used) if used >= budget => Err(CmcError::CreditBudgetExceeded { used, budget }),
+            Ok(_) => Ok(()),
+            Err(e) => {
+                log::warn!("Failed to read CoinMarketCap credit usage: {}", e);
+                Ok(())
+            }
+        }
+    }
+
+    // Counts a failed attempt or rejected call by error kind
+    fn count_error(&self, err: &CmcError) {
+        *self.errors.lock().unwrap().entry(err.kind()).or_insert(0) += 1;
+    }
+
+    // Persists the credits consumed by a call; accounting failures never fail the call
+    async fn record_credits(&self, credits: i32) {
+        self.credits_used
+            .fetch_add(credits.max(0) as u64, Ordering::Relaxed);
+        let repo = CmcCreditRepository::new(self.pool.as_ref());
+        if let Err(e) = repo.record(credits).await {
+            log::warn!("Failed to record CoinMarketCap credit usage: {}", e);
+        }
+    }
 }
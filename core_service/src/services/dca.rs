@@ -0,0 +1,435 @@
+use crate::dto::dca::{
+    ConfirmDcaExecutionDto, CreateDcaPlanDto, DcaExecutionDto, DcaPlanDto, DcaUpcomingExecutionDto,
+    UpdateDcaPlanDto,
+};
+use crate::dto::pagination::PageDto;
+use crate::dto::transaction::{CreateTransactionDto, TransactionDto};
+use crate::error::AppError;
+use crate::models::dca::{
+    DcaExecutionDb, DcaFrequency, DcaPlanDb, DueDcaPlanDb, ExecutionQueryParams, ExecutionSort,
+    UpcomingParams, EXECUTION_PENDING, EXECUTION_STATUSES,
+};
+use crate::models::pagination::PageParams;
+use crate::repository::asset::AssetRepository;
+use crate::repository::dca::DcaRepository;
+use crate::repository::wallet::WalletRepository;
+use crate::services::transaction::TransactionService;
+use crate::utils::datetime::{format_iso8601, now_utc, parse_iso8601};
+use actix_web::web;
+use anyhow::{anyhow, Result};
+use sqlx::types::time::PrimitiveDateTime;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+// How often due plans are checked for executions to generate
+const CHECK_INTERVAL: Duration = Duration::from_secs(60);
+// Defaults of GET /dca/upcoming
+const DEFAULT_UPCOMING_DAYS: i64 = 30;
+const DEFAULT_UPCOMING_LIMIT: usize = 100;
+
+// Service for recurring dollar-cost-averaging plans. A scheduler generates a pending execution
+// at every interval of an active plan, priced at the latest stored price of its asset, which is
+// then confirmed as a buy transaction, optionally adjusted, or skipped.
+#[derive(Clone)]
+pub struct DcaService {
+    pool: web::Data<PgPool>,
+    transaction_service: web::Data<TransactionService>,
+}
+
+impl DcaService {
+    // Creates a new instance of DcaService
+    pub fn new(
+        pool: web::Data<PgPool>,
+        transaction_service: web::Data<TransactionService>,
+    ) -> Self {
+        Self {
+            pool,
+            transaction_service,
+        }
+    }
+
+    // Creates a new plan. Executions scheduled before now are not generated, so a plan
+    // starting in the past begins with its next interval.
+    pub async fn create_plan(&self, plan: CreateDcaPlanDto) -> Result<DcaPlanDto, AppError> {
+        if !AssetRepository::new(self.pool.as_ref())
+            .exists(plan.asset_id)
+            .await?
+        {
+            return Err(AppError::invalid_field(
+                "asset_id",
+                format!("Asset {} not found", plan.asset_id),
+            ));
+        }
+        if !WalletRepository::new(self.pool.as_ref())
+            .exists(plan.wallet_id)
+            .await?
+        {
+            return Err(AppError::invalid_field(
+                "wallet_id",
+                format!("Wallet {} not found", plan.wallet_id),
+            ));
+        }
+
+        let frequency = parse_frequency(&plan.frequency)?;
+        let start_date = parse_date("start_date", &plan.start_date)?;
+        let end_date = plan
+            .end_date
+            .as_deref()
+            .map(|date| parse_date("end_date", date))
+            .transpose()?;
+        check_end_date(start_date, end_date)?;
+        let next_execution_at = first_execution_from(frequency, start_date, end_date, now_utc());
+
+        let repo = DcaRepository::new(self.pool.as_ref());
+        let id = repo
+            .create_plan(&plan, start_date, end_date, next_execution_at)
+            .await?;
+        self.get_plan(id).await
+    }
+
+    // Retrieves all plans
+    pub async fn get_plans(&self) -> Result<Vec<DcaPlanDto>, AppError> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        let plans = repo.get_plans().await?;
+        Ok(plans.into_iter().map(DcaPlanDto::from).collect())
+    }
+
+    // Retrieves a plan or fails with 404
+    pub async fn get_plan(&self, id: i32) -> Result<DcaPlanDto, AppError> {
+        self.get_plan_record(id).await.map(DcaPlanDto::from)
+    }
+
+    // Updates the settings of a plan. Changing the frequency or resuming a paused plan
+    // reschedules it from now on; executions missed while paused are not generated.
+    pub async fn update_plan(
+        &self,
+        id: i32,
+        update: UpdateDcaPlanDto,
+    ) -> Result<DcaPlanDto, AppError> {
+        let mut plan = self.get_plan_record(id).await?;
+        let current_frequency = parse_frequency(&plan.frequency)?;
+        let frequency = match &update.frequency {
+            Some(frequency) => parse_frequency(frequency)?,
+            None => current_frequency,
+        };
+        match &update.end_date {
+            Some(Some(end_date)) => {
+                let end_date = parse_date("end_date", end_date)?;
+                check_end_date(plan.start_date, Some(end_date))?;
+                plan.end_date = Some(end_date);
+            }
+            Some(None) => plan.end_date = None,
+            None => {}
+        }
+        let resumed = update.active == Some(true) && !plan.active;
+
+        if frequency != current_frequency || resumed || plan.next_execution_at.is_none() {
+            plan.next_execution_at =
+                first_execution_from(frequency, plan.start_date, plan.end_date, now_utc());
+        }
+        if let (Some(next), Some(end)) = (plan.next_execution_at, plan.end_date) {
+            if next > end {
+                plan.next_execution_at = None;
+            }
+        }
+        plan.frequency = frequency.as_str().to_string();
+        plan.amount_usd = update.amount_usd.unwrap_or(plan.amount_usd);
+        plan.active = update.active.unwrap_or(plan.active);
+        if let Some(notes) = update.notes {
+            plan.notes = notes;
+        }
+
+        let repo = DcaRepository::new(self.pool.as_ref());
+        if !repo.update_plan(&plan).await? {
+            return Err(plan_not_found(id));
+        }
+        self.get_plan(id).await
+    }
+
+    // Deletes a plan with its executions; transactions of confirmed executions are kept
+    pub async fn delete_plan(&self, id: i32) -> Result<(), AppError> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        if !repo.delete_plan(id).await? {
+            return Err(plan_not_found(id));
+        }
+        Ok(())
+    }
+
+    // Retrieves a page of executions, optionally of one plan and with one status
+    pub async fn get_executions(
+        &self,
+        query: ExecutionQueryParams,
+        page: PageParams<ExecutionSort>,
+    ) -> Result<PageDto<DcaExecutionDto>, AppError> {
+        if let Some(status) = &query.status {
+            if !EXECUTION_STATUSES.contains(&status.as_str()) {
+                return Err(AppError::invalid_field(
+                    "status",
+                    format!(
+                        "Invalid status '{}', expected one of: {}",
+                        status,
+                        EXECUTION_STATUSES.join(", ")
+                    ),
+                ));
+            }
+        }
+
+        let repo = DcaRepository::new(self.pool.as_ref());
+        let (executions, total) = repo
+            .get_executions(query.plan_id, query.status, &page)
+            .await?;
+        let items = executions.into_iter().map(DcaExecutionDto::from).collect();
+        Ok(PageDto::new(items, total, &page))
+    }
+
+    // Confirms a pending execution as a buy transaction. The price, amount, fee and notes can be
+    // adjusted; by default the amount is the USD amount of the execution at its price.
+    pub async fn confirm_execution(
+        &self,
+        id: i32,
+        confirmation: ConfirmDcaExecutionDto,
+    ) -> Result<DcaExecutionDto, AppError> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        let execution = self.get_pending_execution(id).await?;
+        // A non-positive stored price is treated as missing, as when the execution was generated
+        let stored_price = execution.price.filter(|price| *price > 0.0);
+        let price = confirmation.price.or(stored_price).ok_or_else(|| {
+            AppError::invalid_field(
+                "price",
+                format!(
+                    "No positive price of {} was stored when the execution was generated, so a price is required",
+                    execution.asset
+                ),
+            )
+        })?;
+        let notes = match confirmation.notes {
+            Some(notes) => Some(notes),
+            None => self.get_plan_record(execution.plan_id).await?.notes,
+        };
+        let transaction = CreateTransactionDto {
+            asset_id: execution.asset_id,
+            wallet_id: execution.wallet_id,
+            amount: confirmation.amount.unwrap_or(execution.amount_usd / price),
+            price,
+            transaction_type: "BUY".to_string(),
+            fee: confirmation.fee,
+            notes,
+        };
+
+        let record = repo
+            .confirm_execution(id, &transaction)
+            .await?
+            .ok_or_else(|| already_resolved(id))?;
+        self.transaction_service
+            .notify_created(&TransactionDto::from(record))
+            .await;
+        self.get_execution(id).await
+    }
+
+    // Skips a pending execution without creating a transaction
+    pub async fn skip_execution(&self, id: i32) -> Result<DcaExecutionDto, AppError> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        self.get_pending_execution(id).await?;
+        if !repo.skip_execution(id).await? {
+            return Err(already_resolved(id));
+        }
+        self.get_execution(id).await
+    }
+
+    // Lists the executions of active plans scheduled within the next days, including due ones
+    // not generated yet, with their amount estimated at the latest stored price
+    pub async fn get_upcoming(
+        &self,
+        params: &UpcomingParams,
+    ) -> Result<Vec<DcaUpcomingExecutionDto>, AppError> {
+        if let Some(plan_id) = params.plan_id {
+            self.get_plan_record(plan_id).await?;
+        }
+        let days = params.days.unwrap_or(DEFAULT_UPCOMING_DAYS);
+        let limit = params.limit.unwrap_or(DEFAULT_UPCOMING_LIMIT);
+        let until = now_utc() + time::Duration::days(days);
+
+        let repo = DcaRepository::new(self.pool.as_ref());
+        let plans = repo.get_scheduled_plans(params.plan_id).await?;
+        let mut upcoming = Vec::new();
+        for plan in plans {
+            let frequency = parse_frequency(&plan.frequency)?;
+            let mut scheduled_at = plan.next_execution_at;
+            let mut count = 0;
+            while let Some(at) = scheduled_at {
+                if at > until || plan.end_date.is_some_and(|end| at > end) || count == limit {
+                    break;
+                }
+                upcoming.push((at, upcoming_execution(&plan, at)));
+                scheduled_at = frequency.first_after(plan.start_date, at);
+                count += 1;
+            }
+        }
+        upcoming.sort_by(|(a, a_dto), (b, b_dto)| a.cmp(b).then(a_dto.plan_id.cmp(&b_dto.plan_id)));
+        Ok(upcoming
+            .into_iter()
+            .take(limit)
+            .map(|(_, execution)| execution)
+            .collect())
+    }
+
+    // Generates the executions of due plans every minute until cancelled
+    pub async fn run(&self, cancel: CancellationToken) {
+        let mut interval = tokio::time::interval(CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = interval.tick() => {}
+            }
+            match self.generate_due().await {
+                Ok(0) => {}
+                Ok(count) => log::info!("Generated {} pending DCA executions", count),
+                Err(e) => log::error!("Failed to generate DCA executions: {}", e),
+            }
+        }
+    }
+
+    // Generates a pending execution for every interval of the active plans that is due,
+    // catching up on intervals missed while the scheduler was not running, and returns the
+    // number generated
+    async fn generate_due(&self) -> Result<usize> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        let now = now_utc();
+        let mut generated = 0;
+        for plan in repo.get_due_plans(now).await? {
+            generated += self.generate_plan(&repo, &plan, now).await?;
+        }
+        Ok(generated)
+    }
+
+    // Generates the due executions of one plan, returning the number generated
+    async fn generate_plan(
+        &self,
+        repo: &DcaRepository<'_>,
+        plan: &DueDcaPlanDb,
+        now: PrimitiveDateTime,
+    ) -> Result<usize> {
+        let frequency = DcaFrequency::parse(&plan.frequency).ok_or_else(|| {
+            anyhow!(
+                "Unknown frequency '{}' of DCA plan {}",
+                plan.frequency,
+                plan.id
+            )
+        })?;
+        let mut generated = 0;
+        let mut scheduled_at = plan.next_execution_at;
+        loop {
+            let next = frequency
+                .first_after(plan.start_date, scheduled_at)
+                .filter(|next| plan.end_date.is_none_or(|end| *next <= end));
+            // The plan was changed meanwhile; it is picked up again on the next check
+            if !repo.schedule_execution(plan.id, scheduled_at, next).await? {
+                return Ok(generated);
+            }
+            generated += 1;
+            match next {
+                Some(next) if next <= now => scheduled_at = next,
+                _ => return Ok(generated),
+            }
+        }
+    }
+
+    async fn get_plan_record(&self, id: i32) -> Result<DcaPlanDb, AppError> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        repo.get_plan(id).await?.ok_or_else(|| plan_not_found(id))
+    }
+
+    async fn get_execution(&self, id: i32) -> Result<DcaExecutionDto, AppError> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        repo.get_execution(id)
+            .await?
+            .map(DcaExecutionDto::from)
+            .ok_or_else(|| execution_not_found(id))
+    }
+
+    // Retrieves an execution, failing with 404 if it does not exist and 409 if it is resolved
+    async fn get_pending_execution(&self, id: i32) -> Result<DcaExecutionDb, AppError> {
+        let repo = DcaRepository::new(self.pool.as_ref());
+        let execution = repo
+            .get_execution(id)
+            .await?
+            .ok_or_else(|| execution_not_found(id))?;
+        if execution.status != EXECUTION_PENDING {
+            return Err(already_resolved(id));
+        }
+        Ok(execution)
+    }
+}
+
+// First execution of a plan at or after t, unless it is past the end date
+fn first_execution_from(
+    frequency: DcaFrequency,
+    start_date: PrimitiveDateTime,
+    end_date: Option<PrimitiveDateTime>,
+    t: PrimitiveDateTime,
+) -> Option<PrimitiveDateTime> {
+    frequency
+        .first_from(start_date, t)
+        .filter(|next| end_date.is_none_or(|end| *next <= end))
+}
+
+// Builds an upcoming execution of a plan, estimated at the latest stored price
+fn upcoming_execution(
+    plan: &DcaPlanDb,
+    scheduled_at: PrimitiveDateTime,
+) -> DcaUpcomingExecutionDto {
+    DcaUpcomingExecutionDto {
+        plan_id: plan.id,
+        asset_id: plan.asset_id,
+        asset: plan.asset.clone(),
+        wallet_id: plan.wallet_id,
+        wallet: plan.wallet.clone(),
+        scheduled_at: format_iso8601(scheduled_at),
+        amount_usd: plan.amount_usd,
+        estimated_price: plan.latest_price_usd,
+        estimated_amount: plan
+            .latest_price_usd
+            .filter(|price| *price > 0.0)
+            .map(|price| plan.amount_usd / price),
+    }
+}
+
+fn parse_frequency(frequency: &str) -> Result<DcaFrequency, AppError> {
+    DcaFrequency::parse(frequency).ok_or_else(|| {
+        AppError::invalid_field(
+            "frequency",
+            "Frequency must be one of 'daily', 'weekly', 'biweekly' or 'monthly'",
+        )
+    })
+}
+
+fn parse_date(field: &str, date: &str) -> Result<PrimitiveDateTime, AppError> {
+    parse_iso8601(date).map_err(|e| AppError::invalid_field(field, e.to_string()))
+}
+
+// Checks that the end date of a plan is not before its start date
+fn check_end_date(
+    start_date: PrimitiveDateTime,
+    end_date: Option<PrimitiveDateTime>,
+) -> Result<(), AppError> {
+    match end_date {
+        Some(end_date) if end_date < start_date => Err(AppError::invalid_field(
+            "end_date",
+            "end_date must not be before start_date",
+        )),
+        _ => Ok(()),
+    }
+}
+
+fn plan_not_found(id: i32) -> AppError {
+    AppError::not_found(format!("DCA plan {} not found", id))
+}
+
+fn execution_not_found(id: i32) -> AppError {
+    AppError::not_found(format!("DCA execution {} not found", id))
+}
+
+fn already_resolved(id: i32) -> AppError {
+    AppError::Conflict(format!("DCA execution {} is no longer pending", id))
+}
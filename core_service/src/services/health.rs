@@ -0,0 +1,219 @@
+use crate::config::HealthConfig;
+use crate::dto::health::{ComponentHealthDto, ReadinessComponentsDto, ReadinessDto, TaskStatusDto};
+use crate::repository::asset_price::AssetPriceRepository;
+use crate::services::price_refresh::{PriceRefreshService, RefreshTier};
+use crate::services::redis::RedisService;
+use crate::services::supervisor::TaskSupervisor;
+use crate::utils::datetime::format_iso8601;
+use actix_web::web;
+use chrono::Utc;
+use sqlx::types::time::OffsetDateTime;
+use sqlx::PgPool;
+use std::time::{Duration, Instant};
+
+// Service checking whether the application and its dependencies can serve requests
+#[derive(Clone)]
+pub struct HealthService {
+    pool: web::Data<PgPool>,
+    redis_service: web::Data<RedisService>,
+    price_refresh_service: web::Data<PriceRefreshService>,
+    supervisor: web::Data<TaskSupervisor>,
+    max_price_age: Duration,
+    max_refresh_failures: u32,
+}
+
+impl HealthService {
+    // Creates a new HealthService with the configured staleness and refresh failure thresholds
+    pub fn new(
+        pool: web::Data<PgPool>,
+        redis_service: web::Data<RedisService>,
+        price_refresh_service: web::Data<PriceRefreshService>,
+        supervisor: web::Data<TaskSupervisor>,
+        config: &HealthConfig,
+    ) -> Self {
+        Self {
+            pool,
+            redis_service,
+            price_refresh_service,
+            supervisor,
+            max_price_age: Duration::from_secs(config.max_price_age_secs),
+            max_refresh_failures: config.max_refresh_failures,
+        }
+    }
+
+    // Checks all components; the service is ready when every critical component is up
+    pub async fn check_readiness(&self) -> ReadinessDto {
+        let (database, redis, prices) = tokio::join!(
+            self.check_database(),
+            self.check_redis(),
+            self.check_prices()
+        );
+        let components = ReadinessComponentsDto {
+            database,
+            redis,
+            prices,
+            held_price_refresh: self.check_refresh_job(RefreshTier::Held),
+            tracked_price_refresh: self.check_refresh_job(RefreshTier::Tracked),
+        };
+
+        let ready = [
+            &components.database,
+            &components.redis,
+            &components.prices,
+            &components.held_price_refresh,
+            &components.tracked_price_refresh,
+        ]
+        .iter()
+        .all(|component| !component.critical || component.status == "up");
+
+        ReadinessDto {
+            status: if ready { "ready" } else { "not_ready" }.to_string(),
+            components,
+            checked_at: Utc::now().to_rfc3339(),
+        }
+    }
+
+    // Returns the status of all supervised background tasks
+    pub fn get_tasks(&self) -> Vec<TaskStatusDto> {
+        self.supervisor
+            .statuses()
+            .into_iter()
+            .map(|(name, status)| TaskStatusDto {
+                name,
+                state: status.state.as_str().to_string(),
+                restarts: status.restarts,
+                started_at: status.started_at.to_rfc3339(),
+                last_error: status.last_error,
+                last_error_at: status.last_error_at.map(|at| at.to_rfc3339()),
+            })
+            .collect()
+    }
+
+    // Runs a trivial query through the pool
+    async fn check_database(&self) -> ComponentHealthDto {
+        let started = Instant::now();
+        let result = sqlx::query("SELECT 1").execute(self.pool.as_ref()).await;
+        component(true, result.err().map(|e| e.to_string()), Some(started))
+    }
+
+    // Pings Redis; the service stays ready without it because prices fall back to the in-process cache
+    async fn check_redis(&self) -> ComponentHealthDto {
+        let started = Instant::now();
+        let result = self.redis_service.ping().await;
+        component(false, result.err().map(|e| e.to_string()), Some(started))
+    }
+
+    // Compares the age of the latest price of each held and tracked asset with the staleness
+    // threshold of its tier; ready when nothing is held or tracked
+    async fn check_prices(&self) -> ComponentHealthDto {
+        let price_repo =
+            AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone());
+        let mut checked = false;
+        let mut errors = Vec::new();
+        for tier in [RefreshTier::Held, RefreshTier::Tracked] {
+            let stalest = match self.price_refresh_service.tier_cmc_ids(tier).await {
+                Ok(cmc_ids) if cmc_ids.is_empty() => continue,
+                Ok(cmc_ids) => price_repo.get_stalest_price(&cmc_ids).await,
+                Err(e) => Err(e),
+            };
+            checked = true;
+            match stalest {
+                Ok(Some((symbol, newest))) => {
+                    let age = (OffsetDateTime::now_utc() - newest.assume_utc()).whole_seconds();
+                    let max_age = self.max_age(tier).as_secs() as i64;
+                    if age > max_age {
+                        errors.push(format!(
+                            "Latest price of {} asset {} from {} is {}s old (max {}s)",
+                            tier,
+                            symbol,
+                            format_iso8601(newest),
+                            age,
+                            max_age
+                        ));
+                    }
+                }
+                // Assets never priced yet are covered by the refresh job checks
+                Ok(None) => {}
+                Err(e) => errors.push(format!("Failed to read {} asset prices: {}", tier, e)),
+            }
+        }
+        if !checked {
+            return ComponentHealthDto {
+                message: Some("No held or tracked assets".to_string()),
+                ..component(true, None, None)
+            };
+        }
+        component(true, (!errors.is_empty()).then(|| errors.join("; ")), None)
+    }
+
+    // Reports the latest background refresh of a tier; it is only down after
+    // max_refresh_failures consecutive failures or once the last success is older than the
+    // staleness threshold of the tier
+    fn check_refresh_job(&self, tier: RefreshTier) -> ComponentHealthDto {
+        let Some(run) = self.price_refresh_service.last_run(tier) else {
+            return ComponentHealthDto {
+                message: Some("Not run yet".to_string()),
+                ..component(true, None, None)
+            };
+        };
+        let max_age = self.max_age(tier).as_secs() as i64;
+        let success_age = run
+            .last_success_at
+            .map(|at| (Utc::now() - at).num_seconds());
+
+        if run.consecutive_failures >= self.max_refresh_failures {
+            return component(
+                true,
+                Some(format!(
+                    "Last {} runs failed: {}",
+                    run.consecutive_failures,
+                    run.error.unwrap_or_default()
+                )),
+                None,
+            );
+        }
+        if let Some(age) = success_age.filter(|age| *age > max_age) {
+            return component(
+                true,
+                Some(format!(
+                    "Last successful run was {}s ago (max {}s)",
+                    age, max_age
+                )),
+                None,
+            );
+        }
+        let message = match (run.error, run.last_success_at) {
+            (Some(error), _) => format!(
+                "Last run failed ({} of {} tolerated consecutive failures): {}",
+                run.consecutive_failures, self.max_refresh_failures, error
+            ),
+            (None, Some(at)) => format!("Last run succeeded at {}", at.to_rfc3339()),
+            (None, None) => "Not run yet".to_string(),
+        };
+        ComponentHealthDto {
+            message: Some(message),
+            ..component(true, None, None)
+        }
+    }
+
+    // Age after which the prices of a tier are stale; tiers refreshed less often than the
+    // configured threshold get two of their intervals
+    fn max_age(&self, tier: RefreshTier) -> Duration {
+        self.max_price_age
+            .max(self.price_refresh_service.interval(tier) * 2)
+    }
+}
+
+// Builds a component status from an optional error message
+fn component(
+    critical: bool,
+    error: Option<String>,
+    started: Option<Instant>,
+) -> ComponentHealthDto {
+    ComponentHealthDto {
+        status: if error.is_none() { "up" } else { "down" }.to_string(),
+        critical,
+        message: error,
+        latency_ms: started.map(|started| started.elapsed().as_millis() as u64),
+    }
+}
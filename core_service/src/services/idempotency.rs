@@ -0,0 +1,300 @@
+use crate::config::IdempotencyConfig;
+use crate::error::AppError;
+use crate::models::idempotency::{IdempotencyClaim, IdempotencyKeyDb};
+use crate::repository::idempotency_key::IdempotencyKeyRepository;
+use actix_web::body::{self, BoxBody, MessageBody};
+use actix_web::dev::{Payload, ServiceRequest, ServiceResponse};
+use actix_web::http::header::{self, HeaderName, HeaderValue};
+use actix_web::http::{Method, StatusCode};
+use actix_web::middleware::Next;
+use actix_web::{web, Error, HttpResponse, ResponseError};
+use anyhow::{anyhow, Result};
+use sha2::{Digest, Sha256};
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+use uuid::Uuid;
+
+// Header carrying the client-chosen key of a mutating request
+const IDEMPOTENCY_KEY_HEADER: HeaderName = HeaderName::from_static("idempotency-key");
+// Header marking responses replayed from an earlier request
+const REPLAYED_HEADER: HeaderName = HeaderName::from_static("idempotent-replayed");
+// Longest accepted key
+pub const MAX_KEY_LEN: usize = 255;
+// A key without a stored response is considered abandoned after its request sent no heartbeat
+// for this long, e.g. when the instance handling it stopped, and can be claimed by a retry
+const STALE_CLAIM_SECS: i64 = 60;
+// How often a running request refreshes the claim of its key
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+// How often expired keys are deleted
+const PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+// Outcome of claiming the key of a request
+enum Claim {
+    Claimed,                     // First use of the key; the request is executed
+    Completed(IdempotencyKeyDb), // Same request already completed; its response is replayed
+    InProgress,                  // Same request still being processed
+    Mismatch,                    // Key already used for a different request
+}
+
+// Service storing the responses of mutating requests sent with an Idempotency-Key header, so
+// retried requests are answered with the first response instead of being executed again
+#[derive(Clone)]
+pub struct IdempotencyService {
+    pool: web::Data<PgPool>,
+    key_ttl_secs: i64,
+}
+
+impl IdempotencyService {
+    // Creates a new IdempotencyService with the configured key lifetime
+    pub fn new(pool: web::Data<PgPool>, config: &IdempotencyConfig) -> Self {
+        Self {
+            pool,
+            key_ttl_secs: config.key_ttl_secs as i64,
+        }
+    }
+
+    // Claims a key for a request, or reports how an earlier use of the key is to be answered
+    async fn claim(&self, claim: &IdempotencyClaim<'_>) -> Result<Claim> {
+        let repo = IdempotencyKeyRepository::new(self.pool.as_ref());
+        // A key deleted between the two queries is claimed on the second attempt
+        for _ in 0..2 {
+            if repo
+                .claim(claim, self.key_ttl_secs, STALE_CLAIM_SECS)
+                .await?
+            {
+                return Ok(Claim::Claimed);
+            }
+            if let Some(record) = repo.get(claim.key).await? {
+                return Ok(if record.request_hash != claim.request_hash {
+                    Claim::Mismatch
+                } else if record.response_status.is_none() {
+                    Claim::InProgress
+                } else {
+                    Claim::Completed(record)
+                });
+            }
+        }
+        Err(anyhow!("Failed to claim idempotency key {}", claim.key))
+    }
+
+    // Deletes expired keys until cancelled
+    pub async fn run(&self, cancel: CancellationToken) {
+        let repo = IdempotencyKeyRepository::new(self.pool.as_ref());
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = prune.tick() => {
+                    match repo.delete_older_than(self.key_ttl_secs).await {
+                        Ok(0) => {}
+                        Ok(count) => log::debug!("Deleted {} expired idempotency keys", count),
+                        Err(e) => log::warn!("Failed to delete expired idempotency keys: {}", e),
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Middleware making POST, PUT and PATCH requests with an Idempotency-Key header safe to retry.
+// The first response (other than a server error) is stored with a hash of the request and
+// replayed for identical requests with the same key; the key is rejected with 409 Conflict
+// when it comes with a different request or while its first request is still being processed.
+pub async fn idempotency_middleware(
+    mut req: ServiceRequest,
+    next: Next<impl MessageBody + 'static>,
+) -> Result<ServiceResponse<BoxBody>, Error> {
+    if !matches!(*req.method(), Method::POST | Method::PUT | Method::PATCH) {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    }
+    let key = match req.headers().get(&IDEMPOTENCY_KEY_HEADER) {
+        Some(value) => match parse_key(value) {
+            Ok(key) => key,
+            Err(e) => return Ok(reject(req, e)),
+        },
+        None => return Ok(next.call(req).await?.map_into_boxed_body()),
+    };
+    let Some(service) = req.app_data::<web::Data<IdempotencyService>>().cloned() else {
+        return Ok(next.call(req).await?.map_into_boxed_body());
+    };
+
+    // The body is read to hash it and then handed back to the handler
+    let body = match req.extract::<web::Bytes>().await {
+        Ok(body) => body,
+        Err(e) => {
+            let response = e.error_response();
+            return Ok(req.into_response(response));
+        }
+    };
+    let request_hash = hash_request(&req, &body);
+    req.set_payload(Payload::from(body));
+
+    let method = req.method().to_string();
+    let path = req.path().to_string();
+    let token = Uuid::new_v4();
+    let claim = IdempotencyClaim {
+        key: &key,
+        token,
+        request_hash: &request_hash,
+        method: &method,
+        path: &path,
+    };
+    let claim = match service.claim(&claim).await {
+        Ok(claim) => claim,
+        Err(e) => return Ok(reject(req, e.into())),
+    };
+    match claim {
+        Claim::Claimed => {}
+        Claim::Completed(record) => {
+            log::info!("Replaying stored response of idempotency key {}", key);
+            let response = replay(&record);
+            return Ok(req.into_response(response));
+        }
+        Claim::InProgress => {
+            let error = AppError::Conflict(
+                "A request with this Idempotency-Key is still being processed".to_string(),
+            );
+            return Ok(reject(req, error));
+        }
+        Claim::Mismatch => {
+            let error = AppError::Conflict(
+                "This Idempotency-Key was already used for a different request".to_string(),
+            );
+            return Ok(reject(req, error));
+        }
+    }
+
+    // The claim is kept fresh while the handler runs, however long it takes
+    let repo = IdempotencyKeyRepository::new(service.pool.as_ref());
+    let handler = next.call(req);
+    tokio::pin!(handler);
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.tick().await;
+    let result = loop {
+        tokio::select! {
+            result = &mut handler => break result,
+            _ = heartbeat.tick() => match repo.heartbeat(&key, token).await {
+                Ok(true) => {}
+                Ok(false) => log::warn!("Idempotency key {} was claimed by another request", key),
+                Err(e) => log::warn!("Failed to refresh idempotency key {}: {}", key, e),
+            },
+        }
+    };
+    let response = match result {
+        Ok(response) => response.map_into_boxed_body(),
+        Err(e) => {
+            release(&repo, &key, token).await;
+            return Err(e);
+        }
+    };
+    // Server errors are not final, so the key is released for a retry
+    if response.status().is_server_error() {
+        release(&repo, &key, token).await;
+        return Ok(response);
+    }
+
+    let (req, response) = response.into_parts();
+    let status = response.status();
+    let content_type = response
+        .headers()
+        .get(header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let (response, body) = response.into_parts();
+    // The request already took effect, so failing to read or store its response keeps the key
+    // claimed until it goes stale instead of releasing it
+    let body = match body::to_bytes(body).await {
+        Ok(body) => body,
+        Err(e) => {
+            let error = AppError::Internal(anyhow!("Failed to read the response: {}", e));
+            return Ok(ServiceResponse::new(req, error.error_response()));
+        }
+    };
+    match repo
+        .complete(
+            &key,
+            token,
+            status.as_u16() as i32,
+            content_type.as_deref(),
+            &body,
+        )
+        .await
+    {
+        Ok(true) => {}
+        Ok(false) => log::warn!(
+            "Response of idempotency key {} not stored: the key was claimed by another request",
+            key
+        ),
+        Err(e) => log::error!(
+            "Failed to store the response of idempotency key {}: {}",
+            key,
+            e
+        ),
+    }
+    Ok(ServiceResponse::new(
+        req,
+        response.set_body(BoxBody::new(body)),
+    ))
+}
+
+// Answers a request with an error. The response is rendered here rather than returned as an
+// error, so it is built while the request id of the request is known.
+fn reject(req: ServiceRequest, error: AppError) -> ServiceResponse<BoxBody> {
+    let response = error.error_response();
+    req.into_response(response)
+}
+
+// Validates the Idempotency-Key header: 1 to MAX_KEY_LEN visible ASCII characters
+fn parse_key(value: &HeaderValue) -> Result<String, AppError> {
+    value
+        .to_str()
+        .ok()
+        .filter(|key| {
+            !key.is_empty() && key.len() <= MAX_KEY_LEN && key.bytes().all(|b| b.is_ascii_graphic())
+        })
+        .map(str::to_string)
+        .ok_or_else(|| {
+            AppError::invalid_field(
+                "Idempotency-Key",
+                format!(
+                    "Idempotency-Key must be 1 to {} visible ASCII characters",
+                    MAX_KEY_LEN
+                ),
+            )
+        })
+}
+
+// Hex-encoded SHA-256 of the method, path, query string and body of a request
+fn hash_request(req: &ServiceRequest, body: &[u8]) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(req.method().as_str());
+    hasher.update(b"\n");
+    hasher.update(req.path());
+    hasher.update(b"?");
+    hasher.update(req.query_string());
+    hasher.update(b"\n");
+    hasher.update(body);
+    hex::encode(hasher.finalize())
+}
+
+// Rebuilds a stored response, marked as replayed
+fn replay(record: &IdempotencyKeyDb) -> HttpResponse {
+    let status = record
+        .response_status
+        .and_then(|status| StatusCode::from_u16(status as u16).ok())
+        .unwrap_or(StatusCode::OK);
+    let mut response = HttpResponse::build(status);
+    if let Some(content_type) = &record.response_content_type {
+        response.insert_header((header::CONTENT_TYPE, content_type.as_str()));
+    }
+    response.insert_header((REPLAYED_HEADER, "true"));
+    response.body(record.response_body.clone().unwrap_or_default())
+}
+
+// Releases the key of a request that did not complete, logging failures
+async fn release(repo: &IdempotencyKeyRepository<'_>, key: &str, token: Uuid) {
+    if let Err(e) = repo.release(key, token).await {
+        log::warn!("Failed to release idempotency key {}: {}", key, e);
+    }
+}
@@ -0,0 +1,139 @@
+use crate::config::LeaderConfig;
+use sqlx::postgres::{PgConnectOptions, PgConnection};
+use sqlx::{ConnectOptions, Connection, PgPool};
+use std::future::Future;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+// First key of the advisory locks taken for jobs; the second key is the hash of the job name
+const LOCK_NAMESPACE: i32 = 0x4155_5354; // "AUST"
+
+// Elects one instance per job through PostgreSQL session advisory locks. Each lock is held on a
+// dedicated connection outside the pool, so it is released as soon as the leader's session ends
+// and a standby acquires it on its next attempt.
+#[derive(Clone)]
+pub struct LeaderElection {
+    connect_options: PgConnectOptions,
+    enabled: bool,
+    check_interval: Duration,
+}
+
+impl LeaderElection {
+    // Creates a new LeaderElection connecting with the options of the pool
+    pub fn new(pool: &PgPool, config: &LeaderConfig) -> Self {
+        Self {
+            connect_options: pool.connect_options().as_ref().clone(),
+            enabled: config.enabled,
+            check_interval: Duration::from_secs(config.check_interval_secs),
+        }
+    }
+
+    // Runs a job only while this instance holds the job's lock, until cancelled. Standbys retry
+    // the lock every check interval and the leader verifies its session just as often; when the
+    // session is lost the job is cancelled and its current work completed before retrying.
+    // on_leadership is called with true when leadership is acquired and false while waiting.
+    pub async fn run<F, Fut>(
+        &self,
+        name: &str,
+        cancel: CancellationToken,
+        on_leadership: impl Fn(bool) + Send,
+        job: F,
+    ) where
+        F: Fn(CancellationToken) -> Fut + Send + Sync,
+        Fut: Future<Output = ()> + Send,
+    {
+        if !self.enabled {
+            on_leadership(true);
+            job(cancel).await;
+            return;
+        }
+
+        on_leadership(false);
+        loop {
+            match self.try_acquire(name).await {
+                Ok(Some(mut conn)) => {
+                    log::info!("Acquired leadership of {}", name);
+                    on_leadership(true);
+                    let lost = self.lead(&mut conn, name, &cancel, &job).await;
+                    on_leadership(false);
+                    if !lost {
+                        // Release explicitly so a standby does not wait for the session to time out
+                        if let Err(e) = self.release(conn, name).await {
+                            log::warn!("Failed to release leadership of {}: {}", name, e);
+                        }
+                        log::info!("Released leadership of {}", name);
+                        return;
+                    }
+                }
+                Ok(None) => log::debug!("{} is run by another instance", name),
+                Err(e) => log::warn!("Failed to acquire leadership of {}: {}", name, e),
+            }
+
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = tokio::time::sleep(self.check_interval) => {}
+            }
+        }
+    }
+
+    // Opens a connection and tries to take the job's lock on it
+    async fn try_acquire(&self, name: &str) -> Result<Option<PgConnection>, sqlx::Error> {
+        let mut conn = self.connect_options.connect().await?;
+        let acquired: bool = sqlx::query_scalar("SELECT pg_try_advisory_lock($1, hashtext($2))")
+            .bind(LOCK_NAMESPACE)
+            .bind(name)
+            .fetch_one(&mut conn)
+            .await?;
+        if acquired {
+            Ok(Some(conn))
+        } else {
+            conn.close().await.ok();
+            Ok(None)
+        }
+    }
+
+    // Runs the job while verifying the lock session; returns true if leadership was lost
+    async fn lead<F, Fut>(
+        &self,
+        conn: &mut PgConnection,
+        name: &str,
+        cancel: &CancellationToken,
+        job: &F,
+    ) -> bool
+    where
+        F: Fn(CancellationToken) -> Fut,
+        Fut: Future<Output = ()>,
+    {
+        let job_cancel = cancel.child_token();
+        let job_run = job(job_cancel.clone());
+        tokio::pin!(job_run);
+        let mut check = tokio::time::interval(self.check_interval);
+        check.tick().await;
+        loop {
+            tokio::select! {
+                _ = &mut job_run => return false,
+                _ = check.tick() => {
+                    let error = match tokio::time::timeout(self.check_interval, conn.ping()).await {
+                        Ok(Ok(())) => continue,
+                        Ok(Err(e)) => e.to_string(),
+                        Err(_) => "lock session did not respond".to_string(),
+                    };
+                    log::error!("Lost leadership of {}: {}", name, error);
+                    job_cancel.cancel();
+                    job_run.await;
+                    return true;
+                }
+            }
+        }
+    }
+
+    // Unlocks the job's lock and closes its connection
+    async fn release(&self, mut conn: PgConnection, name: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("SELECT pg_advisory_unlock($1, hashtext($2))")
+            .bind(LOCK_NAMESPACE)
+            .bind(name)
+            .execute(&mut conn)
+            .await?;
+        conn.close().await
+    }
+}
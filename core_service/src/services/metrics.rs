@@ -0,0 +1,334 @@
+use crate::services::cmc::CmcService;
+use crate::services::portfolio::PortfolioService;
+use crate::services::price_refresh::{PriceRefreshService, RefreshTier};
+use crate::services::redis::RedisService;
+use actix_web::web;
+use anyhow::Result;
+use prometheus::{
+    Encoder, Gauge, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge,
+    IntGaugeVec, Opts, Registry, TextEncoder,
+};
+use sqlx::PgPool;
+use std::time::Duration;
+
+// Service collecting application metrics and rendering them in the Prometheus text format.
+// HTTP metrics are recorded per request; everything else is read from the services on scrape.
+#[derive(Clone)]
+pub struct MetricsService {
+    pool: web::Data<PgPool>,
+    cmc_service: web::Data<CmcService>,
+    redis_service: web::Data<RedisService>,
+    portfolio_service: web::Data<PortfolioService>,
+    price_refresh_service: web::Data<PriceRefreshService>,
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    db_connections: IntGaugeVec,
+    db_max_connections: IntGauge,
+    cache_hits: IntCounter,
+    cache_misses: IntCounter,
+    cache_redis_failures: IntCounter,
+    cache_degraded: IntGauge,
+    cmc_requests: IntCounter,
+    cmc_errors: IntCounterVec,
+    cmc_credits: IntCounter,
+    cmc_circuit_open: IntGauge,
+    refresh_runs: IntCounterVec,
+    refresh_duration: GaugeVec,
+    refresh_success: IntGaugeVec,
+    refresh_timestamp: GaugeVec,
+    portfolio_value: Gauge,
+    holding_amount: GaugeVec,
+    holding_value: GaugeVec,
+}
+
+impl MetricsService {
+    // Creates a new MetricsService and registers all metrics
+    pub fn new(
+        pool: web::Data<PgPool>,
+        cmc_service: web::Data<CmcService>,
+        redis_service: web::Data<RedisService>,
+        portfolio_service: web::Data<PortfolioService>,
+        price_refresh_service: web::Data<PriceRefreshService>,
+    ) -> Self {
+        let registry = Registry::new_custom(Some("austeris".to_string()), None)
+            .expect("metrics prefix is valid");
+
+        let http_requests = IntCounterVec::new(
+            Opts::new("http_requests_total", "HTTP requests by route and status"),
+            &["method", "route", "status"],
+        )
+        .expect("metric options are valid");
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "http_request_duration_seconds",
+                "HTTP request latency by route",
+            ),
+            &["method", "route"],
+        )
+        .expect("metric options are valid");
+        let db_connections = IntGaugeVec::new(
+            Opts::new("db_pool_connections", "Database pool connections by state"),
+            &["state"],
+        )
+        .expect("metric options are valid");
+        let db_max_connections = IntGauge::new(
+            "db_pool_max_connections",
+            "Maximum number of database pool connections",
+        )
+        .expect("metric options are valid");
+        let cache_hits = IntCounter::new("price_cache_hits_total", "Price cache hits")
+            .expect("metric options are valid");
+        let cache_misses = IntCounter::new("price_cache_misses_total", "Price cache misses")
+            .expect("metric options are valid");
+        let cache_redis_failures =
+            IntCounter::new("price_cache_redis_failures_total", "Failed Redis calls")
+                .expect("metric options are valid");
+        let cache_degraded = IntGauge::new(
+            "price_cache_degraded",
+            "1 while Redis is bypassed in favour of the in-process cache",
+        )
+        .expect("metric options are valid");
+        let cmc_requests =
+            IntCounter::new("cmc_requests_total", "HTTP requests sent to CoinMarketCap")
+                .expect("metric options are valid");
+        let cmc_errors = IntCounterVec::new(
+            Opts::new(
+                "cmc_errors_total",
+                "Failed CoinMarketCap attempts and rejected calls by kind",
+            ),
+            &["kind"],
+        )
+        .expect("metric options are valid");
+        let cmc_credits = IntCounter::new(
+            "cmc_credits_used_total",
+            "CoinMarketCap credits consumed since startup",
+        )
+        .expect("metric options are valid");
+        let cmc_circuit_open = IntGauge::new(
+            "cmc_circuit_open",
+            "1 while CoinMarketCap calls are suspended by the circuit breaker",
+        )
+        .expect("metric options are valid");
+        let refresh_runs = IntCounterVec::new(
+            Opts::new(
+                "price_refresh_runs_total",
+                "Background price refresh runs by tier and outcome",
+            ),
+            &["tier", "outcome"],
+        )
+        .expect("metric options are valid");
+        let refresh_duration = GaugeVec::new(
+            Opts::new(
+                "price_refresh_last_duration_seconds",
+                "Duration of the latest background price refresh",
+            ),
+            &["tier"],
+        )
+        .expect("metric options are valid");
+        let refresh_success = IntGaugeVec::new(
+            Opts::new(
+                "price_refresh_last_success",
+                "1 if the latest background price refresh succeeded",
+            ),
+            &["tier"],
+        )
+        .expect("metric options are valid");
+        let refresh_timestamp = GaugeVec::new(
+            Opts::new(
+                "price_refresh_last_run_timestamp_seconds",
+                "Unix time the latest background price refresh finished",
+            ),
+            &["tier"],
+        )
+        .expect("metric options are valid");
+        let portfolio_value = Gauge::new(
+            "portfolio_value_usd",
+            "Total portfolio value in USD from the latest stored prices",
+        )
+        .expect("metric options are valid");
+        let holding_amount = GaugeVec::new(
+            Opts::new("holding_amount", "Net amount held per asset"),
+            &["asset_id", "symbol"],
+        )
+        .expect("metric options are valid");
+        let holding_value = GaugeVec::new(
+            Opts::new(
+                "holding_value_usd",
+                "Value in USD held per asset from the latest stored price",
+            ),
+            &["asset_id", "symbol"],
+        )
+        .expect("metric options are valid");
+
+        let collectors: Vec<Box<dyn prometheus::core::Collector>> = vec![
+            Box::new(http_requests.clone()),
+            Box::new(http_duration.clone()),
+            Box::new(db_connections.clone()),
+            Box::new(db_max_connections.clone()),
+            Box::new(cache_hits.clone()),
+            Box::new(cache_misses.clone()),
+            Box::new(cache_redis_failures.clone()),
+            Box::new(cache_degraded.clone()),
+            Box::new(cmc_requests.clone()),
+            Box::new(cmc_errors.clone()),
+            Box::new(cmc_credits.clone()),
+            Box::new(cmc_circuit_open.clone()),
+            Box::new(refresh_runs.clone()),
+            Box::new(refresh_duration.clone()),
+            Box::new(refresh_success.clone()),
+            Box::new(refresh_timestamp.clone()),
+            Box::new(portfolio_value.clone()),
+            Box::new(holding_amount.clone()),
+            Box::new(holding_value.clone()),
+        ];
+        for collector in collectors {
+            registry
+                .register(collector)
+                .expect("metric names are unique");
+        }
+
+        Self {
+            pool,
+            cmc_service,
+            redis_service,
+            portfolio_service,
+            price_refresh_service,
+            registry,
+            http_requests,
+            http_duration,
+            db_connections,
+            db_max_connections,
+            cache_hits,
+            cache_misses,
+            cache_redis_failures,
+            cache_degraded,
+            cmc_requests,
+            cmc_errors,
+            cmc_credits,
+            cmc_circuit_open,
+            refresh_runs,
+            refresh_duration,
+            refresh_success,
+            refresh_timestamp,
+            portfolio_value,
+            holding_amount,
+            holding_value,
+        }
+    }
+
+    // Records a served HTTP request; route is the matched pattern to keep label cardinality bounded
+    pub fn observe_request(&self, method: &str, route: &str, status: u16, elapsed: Duration) {
+        self.http_requests
+            .with_label_values(&[method, route, &status.to_string()])
+            .inc();
+        self.http_duration
+            .with_label_values(&[method, route])
+            .observe(elapsed.as_secs_f64());
+    }
+
+    // Refreshes the pulled metrics and renders all metrics in the Prometheus text format
+    pub async fn render(&self) -> Result<String> {
+        self.collect_pool();
+        self.collect_cache();
+        self.collect_cmc();
+        self.collect_price_refresh();
+        // Business gauges are best effort so a database hiccup does not hide the other metrics
+        if let Err(e) = self.collect_portfolio().await {
+            log::warn!("Failed to collect portfolio metrics: {}", e);
+        }
+
+        let mut buffer = Vec::new();
+        TextEncoder::new().encode(&self.registry.gather(), &mut buffer)?;
+        Ok(String::from_utf8(buffer)?)
+    }
+
+    fn collect_pool(&self) {
+        let size = self.pool.size() as i64;
+        let idle = self.pool.num_idle() as i64;
+        self.db_connections.with_label_values(&["idle"]).set(idle);
+        self.db_connections
+            .with_label_values(&["in_use"])
+            .set(size - idle);
+        self.db_max_connections
+            .set(self.pool.options().get_max_connections() as i64);
+    }
+
+    fn collect_cache(&self) {
+        let stats = self.redis_service.stats();
+        sync_counter(&self.cache_hits, stats.hits);
+        sync_counter(&self.cache_misses, stats.misses);
+        sync_counter(&self.cache_redis_failures, stats.redis_failures);
+        self.cache_degraded.set(stats.degraded as i64);
+    }
+
+    fn collect_cmc(&self) {
+        let stats = self.cmc_service.stats();
+        sync_counter(&self.cmc_requests, stats.requests);
+        sync_counter(&self.cmc_credits, stats.credits_used);
+        for (kind, count) in stats.errors {
+            sync_counter(&self.cmc_errors.with_label_values(&[kind]), count);
+        }
+        self.cmc_circuit_open.set(stats.circuit_open as i64);
+    }
+
+    fn collect_price_refresh(&self) {
+        for tier in [RefreshTier::Held, RefreshTier::Tracked] {
+            let Some(run) = self.price_refresh_service.last_run(tier) else {
+                continue;
+            };
+            let tier = tier.to_string();
+            sync_counter(
+                &self.refresh_runs.with_label_values(&[&tier, "success"]),
+                run.successes,
+            );
+            sync_counter(
+                &self.refresh_runs.with_label_values(&[&tier, "failure"]),
+                run.failures,
+            );
+            self.refresh_duration
+                .with_label_values(&[&tier])
+                .set(run.duration.as_secs_f64());
+            self.refresh_success
+                .with_label_values(&[&tier])
+                .set(run.error.is_none() as i64);
+            self.refresh_timestamp
+                .with_label_values(&[&tier])
+                .set(run.finished_at.timestamp() as f64);
+        }
+    }
+
+    async fn collect_portfolio(&self) -> Result<()> {
+        let holdings = self
+            .portfolio_service
+            .get_holdings_with_stored_prices()
+            .await?;
+
+        // Reset so assets that are no longer held disappear from the output
+        self.holding_amount.reset();
+        self.holding_value.reset();
+        let mut total_value = 0.0;
+        for (holding, price) in holdings {
+            let labels = [holding.asset_id.to_string(), holding.symbol.clone()];
+            let labels = [labels[0].as_str(), labels[1].as_str()];
+            self.holding_amount
+                .with_label_values(&labels)
+                .set(holding.amount);
+            if let Some(price) = price {
+                let value = holding.amount * price;
+                self.holding_value.with_label_values(&labels).set(value);
+                total_value += value;
+            }
+        }
+        self.portfolio_value.set(total_value);
+        Ok(())
+    }
+}
+
+// Advances a Prometheus counter to a running total kept by a service
+fn sync_counter(counter: &IntCounter, total: u64) {
+    let current = counter.get();
+    if total > current {
+        counter.inc_by(total - current);
+    }
+}
@@ -1,7 +1,20 @@
 pub mod asset;
+pub mod backtest;
 pub mod cmc;
+pub mod dca;
+pub mod health;
+pub mod idempotency;
+pub mod leader;
+pub mod metrics;
 pub mod portfolio;
+pub mod price_refresh;
 pub mod redis;
+pub mod scenario;
 pub mod snapshot;
+pub mod stream;
+pub mod supervisor;
+pub mod tag;
 pub mod transaction;
 pub mod wallet;
+pub mod watchlist;
+pub mod webhook;
@@ -1,5 +1,6 @@
 use crate::dto::snapshot::SnapshotAssetDto;
-use crate::repository::asset::AssetRepository;
+use crate::error::AppError;
+use crate::models::portfolio::HoldingDb;
 use crate::repository::asset_price::AssetPriceRepository;
 use crate::repository::transaction::TransactionRepository;
 use crate::services::cmc::CmcService;
@@ -11,7 +12,6 @@ use chrono::{DateTime, Duration, Utc};
 use sqlx::types::time::PrimitiveDateTime;
 use sqlx::PgPool;
 use std::collections::HashMap;
-use time::OffsetDateTime;
 
 // PortfolioService handles portfolio-related calculations
 #[derive(Clone)]
@@ -34,127 +34,102 @@ impl PortfolioService {
         }
     }
 
-    // Calculates current asset holdings from all transactions
-    pub async fn get_current_assets(&self) -> Result<HashMap<String, (f64, i32)>> {
+    // Calculates current asset holdings from all transactions, keyed by asset_id
+    pub async fn get_current_assets(&self) -> Result<HashMap<i32, HoldingDb>> {
         let transaction_repo = TransactionRepository::new(self.pool.as_ref());
-        let asset_repo = AssetRepository::new(self.pool.as_ref());
-        let transactions = transaction_repo.get_all_transactions().await?;
-        let mut asset_amounts: HashMap<String, (f64, i32)> = HashMap::new();
-
-        for record in transactions {
-            let (amount, cmc_id) = asset_amounts
-                .entry(record.asset.clone())
-                .or_insert((0.0, 0));
-            if *cmc_id == 0 {
-                let asset_cmc_id = asset_repo
-                    .get_cmc_id_by_asset_id(record.id) // Assuming id is tied to asset_id in transactions
-                    .await?
-                    .unwrap_or(0); // Default to 0 if not found
-                *cmc_id = asset_cmc_id;
-            }
-            if record.transaction_type == "BUY" {
-                *amount += record.amount;
-            } else if record.transaction_type == "SELL" {
-                *amount -= record.amount;
-            }
-        }
-
-        Ok(asset_amounts)
+        let holdings = transaction_repo.get_holdings().await?;
+        Ok(holdings
+            .into_iter()
+            .map(|holding| (holding.asset_id, holding))
+            .collect())
     }
 
     // Calculates the total portfolio value in USD
     pub async fn get_portfolio_value(&self) -> Result<f64> {
-        let asset_amounts = self.get_current_assets().await?;
+        let holdings = self.get_current_assets().await?;
         let price_repo =
             AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone());
         let mut total_value = 0.0;
 
         // Identify assets needing fresh prices (missing or older than 1 hour)
-        let now = Utc::now();
-        let one_hour_ago = now - Duration::hours(1);
-        let mut cmc_ids_to_fetch: Vec<i32> = Vec::new();
-        let mut price_map: HashMap<i32, (f64, PrimitiveDateTime)> = HashMap::new();
-
-        // Step 1: Try to get prices from Redis first
-        for (_symbol, (amount, cmc_id)) in &asset_amounts {
-            if *amount > 0.0 {
-                match self.redis_service.get_price(*cmc_id).await? {
-                    Some(cached) => {
-                        let timestamp = parse_iso8601(&cached.timestamp)?;
-                        let timestamp_offset = timestamp.assume_utc();
-                        let timestamp_utc: DateTime<Utc> = DateTime::from_timestamp(
-                            timestamp_offset.unix_timestamp(),
-                            timestamp_offset.nanosecond(),
-                        )
-                        .unwrap_or_else(|| Utc::now());
-
-                        if timestamp_utc >= one_hour_ago {
-                            total_value += amount * cached.price_usd;
-                            price_map.insert(*cmc_id, (cached.price_usd, timestamp));
-                        } else {
-                            cmc_ids_to_fetch.push(*cmc_id);
-                        }
-                    }
-                    None => {
-                        cmc_ids_to_fetch.push(*cmc_id);
-                    }
+        let one_hour_ago = Utc::now() - Duration::hours(1);
+        let mut asset_ids_to_fetch: Vec<i32> = Vec::new();
+
+        // Step 1: Try to get prices from Redis first, in a single round trip
+        let held_asset_ids: Vec<i32> = holdings
+            .values()
+            .filter(|h| h.amount > 0.0)
+            .map(|h| h.asset_id)
+            .collect();
+        let cached_prices = self.redis_service.get_prices(&held_asset_ids).await;
+        for asset_id in held_asset_ids {
+            match cached_prices.get(&asset_id) {
+                Some(cached) if is_fresh(parse_iso8601(&cached.timestamp)?, one_hour_ago) => {
+                    total_value += holdings[&asset_id].amount * cached.price_usd;
                 }
+                _ => asset_ids_to_fetch.push(asset_id),
             }
         }
 
         // Step 2: Fetch missing or outdated prices from DB or CMC
-        if !cmc_ids_to_fetch.is_empty() {
+        if !asset_ids_to_fetch.is_empty() {
             // Try DB first
-            let latest_prices = price_repo.get_latest_prices().await?;
-            let db_prices: HashMap<i32, (f64, PrimitiveDateTime)> = latest_prices
+            let db_prices: HashMap<i32, (f64, PrimitiveDateTime)> = price_repo
+                .get_latest_prices()
+                .await?
                 .into_iter()
-                .map(|(cmc_id, price, timestamp)| (cmc_id, (price, timestamp)))
+                .map(|(asset_id, price, timestamp)| (asset_id, (price, timestamp)))
                 .collect();
 
             let mut still_missing: Vec<i32> = Vec::new();
-            for cmc_id in &cmc_ids_to_fetch {
-                if let Some((price, timestamp)) = db_prices.get(cmc_id) {
-                    let timestamp_offset = timestamp.assume_utc();
-                    let timestamp_utc: DateTime<Utc> = DateTime::from_timestamp(
-                        timestamp_offset.unix_timestamp(),
-                        timestamp_offset.nanosecond(),
-                    )
-                    .unwrap_or_else(|| Utc::now());
-
-                    if timestamp_utc >= one_hour_ago {
-                        if let Some((_, (amount, _))) =
-                            asset_amounts.iter().find(|(_, (_, id))| id == cmc_id)
-                        {
-                            total_value += amount * price;
-                            price_map.insert(*cmc_id, (*price, *timestamp));
-                        }
-                    } else {
-                        still_missing.push(*cmc_id);
+            for asset_id in &asset_ids_to_fetch {
+                match db_prices.get(asset_id) {
+                    Some((price, timestamp)) if is_fresh(*timestamp, one_hour_ago) => {
+                        total_value += holdings[asset_id].amount * price;
                     }
-                } else {
-                    still_missing.push(*cmc_id);
+                    _ => still_missing.push(*asset_id),
                 }
             }
 
             if !still_missing.is_empty() {
-                let fresh_quotes = self
-                    .cmc_service
-                    .fetch_quotes_for_assets(self.pool.as_ref())
-                    .await?;
+                // Only the assets without a fresh price are fetched on demand
+                let cmc_ids: Vec<i32> = still_missing
+                    .iter()
+                    .map(|asset_id| holdings[asset_id].cmc_id)
+                    .collect();
+                let fresh_quotes = match self.cmc_service.fetch_quotes(&cmc_ids).await {
+                    Ok(quotes) => quotes,
+                    // The value would silently use outdated or missing prices, so it is refused
+                    Err(e) if e.is_unavailable() => {
+                        let ids: Vec<String> = still_missing.iter().map(i32::to_string).collect();
+                        return Err(AppError::StalePrice {
+                            message: format!(
+                                "Prices of assets {} are missing or older than 1 hour and could not be refreshed: {}",
+                                ids.join(", "),
+                                e
+                            ),
+                            asset_ids: still_missing,
+                        }
+                        .into());
+                    }
+                    Err(e) => return Err(e.into()),
+                };
                 price_repo.save_prices(fresh_quotes.clone()).await?;
 
-                let now_offset = OffsetDateTime::now_utc();
-                let now_pdt = PrimitiveDateTime::new(now_offset.date(), now_offset.time());
-                for (cmc_id, quote) in fresh_quotes {
-                    if let Some(price) = quote.price {
-                        price_map.insert(cmc_id, (price, now_pdt));
-                        if let Some((_, (amount, _))) =
-                            asset_amounts.iter().find(|(_, (_, id))| id == &cmc_id)
-                        {
-                            total_value += amount * price;
-                        }
-                    } else {
-                        log::warn!("No price available for cmc_id {} after CMC fetch", cmc_id);
+                // Quotes are keyed by cmc_id, which is unique per asset
+                let quotes_by_cmc_id: HashMap<i32, _> = fresh_quotes.into_iter().collect();
+                for asset_id in still_missing {
+                    let holding = &holdings[&asset_id];
+                    match quotes_by_cmc_id
+                        .get(&holding.cmc_id)
+                        .and_then(|quote| quote.price)
+                    {
+                        Some(price) => total_value += holding.amount * price,
+                        None => log::warn!(
+                            "No price available for asset {} ({}) after CMC fetch",
+                            asset_id,
+                            holding.symbol
+                        ),
                     }
                 }
             }
@@ -163,19 +138,56 @@ impl PortfolioService {
         Ok(total_value)
     }
 
+    // Returns positive holdings with their latest stored price, without calling CoinMarketCap
+    pub async fn get_holdings_with_stored_prices(&self) -> Result<Vec<(HoldingDb, Option<f64>)>> {
+        let holdings = self.get_current_assets().await?;
+        let price_repo =
+            AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone());
+        let prices: HashMap<i32, f64> = price_repo
+            .get_latest_prices()
+            .await?
+            .into_iter()
+            .map(|(asset_id, price, _)| (asset_id, price))
+            .collect();
+
+        let mut valued: Vec<(HoldingDb, Option<f64>)> = holdings
+            .into_values()
+            .filter(|holding| holding.amount > 0.0)
+            .map(|holding| {
+                let price = prices.get(&holding.asset_id).copied();
+                (holding, price)
+            })
+            .collect();
+        valued.sort_by_key(|(holding, _)| holding.asset_id);
+        Ok(valued)
+    }
+
     // Generates a snapshot of current assets
     pub async fn get_current_snapshot(&self) -> Result<Vec<SnapshotAssetDto>> {
-        let asset_amounts = self.get_current_assets().await?;
-        let snapshot_assets: Vec<SnapshotAssetDto> = asset_amounts
-            .into_iter()
-            .filter(|(_, (amount, _))| *amount > 0.0)
-            .map(|(symbol, (amount, cmc_id))| SnapshotAssetDto {
-                symbol,
-                amount,
-                cmc_id,
+        let holdings = self.get_current_assets().await?;
+        let mut snapshot_assets: Vec<SnapshotAssetDto> = holdings
+            .into_values()
+            .filter(|holding| holding.amount > 0.0)
+            .map(|holding| SnapshotAssetDto {
+                asset_id: Some(holding.asset_id),
+                symbol: holding.symbol,
+                amount: holding.amount,
+                cmc_id: holding.cmc_id,
             })
             .collect();
+        snapshot_assets.sort_by_key(|asset| asset.asset_id);
 
         Ok(snapshot_assets)
     }
 }
+
+// Checks whether a price timestamp is not older than the given cutoff
+fn is_fresh(timestamp: PrimitiveDateTime, cutoff: DateTime<Utc>) -> bool {
+    let timestamp_offset = timestamp.assume_utc();
+    let timestamp_utc: DateTime<Utc> = DateTime::from_timestamp(
+        timestamp_offset.unix_timestamp(),
+        timestamp_offset.nanosecond(),
+    )
+    .unwrap_or_else(Utc::now);
+    timestamp_utc >= cutoff
+}
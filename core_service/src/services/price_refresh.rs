@@ -0,0 +1,231 @@
+use crate::config::PriceRefreshConfig;
+use crate::dto::asset::RefreshPricesResponse;
+use crate::models::webhook::WebhookEvent;
+use crate::repository::asset::AssetRepository;
+use crate::repository::asset_price::AssetPriceRepository;
+use crate::services::cmc::CmcService;
+use crate::services::redis::RedisService;
+use crate::services::webhook::WebhookService;
+use actix_web::web;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+// Groups of assets refreshed on their own schedule; assets in neither tier are only fetched on demand
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RefreshTier {
+    Held,    // Assets with a positive holding
+    Tracked, // Assets on a watchlist that are not held
+}
+
+impl fmt::Display for RefreshTier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Held => write!(f, "held"),
+            Self::Tracked => write!(f, "tracked"),
+        }
+    }
+}
+
+// Outcome of the latest background refresh of a tier
+#[derive(Debug, Clone)]
+pub struct RefreshRun {
+    pub finished_at: DateTime<Utc>,
+    pub duration: Duration,
+    pub error: Option<String>,     // None when the refresh succeeded
+    pub successes: u64,            // Successful runs since startup
+    pub failures: u64,             // Failed runs since startup
+    pub consecutive_failures: u32, // Failed runs since the last success
+    pub last_success_at: Option<DateTime<Utc>>, // None if no run has succeeded since startup
+}
+
+// Data of the prices.updated webhook event sent after a background refresh
+#[derive(Debug, Serialize)]
+struct PricesUpdatedEvent {
+    tier: String,
+    updated_count: usize,
+    updated_at: String,
+}
+
+// Service refreshing asset prices from CoinMarketCap per tier and on demand
+#[derive(Clone)]
+pub struct PriceRefreshService {
+    pool: web::Data<PgPool>,
+    cmc_service: web::Data<CmcService>,
+    redis_service: web::Data<RedisService>,
+    webhook_service: web::Data<WebhookService>,
+    held_interval: Duration,
+    tracked_interval: Duration,
+    last_runs: Arc<Mutex<HashMap<RefreshTier, RefreshRun>>>,
+}
+
+impl PriceRefreshService {
+    // Creates a new PriceRefreshService with the configured tier intervals
+    pub fn new(
+        pool: web::Data<PgPool>,
+        cmc_service: web::Data<CmcService>,
+        redis_service: web::Data<RedisService>,
+        webhook_service: web::Data<WebhookService>,
+        config: &PriceRefreshConfig,
+    ) -> Self {
+        Self {
+            pool,
+            cmc_service,
+            redis_service,
+            webhook_service,
+            held_interval: Duration::from_secs(config.held_interval_secs),
+            tracked_interval: Duration::from_secs(config.tracked_interval_secs),
+            last_runs: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    // Returns the configured refresh interval of a tier
+    pub fn interval(&self, tier: RefreshTier) -> Duration {
+        match tier {
+            RefreshTier::Held => self.held_interval,
+            RefreshTier::Tracked => self.tracked_interval,
+        }
+    }
+
+    // Returns the outcome of the latest background refresh of a tier, if it has run yet
+    pub fn last_run(&self, tier: RefreshTier) -> Option<RefreshRun> {
+        self.last_runs.lock().unwrap().get(&tier).cloned()
+    }
+
+    // Refreshes the prices of a tier at its configured interval until cancelled; a refresh in
+    // progress is completed before returning so no price write is cut short
+    pub async fn run(&self, tier: RefreshTier, cancel: CancellationToken) {
+        let mut interval = tokio::time::interval(self.interval(tier));
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = interval.tick() => {}
+            }
+            log::info!("Updating {} asset prices...", tier);
+            let started = Instant::now();
+            let error = match self.refresh_tier(tier).await {
+                Ok(count) => {
+                    log::info!("Updated {} {} asset prices successfully", count, tier);
+                    if count > 0 {
+                        let event = PricesUpdatedEvent {
+                            tier: tier.to_string(),
+                            updated_count: count,
+                            updated_at: Utc::now().to_rfc3339(),
+                        };
+                        self.webhook_service
+                            .emit(WebhookEvent::PricesUpdated, &event)
+                            .await;
+                    }
+                    None
+                }
+                Err(e) => {
+                    log::error!("Failed to update {} asset prices: {}", tier, e);
+                    Some(e.to_string())
+                }
+            };
+            {
+                let mut last_runs = self.last_runs.lock().unwrap();
+                let previous = last_runs.get(&tier);
+                let failed = error.is_some();
+                let finished_at = Utc::now();
+                let run = RefreshRun {
+                    finished_at,
+                    duration: started.elapsed(),
+                    successes: previous.map_or(0, |run| run.successes) + u64::from(!failed),
+                    failures: previous.map_or(0, |run| run.failures) + u64::from(failed),
+                    consecutive_failures: if failed {
+                        previous.map_or(0, |run| run.consecutive_failures) + 1
+                    } else {
+                        0
+                    },
+                    last_success_at: if failed {
+                        previous.and_then(|run| run.last_success_at)
+                    } else {
+                        Some(finished_at)
+                    },
+                    error,
+                };
+                last_runs.insert(tier, run);
+            }
+            let stats = self.redis_service.stats();
+            log::info!(
+                "Price cache stats: {} hits, {} misses, {} Redis failures{}",
+                stats.hits,
+                stats.misses,
+                stats.redis_failures,
+                if stats.degraded { " (degraded)" } else { "" }
+            );
+        }
+    }
+
+    // Fetches the cmc_ids of the assets currently in a tier
+    pub async fn tier_cmc_ids(&self, tier: RefreshTier) -> Result<Vec<i32>> {
+        let asset_repo = AssetRepository::new(self.pool.as_ref());
+        match tier {
+            RefreshTier::Held => asset_repo.get_held_cmc_ids().await,
+            RefreshTier::Tracked => asset_repo.get_tracked_cmc_ids().await,
+        }
+    }
+
+    // Fetches and saves the latest prices of all assets in a tier, returning the number saved
+    pub async fn refresh_tier(&self, tier: RefreshTier) -> Result<usize> {
+        let cmc_ids = self.tier_cmc_ids(tier).await?;
+        if cmc_ids.is_empty() {
+            return Ok(0);
+        }
+
+        let quotes = self.cmc_service.fetch_quotes(&cmc_ids).await?;
+        let price_repo =
+            AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone());
+        price_repo.save_prices(quotes).await
+    }
+
+    // Immediately fetches and saves the latest prices of the given assets
+    pub async fn refresh_assets(&self, asset_ids: &[i32]) -> Result<RefreshPricesResponse> {
+        let asset_repo = AssetRepository::new(self.pool.as_ref());
+        let known = asset_repo.get_cmc_ids(asset_ids).await?;
+
+        let known_ids: HashSet<i32> = known.iter().map(|(asset_id, _)| *asset_id).collect();
+        let mut unknown_asset_ids: Vec<i32> = asset_ids
+            .iter()
+            .filter(|id| !known_ids.contains(id))
+            .copied()
+            .collect();
+        unknown_asset_ids.sort_unstable();
+        unknown_asset_ids.dedup();
+
+        let mut missing_price_asset_ids = Vec::new();
+        let mut refreshed_count = 0;
+        if !known.is_empty() {
+            let cmc_ids: Vec<i32> = known.iter().map(|(_, cmc_id)| *cmc_id).collect();
+            let quotes = self.cmc_service.fetch_quotes(&cmc_ids).await?;
+            let priced: HashSet<i32> = quotes
+                .iter()
+                .filter(|(_, quote)| quote.price.is_some())
+                .map(|(cmc_id, _)| *cmc_id)
+                .collect();
+            missing_price_asset_ids = known
+                .iter()
+                .filter(|(_, cmc_id)| !priced.contains(cmc_id))
+                .map(|(asset_id, _)| *asset_id)
+                .collect();
+
+            let price_repo =
+                AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone());
+            refreshed_count = price_repo.save_prices(quotes).await?;
+        }
+
+        Ok(RefreshPricesResponse {
+            refreshed_count,
+            unknown_asset_ids,
+            missing_price_asset_ids,
+            refreshed_at: Utc::now().to_rfc3339(),
+        })
+    }
+}
@@ -1,59 +1,262 @@
-use anyhow::Result;
-use redis::{AsyncCommands, Client};
+use crate::config::RedisConfig;
+use lru::LruCache;
+use redis::aio::{ConnectionManager, ConnectionManagerConfig};
+use redis::Client;
 use serde::{Deserialize, Serialize};
-use std::env;
+use std::collections::HashMap;
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 
-#[derive(Serialize, Deserialize, Debug)]
+// Maximum number of prices kept in the in-process fallback cache
+const LOCAL_CACHE_CAPACITY: usize = 10_000;
+// Timeout for connecting to Redis and for each command
+const REDIS_TIMEOUT: Duration = Duration::from_millis(500);
+// Bounds of the exponential backoff between reconnection attempts
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(60);
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct CachedPrice {
     pub price_usd: f64,
     pub timestamp: String,
 }
 
+// Hit/miss and failure counters of the price cache
+#[derive(Serialize, Debug, Clone, Copy)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub redis_failures: u64,
+    pub degraded: bool,
+}
+
+// Tracks Redis outages to back off reconnection attempts
+#[derive(Default)]
+struct RedisHealth {
+    consecutive_failures: u32,
+    retry_at: Option<Instant>,
+}
+
+// Price cache backed by Redis, with an in-process LRU cache used when Redis is unavailable.
+// Redis is only an accelerator: its failures are logged and counted but never returned.
 #[derive(Clone)]
 pub struct RedisService {
-    client: Client,
+    client: Option<Client>,
+    connection: Arc<tokio::sync::Mutex<Option<ConnectionManager>>>, // Shared, lazily established connection
+    local: Arc<Mutex<LruCache<i32, (CachedPrice, Instant)>>>,       // Prices with their expiry
+    health: Arc<Mutex<RedisHealth>>,
+    ttl_secs: u64,
+    hits: Arc<AtomicU64>,
+    misses: Arc<AtomicU64>,
+    failures: Arc<AtomicU64>,
 }
 
 impl RedisService {
-    // Creates a new RedisService instance using REDIS_URL from environment
-    pub fn new() -> Result<Self> {
-        let redis_url =
-            env::var("REDIS_URL").unwrap_or_else(|_| "redis://127.0.0.1:6379".to_string());
-        let client = Client::open(redis_url)?;
-        Ok(Self { client })
-    }
-
-    // Saves a price to Redis with a TTL of 1 hour
-    pub async fn save_price(&self, asset_id: i32, price_usd: f64, timestamp: String) -> Result<()> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let key = format!("asset_price:{}", asset_id);
-        let cached_price = CachedPrice {
-            price_usd,
-            timestamp,
+    // Creates a new RedisService instance from the Redis configuration
+    pub fn new(config: &RedisConfig) -> Self {
+        let ttl_secs = config.price_ttl_secs;
+        let client = Client::open(config.url.as_str())
+            .map_err(|e| log::error!("Invalid Redis URL, using in-process cache only: {}", e))
+            .ok();
+        Self {
+            client,
+            connection: Arc::new(tokio::sync::Mutex::new(None)),
+            local: Arc::new(Mutex::new(LruCache::new(
+                NonZeroUsize::new(LOCAL_CACHE_CAPACITY).expect("capacity is non-zero"),
+            ))),
+            health: Arc::new(Mutex::new(RedisHealth::default())),
+            ttl_secs,
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
+            failures: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    // Returns the shared connection, or None while Redis is down and backing off
+    async fn connection(&self) -> Option<ConnectionManager> {
+        let client = self.client.as_ref()?;
+        if let Some(retry_at) = self.health.lock().unwrap().retry_at {
+            if Instant::now() < retry_at {
+                return None;
+            }
+        }
+
+        let mut connection = self.connection.lock().await;
+        if let Some(conn) = connection.as_ref() {
+            return Some(conn.clone());
+        }
+
+        let config = ConnectionManagerConfig::new()
+            .set_number_of_retries(1)
+            .set_connection_timeout(REDIS_TIMEOUT)
+            .set_response_timeout(REDIS_TIMEOUT);
+        match ConnectionManager::new_with_config(client.clone(), config).await {
+            Ok(conn) => {
+                *connection = Some(conn.clone());
+                Some(conn)
+            }
+            Err(e) => {
+                self.record_failure(&e);
+                None
+            }
+        }
+    }
+
+    // Logs and counts a Redis failure and schedules the next reconnection attempt
+    fn record_failure(&self, err: &redis::RedisError) {
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        let mut health = self.health.lock().unwrap();
+        health.consecutive_failures += 1;
+        let delay = MIN_RECONNECT_DELAY
+            .saturating_mul(2u32.saturating_pow(health.consecutive_failures - 1))
+            .min(MAX_RECONNECT_DELAY);
+        health.retry_at = Some(Instant::now() + delay);
+        log::warn!(
+            "Redis unavailable ({}), using in-process cache; retrying in {}s",
+            err,
+            delay.as_secs()
+        );
+    }
+
+    // Resets the backoff after a successful Redis call
+    fn record_success(&self) {
+        let mut health = self.health.lock().unwrap();
+        if health.consecutive_failures > 0 {
+            log::info!(
+                "Redis connection restored after {} failures",
+                health.consecutive_failures
+            );
+            *health = RedisHealth::default();
+        }
+    }
+
+    // Saves prices to the in-process cache and to Redis with the configured TTL in a single pipelined round trip
+    pub async fn save_prices(&self, prices: &[(i32, CachedPrice)]) {
+        if prices.is_empty() {
+            return;
+        }
+
+        let expires_at = Instant::now() + Duration::from_secs(self.ttl_secs);
+        {
+            let mut local = self.local.lock().unwrap();
+            for (asset_id, cached_price) in prices {
+                local.put(*asset_id, (cached_price.clone(), expires_at));
+            }
+        }
+
+        let Some(mut conn) = self.connection().await else {
+            return;
         };
-        let serialized = serde_json::to_string(&cached_price)?;
+        let mut pipe = redis::pipe();
+        for (asset_id, cached_price) in prices {
+            match serde_json::to_string(cached_price) {
+                Ok(serialized) => {
+                    pipe.cmd("SETEX")
+                        .arg(price_key(*asset_id))
+                        .arg(self.ttl_secs)
+                        .arg(serialized)
+                        .ignore();
+                }
+                Err(e) => log::warn!("Failed to serialize price for asset {}: {}", asset_id, e),
+            }
+        }
+
         // Explicitly specify the return type as () to avoid never type fallback
-        let _: () = redis::cmd("SETEX")
-            .arg(&key)
-            .arg(3600)
-            .arg(&serialized)
-            .query_async(&mut conn)
-            .await
-            .map_err(anyhow::Error::from)?;
-        Ok(())
+        match pipe.query_async::<()>(&mut conn).await {
+            Ok(()) => self.record_success(),
+            Err(e) => self.record_failure(&e),
+        }
     }
 
-    // Retrieves a price from Redis by asset_id
-    pub async fn get_price(&self, asset_id: i32) -> Result<Option<CachedPrice>> {
-        let mut conn = self.client.get_multiplexed_async_connection().await?;
-        let key = format!("asset_price:{}", asset_id);
-        let result: Option<String> = conn.get(key).await?;
-        match result {
-            Some(data) => {
-                let cached_price: CachedPrice = serde_json::from_str(&data)?;
-                Ok(Some(cached_price))
+    // Retrieves cached prices for the given asset_ids with a single MGET,
+    // falling back to the in-process cache for misses and while Redis is unavailable
+    pub async fn get_prices(&self, asset_ids: &[i32]) -> HashMap<i32, CachedPrice> {
+        let mut prices = HashMap::new();
+        if asset_ids.is_empty() {
+            return prices;
+        }
+
+        if let Some(mut conn) = self.connection().await {
+            let keys: Vec<String> = asset_ids.iter().map(|id| price_key(*id)).collect();
+            let result: redis::RedisResult<Vec<Option<String>>> =
+                redis::cmd("MGET").arg(&keys).query_async(&mut conn).await;
+            match result {
+                Ok(values) => {
+                    self.record_success();
+                    for (asset_id, value) in asset_ids.iter().zip(values) {
+                        let Some(data) = value else { continue };
+                        match serde_json::from_str::<CachedPrice>(&data) {
+                            Ok(cached_price) => {
+                                prices.insert(*asset_id, cached_price);
+                            }
+                            Err(e) => {
+                                log::warn!("Invalid cached price for asset {}: {}", asset_id, e)
+                            }
+                        }
+                    }
+                }
+                Err(e) => self.record_failure(&e),
+            }
+        }
+
+        {
+            let now = Instant::now();
+            let mut local = self.local.lock().unwrap();
+            for asset_id in asset_ids {
+                if prices.contains_key(asset_id) {
+                    continue;
+                }
+                match local.get(asset_id) {
+                    Some((cached_price, expires_at)) if *expires_at > now => {
+                        prices.insert(*asset_id, cached_price.clone());
+                    }
+                    Some(_) => {
+                        local.pop(asset_id);
+                    }
+                    None => {}
+                }
             }
-            None => Ok(None),
         }
+
+        self.hits.fetch_add(prices.len() as u64, Ordering::Relaxed);
+        self.misses
+            .fetch_add((asset_ids.len() - prices.len()) as u64, Ordering::Relaxed);
+        prices
     }
+
+    // Sends PING to Redis, reporting whether it is currently reachable
+    pub async fn ping(&self) -> anyhow::Result<()> {
+        if self.client.is_none() {
+            anyhow::bail!("Redis URL is invalid, using in-process cache only");
+        }
+        let Some(mut conn) = self.connection().await else {
+            anyhow::bail!("Redis is unavailable, using in-process cache");
+        };
+        match redis::cmd("PING").query_async::<String>(&mut conn).await {
+            Ok(_) => {
+                self.record_success();
+                Ok(())
+            }
+            Err(e) => {
+                self.record_failure(&e);
+                Err(e.into())
+            }
+        }
+    }
+
+    // Returns the counters recorded since startup and whether Redis is currently bypassed
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            redis_failures: self.failures.load(Ordering::Relaxed),
+            degraded: self.client.is_none() || self.health.lock().unwrap().consecutive_failures > 0,
+        }
+    }
+}
+
+fn price_key(asset_id: i32) -> String {
+    format!("asset_price:{}", asset_id)
 }
@@ -0,0 +1,255 @@
+use crate::dto::scenario::{
+    ScenarioAssetDto, ScenarioOverrideDto, ScenarioRequestDto, ScenarioResultDto,
+};
+use crate::error::{field_errors, AppError, FieldError};
+use crate::services::portfolio::PortfolioService;
+use actix_web::web;
+use std::collections::HashMap;
+use validator::Validate;
+
+// Service valuing the current holdings under hypothetical prices. Nothing is stored.
+#[derive(Clone)]
+pub struct ScenarioService {
+    portfolio_service: web::Data<PortfolioService>,
+}
+
+impl ScenarioService {
+    // Creates a new instance of ScenarioService
+    pub fn new(portfolio_service: web::Data<PortfolioService>) -> Self {
+        Self { portfolio_service }
+    }
+
+    // Applies the overrides, and the shock to every other asset, to the latest stored prices of
+    // the current holdings and compares the resulting valuation with the current one
+    pub async fn run(&self, request: ScenarioRequestDto) -> Result<ScenarioResultDto, AppError> {
+        let errors: Vec<FieldError> = request
+            .overrides
+            .iter()
+            .enumerate()
+            .flat_map(|(index, item)| {
+                override_errors(item)
+                    .into_iter()
+                    .map(move |error| FieldError {
+                        field: format!("overrides[{}].{}", index, error.field),
+                        message: error.message,
+                    })
+            })
+            .collect();
+        if !errors.is_empty() {
+            return Err(AppError::invalid_fields(errors));
+        }
+
+        let holdings = self
+            .portfolio_service
+            .get_holdings_with_stored_prices()
+            .await?;
+        let mut overrides: HashMap<i32, &ScenarioOverrideDto> = HashMap::new();
+        for (index, item) in request.overrides.iter().enumerate() {
+            if !holdings
+                .iter()
+                .any(|(holding, _)| holding.asset_id == item.asset_id)
+            {
+                return Err(AppError::invalid_field(
+                    &format!("overrides[{}].asset_id", index),
+                    format!("Asset {} is not held", item.asset_id),
+                ));
+            }
+            if overrides.insert(item.asset_id, item).is_some() {
+                return Err(AppError::invalid_field(
+                    &format!("overrides[{}].asset_id", index),
+                    format!("Asset {} is overridden more than once", item.asset_id),
+                ));
+            }
+        }
+        let shock_pct = request.shock_pct.unwrap_or(0.0);
+
+        let mut assets: Vec<ScenarioAssetDto> = holdings
+            .into_iter()
+            .map(|(holding, current_price)| {
+                let (adjustment, scenario_price) = match overrides.get(&holding.asset_id) {
+                    Some(ScenarioOverrideDto {
+                        price_usd: Some(price),
+                        ..
+                    }) => ("price_usd", Some(*price)),
+                    Some(ScenarioOverrideDto {
+                        change_pct: Some(change),
+                        ..
+                    }) => (
+                        "change_pct",
+                        current_price.map(|price| apply(price, *change)),
+                    ),
+                    _ if shock_pct != 0.0 => {
+                        ("shock", current_price.map(|price| apply(price, shock_pct)))
+                    }
+                    _ => ("none", current_price),
+                };
+                let current_value = current_price.map(|price| holding.amount * price);
+                let scenario_value = scenario_price.map(|price| holding.amount * price);
+                let change_usd = current_value
+                    .zip(scenario_value)
+                    .map(|(current, scenario)| scenario - current);
+                ScenarioAssetDto {
+                    asset_id: holding.asset_id,
+                    symbol: holding.symbol,
+                    amount: holding.amount,
+                    adjustment: adjustment.to_string(),
+                    current_price_usd: current_price,
+                    scenario_price_usd: scenario_price,
+                    current_value_usd: current_value,
+                    scenario_value_usd: scenario_value,
+                    change_usd,
+                    change_pct: change_pct(current_value, change_usd),
+                    current_weight: None,
+                    scenario_weight: None,
+                }
+            })
+            .collect();
+
+        // Only assets valued both now and under the scenario count towards the totals
+        let valued = |asset: &&ScenarioAssetDto| asset.change_usd.is_some();
+        let current_total: f64 = assets
+            .iter()
+            .filter(valued)
+            .filter_map(|asset| asset.current_value_usd)
+            .sum();
+        let scenario_total: f64 = assets
+            .iter()
+            .filter(valued)
+            .filter_map(|asset| asset.scenario_value_usd)
+            .sum();
+        for asset in assets.iter_mut().filter(|asset| asset.change_usd.is_some()) {
+            asset.current_weight = weight(asset.current_value_usd, current_total);
+            asset.scenario_weight = weight(asset.scenario_value_usd, scenario_total);
+        }
+        let change_usd = scenario_total - current_total;
+
+        Ok(ScenarioResultDto {
+            current_value_usd: current_total,
+            scenario_value_usd: scenario_total,
+            change_usd,
+            change_pct: change_pct(Some(current_total), Some(change_usd)),
+            assets,
+        })
+    }
+}
+
+// Lists the field errors of an override, which must set exactly one of change_pct and price_usd
+fn override_errors(item: &ScenarioOverrideDto) -> Vec<FieldError> {
+    let mut errors = item
+        .validate()
+        .err()
+        .map(|e| field_errors(&e))
+        .unwrap_or_default();
+    if item.change_pct.is_some() == item.price_usd.is_some() {
+        errors.push(FieldError {
+            field: "change_pct".to_string(),
+            message: "Exactly one of change_pct and price_usd must be set".to_string(),
+        });
+    }
+    errors
+}
+
+// Applies a percentage change to a price
+fn apply(price: f64, change_pct: f64) -> f64 {
+    price * (1.0 + change_pct / 100.0)
+}
+
+// Expresses a change as a percentage of the current value, None when it is not positive
+fn change_pct(current: Option<f64>, change: Option<f64>) -> Option<f64> {
+    current
+        .zip(change)
+        .filter(|(current, _)| *current > 0.0)
+        .map(|(current, change)| change / current * 100.0)
+}
+
+// Share of a value in a total, None when the total is not positive
+fn weight(value: Option<f64>, total: f64) -> Option<f64> {
+    value.filter(|_| total > 0.0).map(|value| value / total)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!(
+            (actual - expected).abs() < 1e-9,
+            "expected {}, got {}",
+            expected,
+            actual
+        );
+    }
+
+    fn scenario_override(change_pct: Option<f64>, price_usd: Option<f64>) -> ScenarioOverrideDto {
+        ScenarioOverrideDto {
+            asset_id: 1,
+            change_pct,
+            price_usd,
+        }
+    }
+
+    #[test]
+    fn apply_changes_the_price_by_a_percentage() {
+        assert_close(apply(100.0, -30.0), 70.0);
+        assert_close(apply(50.0, 20.0), 60.0);
+        assert_close(apply(100.0, 0.0), 100.0);
+        assert_close(apply(100.0, -100.0), 0.0);
+        assert_close(apply(0.0, 50.0), 0.0);
+    }
+
+    #[test]
+    fn change_pct_is_relative_to_the_current_value() {
+        assert_close(change_pct(Some(200.0), Some(-50.0)).unwrap(), -25.0);
+        assert_close(change_pct(Some(200.0), Some(100.0)).unwrap(), 50.0);
+        assert_close(change_pct(Some(200.0), Some(-200.0)).unwrap(), -100.0);
+    }
+
+    #[test]
+    fn change_pct_needs_a_positive_current_value() {
+        assert_eq!(change_pct(Some(0.0), Some(10.0)), None);
+        assert_eq!(change_pct(Some(-10.0), Some(10.0)), None);
+        assert_eq!(change_pct(None, Some(10.0)), None);
+        assert_eq!(change_pct(Some(100.0), None), None);
+    }
+
+    #[test]
+    fn weight_is_the_share_of_the_total() {
+        assert_close(weight(Some(30.0), 120.0).unwrap(), 0.25);
+        assert_close(weight(Some(0.0), 120.0).unwrap(), 0.0);
+    }
+
+    #[test]
+    fn weight_needs_a_positive_total() {
+        assert_eq!(weight(Some(30.0), 0.0), None);
+        assert_eq!(weight(Some(0.0), 0.0), None);
+        assert_eq!(weight(Some(30.0), -10.0), None);
+        assert_eq!(weight(None, 120.0), None);
+    }
+
+    #[test]
+    fn override_needs_exactly_one_adjustment() {
+        assert!(override_errors(&scenario_override(Some(-30.0), None)).is_empty());
+        assert!(override_errors(&scenario_override(None, Some(0.0))).is_empty());
+
+        for item in [
+            scenario_override(None, None),
+            scenario_override(Some(-30.0), Some(100.0)),
+        ] {
+            let errors = override_errors(&item);
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].field, "change_pct");
+        }
+    }
+
+    #[test]
+    fn override_rejects_shocks_below_minus_100_and_negative_prices() {
+        let errors = override_errors(&scenario_override(Some(-150.0), None));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "change_pct");
+        assert_eq!(errors[0].message, "Change must be at least -100%");
+
+        let errors = override_errors(&scenario_override(None, Some(-1.0)));
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "price_usd");
+    }
+}
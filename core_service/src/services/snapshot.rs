@@ -1,96 +1,109 @@
+use crate::dto::pagination::PageDto;
 use crate::dto::snapshot::{SnapshotDiffDto, SnapshotDto};
 use crate::error::AppError;
+use crate::models::pagination::PageParams;
+use crate::models::snapshot::SnapshotSort;
+use crate::models::webhook::WebhookEvent;
 use crate::repository::snapshot::SnapshotRepository;
 use crate::services::portfolio::PortfolioService;
+use crate::services::webhook::WebhookService;
 use actix_web::web;
 use anyhow::Result;
 use sqlx::PgPool;
-use std::collections::HashMap;
 
 // Service for managing portfolio snapshots
 #[derive(Clone)]
 pub struct SnapshotService {
     pool: web::Data<PgPool>,
     portfolio_service: web::Data<PortfolioService>,
+    webhook_service: web::Data<WebhookService>,
 }
 
 impl SnapshotService {
     // Creates a new instance of SnapshotService
-    pub fn new(pool: web::Data<PgPool>, portfolio_service: web::Data<PortfolioService>) -> Self {
+    pub fn new(
+        pool: web::Data<PgPool>,
+        portfolio_service: web::Data<PortfolioService>,
+        webhook_service: web::Data<WebhookService>,
+    ) -> Self {
         Self {
             pool,
             portfolio_service,
+            webhook_service,
         }
     }
 
     // Creates a new portfolio snapshot
     pub async fn create(&self) -> Result<SnapshotDto, AppError> {
-        let snapshot_assets = self
-            .portfolio_service
-            .get_current_snapshot()
-            .await
-            .map_err(AppError::internal)?;
+        let snapshot_assets = self.portfolio_service.get_current_snapshot().await?;
         let repo = SnapshotRepository::new(self.pool.as_ref());
         let record = repo.create(snapshot_assets).await?;
-        Ok(record.into())
+        let snapshot = SnapshotDto::from(record);
+        self.webhook_service
+            .emit(WebhookEvent::SnapshotCreated, &snapshot)
+            .await;
+        Ok(snapshot)
     }
 
-    // Retrieves all snapshots with differences from current state
-    pub async fn get_all(&self) -> Result<Vec<SnapshotDto>, AppError> {
+    // Retrieves a page of snapshots with differences from current state
+    pub async fn get_all(
+        &self,
+        page: PageParams<SnapshotSort>,
+    ) -> Result<PageDto<SnapshotDto>, AppError> {
         let repo = SnapshotRepository::new(self.pool.as_ref());
-        let snapshots = repo.get_all().await?;
+        let (snapshots, total) = repo.get_page(&page).await?;
 
-        let current_assets = self
-            .portfolio_service
-            .get_current_assets()
-            .await
-            .map_err(AppError::internal)?;
+        let current_assets = self.portfolio_service.get_current_assets().await?;
+        let mut current_holdings: Vec<_> = current_assets.values().collect();
+        current_holdings.sort_by_key(|holding| holding.asset_id);
 
         // Map snapshots to DTOs with calculated differences
-        let response: Vec<SnapshotDto> = snapshots
+        let items: Vec<SnapshotDto> = snapshots
             .into_iter()
             .map(|record| {
                 let mut dto: SnapshotDto = record.into();
-                let mut diff_map: HashMap<String, SnapshotDiffDto> = HashMap::new();
+                let mut diff: Vec<SnapshotDiffDto> = Vec::new();
 
-                // Calculate differences between snapshot and current state
+                // Calculate differences between snapshot and current state by asset_id
                 for asset in &dto.assets {
-                    let current = current_assets
-                        .get(&asset.symbol)
-                        .map(|(amt, _)| *amt)
+                    let current = asset
+                        .asset_id
+                        .and_then(|id| current_assets.get(&id))
+                        .map(|holding| holding.amount)
                         .unwrap_or(0.0);
-                    let diff = current - asset.amount;
-                    if diff != 0.0 {
-                        diff_map.insert(
-                            asset.symbol.clone(),
-                            SnapshotDiffDto {
-                                symbol: asset.symbol.clone(),
-                                amount_diff: diff,
-                                cmc_id: asset.cmc_id,
-                            },
-                        );
+                    let amount_diff = current - asset.amount;
+                    if amount_diff != 0.0 {
+                        diff.push(SnapshotDiffDto {
+                            asset_id: asset.asset_id,
+                            symbol: asset.symbol.clone(),
+                            amount_diff,
+                            cmc_id: asset.cmc_id,
+                        });
                     }
                 }
 
                 // Include assets present now but not in the snapshot
-                for (symbol, (current_amount, cmc_id)) in &current_assets {
-                    if *current_amount > 0.0 && !dto.assets.iter().any(|a| &a.symbol == symbol) {
-                        diff_map.insert(
-                            symbol.clone(),
-                            SnapshotDiffDto {
-                                symbol: symbol.clone(),
-                                amount_diff: *current_amount,
-                                cmc_id: *cmc_id,
-                            },
-                        );
+                for holding in &current_holdings {
+                    if holding.amount > 0.0
+                        && !dto
+                            .assets
+                            .iter()
+                            .any(|a| a.asset_id == Some(holding.asset_id))
+                    {
+                        diff.push(SnapshotDiffDto {
+                            asset_id: Some(holding.asset_id),
+                            symbol: holding.symbol.clone(),
+                            amount_diff: holding.amount,
+                            cmc_id: holding.cmc_id,
+                        });
                     }
                 }
 
-                dto.diff = Some(diff_map.into_values().collect());
+                dto.diff = Some(diff);
                 dto
             })
             .collect();
 
-        Ok(response)
+        Ok(PageDto::new(items, total, &page))
     }
 }
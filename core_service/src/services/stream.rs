@@ -0,0 +1,410 @@
+use crate::config::StreamConfig;
+use crate::dto::stream::{
+    HeartbeatDto, PortfolioEventDto, PortfolioHoldingDto, PriceUpdateDto, PricesEventDto,
+};
+use crate::models::stream::{PriceEventDb, StreamEventDb, HOLDINGS_EVENT, PRICES_EVENT};
+use crate::repository::asset_price::AssetPriceRepository;
+use crate::repository::stream_event::{StreamEventRepository, STREAM_EVENTS_CHANNEL};
+use crate::services::portfolio::PortfolioService;
+use crate::services::redis::RedisService;
+use crate::utils::datetime::format_iso8601;
+use actix_web::web::{self, Bytes};
+use anyhow::Result;
+use chrono::Utc;
+use futures_util::Stream;
+use serde::Serialize;
+use sqlx::postgres::PgListener;
+use sqlx::PgPool;
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::time::Duration;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, mpsc};
+use tokio_util::sync::CancellationToken;
+
+// Updates kept for connected clients that fall behind; slower clients resync from the event log
+const UPDATE_CAPACITY: usize = 256;
+// Frames buffered per client before the client task waits for the connection
+const CLIENT_BUFFER: usize = 64;
+// Events read from the log per query when catching up
+const REPLAY_BATCH: i64 = 500;
+// How often expired events are deleted from the log
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+// Reconnection delay suggested to EventSource clients
+const CLIENT_RETRY_MS: u64 = 3000;
+
+// Update forwarded to the connected clients of this instance; ids are those of the event log
+#[derive(Debug, Clone)]
+pub enum StreamUpdate {
+    Prices {
+        id: i64,
+        prices: Vec<PriceUpdateDto>,
+    },
+    Portfolio {
+        id: i64,
+        portfolio: PortfolioEventDto,
+    },
+}
+
+// What a streaming client subscribed to
+#[derive(Debug, Clone)]
+pub struct Subscription {
+    pub asset_ids: HashSet<i32>,
+    pub portfolio: bool,
+}
+
+// Service pushing price and portfolio updates to Server-Sent Events clients. Updates are
+// appended to the stream_events log by whichever instance saves prices or transactions, and
+// every instance forwards them to its own clients through LISTEN/NOTIFY.
+#[derive(Clone)]
+pub struct StreamService {
+    pool: web::Data<PgPool>,
+    redis_service: web::Data<RedisService>,
+    portfolio_service: web::Data<PortfolioService>,
+    sender: broadcast::Sender<StreamUpdate>,
+    heartbeat_interval: Duration,
+    retention_secs: i64,
+    closed: CancellationToken,
+}
+
+impl StreamService {
+    // Creates a new StreamService with the configured heartbeat interval and event retention
+    pub fn new(
+        pool: web::Data<PgPool>,
+        redis_service: web::Data<RedisService>,
+        portfolio_service: web::Data<PortfolioService>,
+        config: &StreamConfig,
+    ) -> Self {
+        let (sender, _) = broadcast::channel(UPDATE_CAPACITY);
+        Self {
+            pool,
+            redis_service,
+            portfolio_service,
+            sender,
+            heartbeat_interval: Duration::from_secs(config.heartbeat_interval_secs),
+            retention_secs: config.retention_secs as i64,
+            closed: CancellationToken::new(),
+        }
+    }
+
+    // Ends all client streams so the HTTP server can stop
+    pub fn close(&self) {
+        self.closed.cancel();
+    }
+
+    // Forwards events appended by any instance to the clients of this instance and deletes
+    // expired events, until cancelled
+    pub async fn run(&self, cancel: CancellationToken) {
+        let mut listener = match PgListener::connect_with(self.pool.as_ref()).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                log::error!("Failed to connect the stream event listener: {}", e);
+                return;
+            }
+        };
+        if let Err(e) = listener.listen(STREAM_EVENTS_CHANNEL).await {
+            log::error!("Failed to listen for stream events: {}", e);
+            return;
+        }
+
+        let repo = StreamEventRepository::new(self.pool.as_ref());
+        let mut last_id = match repo.get_id_range().await {
+            Ok(range) => range.map_or(0, |(_, last)| last),
+            Err(e) => {
+                log::error!("Failed to read the stream event log: {}", e);
+                return;
+            }
+        };
+        let mut prune = tokio::time::interval(PRUNE_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                notification = listener.recv() => {
+                    // The listener reconnects on the next call; events sent meanwhile are read below
+                    if let Err(e) = notification {
+                        log::warn!("Stream event listener failed: {}", e);
+                        tokio::time::sleep(Duration::from_secs(1)).await;
+                    }
+                }
+                _ = prune.tick() => {
+                    match repo.delete_older_than(self.retention_secs).await {
+                        Ok(0) => {}
+                        Ok(count) => log::debug!("Deleted {} expired stream events", count),
+                        Err(e) => log::warn!("Failed to delete expired stream events: {}", e),
+                    }
+                }
+            }
+
+            // Reading from the log instead of the notification keeps updates ordered and complete
+            match self.forward_after(last_id).await {
+                Ok(id) => last_id = id,
+                Err(e) => log::warn!("Failed to forward stream events: {}", e),
+            }
+        }
+    }
+
+    // Broadcasts the events appended after the given id, returning the id of the last one
+    async fn forward_after(&self, after_id: i64) -> Result<i64> {
+        let repo = StreamEventRepository::new(self.pool.as_ref());
+        let mut last_id = after_id;
+        loop {
+            let events = repo.get_after(last_id, REPLAY_BATCH).await?;
+            let Some(last) = events.last() else {
+                return Ok(last_id);
+            };
+            last_id = last.id;
+            if self.sender.receiver_count() == 0 {
+                continue;
+            }
+
+            let mut holdings_affected = false;
+            for event in &events {
+                holdings_affected |= event.kind == PRICES_EVENT || event.kind == HOLDINGS_EVENT;
+                if let Some(prices) = decode_prices(event) {
+                    let _ = self.sender.send(StreamUpdate::Prices {
+                        id: event.id,
+                        prices,
+                    });
+                }
+            }
+            // The portfolio is revalued once per batch instead of once per event
+            if holdings_affected {
+                let portfolio = self.get_portfolio().await?;
+                let _ = self.sender.send(StreamUpdate::Portfolio {
+                    id: last_id,
+                    portfolio,
+                });
+            }
+        }
+    }
+
+    // Opens a stream of Server-Sent Events for a subscription. Without a valid last event id the
+    // stream starts with the current prices and portfolio; otherwise missed events are replayed.
+    pub fn subscribe(
+        &self,
+        subscription: Subscription,
+        last_event_id: Option<i64>,
+    ) -> impl Stream<Item = Result<Bytes, Infallible>> {
+        let (tx, rx) = mpsc::channel(CLIENT_BUFFER);
+        // Subscribe before reading the log so no update falls between the two
+        let updates = self.sender.subscribe();
+        let service = self.clone();
+        tokio::spawn(async move {
+            service
+                .feed_client(subscription, last_event_id, updates, tx)
+                .await
+        });
+        futures_util::stream::unfold(rx, |mut rx| async move {
+            rx.recv().await.map(|frame| (Ok(frame), rx))
+        })
+    }
+
+    // Writes the frames of one client until it disconnects or the service is closed
+    async fn feed_client(
+        &self,
+        subscription: Subscription,
+        last_event_id: Option<i64>,
+        mut updates: broadcast::Receiver<StreamUpdate>,
+        tx: mpsc::Sender<Bytes>,
+    ) {
+        let mut sent_id = match self.start_client(&subscription, last_event_id, &tx).await {
+            Ok(id) => id,
+            Err(e) => {
+                log::warn!("Failed to start stream: {}", e);
+                return;
+            }
+        };
+
+        let mut heartbeat = tokio::time::interval(self.heartbeat_interval);
+        heartbeat.tick().await;
+        loop {
+            let frame = tokio::select! {
+                _ = self.closed.cancelled() => return,
+                _ = tx.closed() => return,
+                _ = heartbeat.tick() => {
+                    let heartbeat = HeartbeatDto { timestamp: Utc::now().to_rfc3339() };
+                    event_frame("heartbeat", None, &heartbeat)
+                }
+                update = updates.recv() => match update {
+                    // Portfolio updates share the id of the last event they reflect
+                    Ok(StreamUpdate::Prices { id, .. }) if id <= sent_id => continue,
+                    Ok(StreamUpdate::Portfolio { id, .. }) if id < sent_id => continue,
+                    Ok(StreamUpdate::Prices { id, prices }) => {
+                        let prices = filter_prices(&subscription, prices);
+                        if prices.is_empty() {
+                            continue;
+                        }
+                        sent_id = id;
+                        event_frame("prices", Some(id), &PricesEventDto { prices })
+                    }
+                    Ok(StreamUpdate::Portfolio { id, portfolio }) => {
+                        if !subscription.portfolio {
+                            continue;
+                        }
+                        sent_id = id;
+                        event_frame("portfolio", Some(id), &portfolio)
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        log::warn!("Stream client lagged by {} updates, replaying from the log", skipped);
+                        match self.replay(&subscription, sent_id, &tx).await {
+                            Ok(id) => sent_id = id,
+                            Err(e) => {
+                                log::warn!("Failed to replay stream events: {}", e);
+                                return;
+                            }
+                        }
+                        continue;
+                    }
+                    Err(RecvError::Closed) => return,
+                },
+            };
+            if tx.send(frame).await.is_err() {
+                return;
+            }
+        }
+    }
+
+    // Sends the initial frames of a client, returning the id of the last event they cover
+    async fn start_client(
+        &self,
+        subscription: &Subscription,
+        last_event_id: Option<i64>,
+        tx: &mpsc::Sender<Bytes>,
+    ) -> Result<i64> {
+        send(tx, Bytes::from(format!("retry: {}\n\n", CLIENT_RETRY_MS))).await?;
+
+        let repo = StreamEventRepository::new(self.pool.as_ref());
+        let range = repo.get_id_range().await?;
+        if let Some(last_event_id) = last_event_id {
+            // Resume only if no event after the client's last one has expired
+            let resumable = match range {
+                Some((first, last)) => last_event_id + 1 >= first && last_event_id <= last,
+                None => false,
+            };
+            if resumable {
+                return self.replay(subscription, last_event_id, tx).await;
+            }
+        }
+
+        let current_id = range.map_or(0, |(_, last)| last);
+        if !subscription.asset_ids.is_empty() {
+            let price_repo =
+                AssetPriceRepository::new(self.pool.as_ref(), self.redis_service.as_ref().clone());
+            let mut prices: Vec<PriceUpdateDto> = price_repo
+                .get_latest_prices()
+                .await?
+                .into_iter()
+                .filter(|(asset_id, _, _)| subscription.asset_ids.contains(asset_id))
+                .map(|(asset_id, price_usd, timestamp)| PriceUpdateDto {
+                    asset_id,
+                    price_usd,
+                    timestamp: format_iso8601(timestamp),
+                })
+                .collect();
+            prices.sort_by_key(|price| price.asset_id);
+            let frame = event_frame("prices", Some(current_id), &PricesEventDto { prices });
+            send(tx, frame).await?;
+        }
+        if subscription.portfolio {
+            let portfolio = self.get_portfolio().await?;
+            send(tx, event_frame("portfolio", Some(current_id), &portfolio)).await?;
+        }
+        Ok(current_id)
+    }
+
+    // Sends the events after the given id from the log, followed by the current portfolio if any
+    // event could have changed it, returning the id of the last event
+    async fn replay(
+        &self,
+        subscription: &Subscription,
+        after_id: i64,
+        tx: &mpsc::Sender<Bytes>,
+    ) -> Result<i64> {
+        let repo = StreamEventRepository::new(self.pool.as_ref());
+        let mut last_id = after_id;
+        loop {
+            let events = repo.get_after(last_id, REPLAY_BATCH).await?;
+            let Some(last) = events.last() else {
+                break;
+            };
+            last_id = last.id;
+            for event in &events {
+                let Some(prices) = decode_prices(event) else {
+                    continue;
+                };
+                let prices = filter_prices(subscription, prices);
+                if !prices.is_empty() {
+                    send(
+                        tx,
+                        event_frame("prices", Some(event.id), &PricesEventDto { prices }),
+                    )
+                    .await?;
+                }
+            }
+        }
+        if subscription.portfolio && last_id > after_id {
+            let portfolio = self.get_portfolio().await?;
+            send(tx, event_frame("portfolio", Some(last_id), &portfolio)).await?;
+        }
+        Ok(last_id)
+    }
+
+    // Values the current holdings with the latest stored prices
+    async fn get_portfolio(&self) -> Result<PortfolioEventDto> {
+        let holdings = self
+            .portfolio_service
+            .get_holdings_with_stored_prices()
+            .await?;
+        let holdings: Vec<PortfolioHoldingDto> = holdings
+            .into_iter()
+            .map(|(holding, price_usd)| PortfolioHoldingDto {
+                asset_id: holding.asset_id,
+                symbol: holding.symbol,
+                amount: holding.amount,
+                price_usd,
+                value_usd: price_usd.map(|price| price * holding.amount),
+            })
+            .collect();
+        Ok(PortfolioEventDto {
+            total_value_usd: holdings.iter().filter_map(|h| h.value_usd).sum(),
+            holdings,
+            updated_at: Utc::now().to_rfc3339(),
+        })
+    }
+}
+
+// Decodes the prices of a prices event, or None for other kinds
+fn decode_prices(event: &StreamEventDb) -> Option<Vec<PriceUpdateDto>> {
+    if event.kind != PRICES_EVENT {
+        return None;
+    }
+    match serde_json::from_value::<Vec<PriceEventDb>>(event.payload.0.clone()) {
+        Ok(prices) => Some(prices.into_iter().map(PriceUpdateDto::from).collect()),
+        Err(e) => {
+            log::warn!("Invalid payload of stream event {}: {}", event.id, e);
+            None
+        }
+    }
+}
+
+// Keeps the prices of the subscribed assets
+fn filter_prices(subscription: &Subscription, prices: Vec<PriceUpdateDto>) -> Vec<PriceUpdateDto> {
+    prices
+        .into_iter()
+        .filter(|price| subscription.asset_ids.contains(&price.asset_id))
+        .collect()
+}
+
+// Formats a Server-Sent Event with a JSON data line
+fn event_frame(event: &str, id: Option<i64>, data: &impl Serialize) -> Bytes {
+    let data = serde_json::to_string(data).unwrap_or_else(|_| "null".to_string());
+    let id = id.map(|id| format!("id: {}\n", id)).unwrap_or_default();
+    Bytes::from(format!("{}event: {}\ndata: {}\n\n", id, event, data))
+}
+
+// Sends a frame to a client, failing once the client has disconnected
+async fn send(tx: &mpsc::Sender<Bytes>, frame: Bytes) -> Result<()> {
+    tx.send(frame)
+        .await
+        .map_err(|_| anyhow::anyhow!("Stream client disconnected"))
+}
@@ -0,0 +1,218 @@
+use crate::services::leader::LeaderElection;
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+// Bounds of the exponential backoff between restarts of a failed task
+const MIN_RESTART_DELAY: Duration = Duration::from_secs(1);
+const MAX_RESTART_DELAY: Duration = Duration::from_secs(60);
+// A run lasting this long resets the backoff of a task
+const HEALTHY_RUN: Duration = Duration::from_secs(60);
+
+// Lifecycle state of a supervised task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskState {
+    Running,
+    Standby,    // Waiting for another instance to give up leadership of the job
+    Restarting, // Waiting out the backoff after a panic or unexpected exit
+    Stopped,    // Stopped after shutdown was requested
+}
+
+impl TaskState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Running => "running",
+            Self::Standby => "standby",
+            Self::Restarting => "restarting",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+// Status of a supervised task
+#[derive(Debug, Clone)]
+pub struct TaskStatus {
+    pub state: TaskState,
+    pub restarts: u32,
+    pub started_at: DateTime<Utc>, // Start of the current or latest run
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+}
+
+// Runs background tasks, restarting them with backoff when they panic or exit unexpectedly,
+// and stops them cooperatively on shutdown
+#[derive(Clone)]
+pub struct TaskSupervisor {
+    statuses: Arc<Mutex<BTreeMap<String, TaskStatus>>>,
+    handles: Arc<Mutex<Vec<JoinHandle<()>>>>,
+    shutdown: CancellationToken,
+    leader: LeaderElection,
+}
+
+impl TaskSupervisor {
+    // Creates a supervisor without tasks, electing leaders of exclusive tasks through leader
+    pub fn new(leader: LeaderElection) -> Self {
+        Self {
+            leader,
+            statuses: Arc::new(Mutex::new(BTreeMap::new())),
+            handles: Arc::new(Mutex::new(Vec::new())),
+            shutdown: CancellationToken::new(),
+        }
+    }
+
+    // Spawns a supervised task. The factory is called for every (re)start with a token that is
+    // cancelled on shutdown; the task should return once it is cancelled and must not be
+    // interrupted in the middle of a database write.
+    pub fn spawn<F, Fut>(&self, name: &str, factory: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let name = name.to_string();
+        let statuses = self.statuses.clone();
+        let shutdown = self.shutdown.clone();
+        self.statuses.lock().unwrap().insert(
+            name.clone(),
+            TaskStatus {
+                state: TaskState::Running,
+                restarts: 0,
+                started_at: Utc::now(),
+                last_error: None,
+                last_error_at: None,
+            },
+        );
+
+        let handle = tokio::spawn(async move {
+            let mut consecutive_failures: u32 = 0;
+            loop {
+                let started = Instant::now();
+                // Each run is a separate task so a panic is caught instead of ending supervision
+                let result = tokio::spawn(factory(shutdown.child_token())).await;
+                if shutdown.is_cancelled() {
+                    update(&statuses, &name, |status| status.state = TaskState::Stopped);
+                    log::info!("Task {} stopped", name);
+                    return;
+                }
+
+                let error = match result {
+                    Ok(()) => "exited unexpectedly".to_string(),
+                    Err(e) if e.is_panic() => format!("panicked: {}", panic_message(e)),
+                    Err(e) => format!("failed: {}", e),
+                };
+                if started.elapsed() >= HEALTHY_RUN {
+                    consecutive_failures = 0;
+                }
+                consecutive_failures += 1;
+                let delay = MIN_RESTART_DELAY
+                    .saturating_mul(2u32.saturating_pow(consecutive_failures - 1))
+                    .min(MAX_RESTART_DELAY);
+                log::error!(
+                    "Task {} {}, restarting in {}s",
+                    name,
+                    error,
+                    delay.as_secs()
+                );
+                update(&statuses, &name, |status| {
+                    status.state = TaskState::Restarting;
+                    status.last_error = Some(error);
+                    status.last_error_at = Some(Utc::now());
+                });
+
+                tokio::select! {
+                    _ = shutdown.cancelled() => {
+                        update(&statuses, &name, |status| status.state = TaskState::Stopped);
+                        return;
+                    }
+                    _ = tokio::time::sleep(delay) => {}
+                }
+                update(&statuses, &name, |status| {
+                    status.state = TaskState::Running;
+                    status.restarts += 1;
+                    status.started_at = Utc::now();
+                });
+            }
+        });
+        self.handles.lock().unwrap().push(handle);
+    }
+
+    // Spawns a supervised task that runs on a single instance at a time; the other instances
+    // keep it on standby and take over when the leader stops or dies
+    pub fn spawn_exclusive<F, Fut>(&self, name: &str, factory: F)
+    where
+        F: Fn(CancellationToken) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        let job = name.to_string();
+        let leader = self.leader.clone();
+        let statuses = self.statuses.clone();
+        let factory = Arc::new(factory);
+        self.spawn(name, move |cancel| {
+            let job = job.clone();
+            let leader = leader.clone();
+            let statuses = statuses.clone();
+            let factory = factory.clone();
+            async move {
+                let on_leadership = |is_leader: bool| {
+                    update(&statuses, &job, |status| {
+                        status.state = if is_leader {
+                            TaskState::Running
+                        } else {
+                            TaskState::Standby
+                        }
+                    })
+                };
+                leader
+                    .run(&job, cancel, on_leadership, |cancel| factory(cancel))
+                    .await
+            }
+        });
+    }
+
+    // Returns the status of every supervised task by name
+    pub fn statuses(&self) -> BTreeMap<String, TaskStatus> {
+        self.statuses.lock().unwrap().clone()
+    }
+
+    // Signals cancellation to all tasks and waits up to the timeout for them to finish
+    pub async fn shutdown(&self, timeout: Duration) {
+        self.shutdown.cancel();
+        let handles: Vec<JoinHandle<()>> = self.handles.lock().unwrap().drain(..).collect();
+        log::info!("Waiting for {} background tasks to stop", handles.len());
+        let wait_all = async {
+            for handle in handles {
+                let _ = handle.await;
+            }
+        };
+        if tokio::time::timeout(timeout, wait_all).await.is_err() {
+            log::warn!(
+                "Background tasks did not stop within {}s",
+                timeout.as_secs()
+            );
+        }
+    }
+}
+
+// Applies a change to the status of a task
+fn update(
+    statuses: &Mutex<BTreeMap<String, TaskStatus>>,
+    name: &str,
+    change: impl FnOnce(&mut TaskStatus),
+) {
+    if let Some(status) = statuses.lock().unwrap().get_mut(name) {
+        change(status);
+    }
+}
+
+// Extracts the message of a panic payload
+fn panic_message(err: tokio::task::JoinError) -> String {
+    let payload = err.into_panic();
+    payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
@@ -0,0 +1,186 @@
+use crate::dto::tag::{
+    CreateTagDto, TagAssetBreakdownDto, TagBreakdownDto, TagDto, TagTransactionsResponse,
+};
+use crate::error::AppError;
+use crate::models::tag::{parse_tag_names, BreakdownParams, TagAssetTotalsDb};
+use crate::repository::tag::TagRepository;
+use actix_web::web;
+use anyhow::Result;
+use sqlx::PgPool;
+
+// Service for managing tags of transactions and the breakdowns grouped by them
+#[derive(Clone)]
+pub struct TagService {
+    pool: web::Data<PgPool>,
+}
+
+impl TagService {
+    // Creates a new instance of TagService
+    pub fn new(pool: web::Data<PgPool>) -> Self {
+        Self { pool }
+    }
+
+    // Creates a new tag; names are unique regardless of case
+    pub async fn create(&self, tag: CreateTagDto) -> Result<TagDto, AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        let id = repo
+            .create(tag.name.trim(), tag.description.as_deref())
+            .await?;
+        self.get(id).await
+    }
+
+    // Retrieves all tags with the number of tagged transactions
+    pub async fn get_all(&self) -> Result<Vec<TagDto>, AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        let tags = repo.get_all().await?;
+        Ok(tags.into_iter().map(TagDto::from).collect())
+    }
+
+    // Retrieves a tag or fails with 404
+    pub async fn get(&self, id: i32) -> Result<TagDto, AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        repo.get_by_id(id)
+            .await?
+            .map(TagDto::from)
+            .ok_or_else(|| tag_not_found(id))
+    }
+
+    // Renames a tag and replaces its description
+    pub async fn update(&self, id: i32, tag: CreateTagDto) -> Result<TagDto, AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        if !repo
+            .update(id, tag.name.trim(), tag.description.as_deref())
+            .await?
+        {
+            return Err(tag_not_found(id));
+        }
+        self.get(id).await
+    }
+
+    // Deletes a tag; the tagged transactions are kept
+    pub async fn delete(&self, id: i32) -> Result<(), AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        if !repo.delete(id).await? {
+            return Err(tag_not_found(id));
+        }
+        Ok(())
+    }
+
+    // Tags transactions in bulk; nothing is tagged if any of them does not exist
+    pub async fn tag_transactions(
+        &self,
+        id: i32,
+        transaction_ids: Vec<i32>,
+    ) -> Result<TagTransactionsResponse, AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        self.get(id).await?;
+
+        let missing = repo.get_missing_transactions(&transaction_ids).await?;
+        if !missing.is_empty() {
+            let ids = missing.iter().map(|id| id.to_string()).collect::<Vec<_>>();
+            return Err(AppError::invalid_field(
+                "transaction_ids",
+                format!("Transactions {} not found", ids.join(", ")),
+            ));
+        }
+
+        let mut unique_ids = transaction_ids;
+        unique_ids.sort_unstable();
+        unique_ids.dedup();
+        let tagged = repo.tag_transactions(id, &unique_ids).await?;
+        Ok(TagTransactionsResponse {
+            tagged,
+            already_tagged: unique_ids.len() as u64 - tagged,
+        })
+    }
+
+    // Removes a tag from a transaction
+    pub async fn untag_transaction(&self, id: i32, transaction_id: i32) -> Result<(), AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        self.get(id).await?;
+        if !repo.untag_transaction(id, transaction_id).await? {
+            return Err(AppError::not_found(format!(
+                "Transaction {} is not tagged with tag {}",
+                transaction_id, id
+            )));
+        }
+        Ok(())
+    }
+
+    // Calculates holdings and profit and loss per tag with the average cost method, valued at
+    // the latest stored prices. Buy fees are added to the cost and sell fees deducted from the
+    // proceeds. Untagged transactions are included by default unless tags are selected.
+    pub async fn get_breakdown(
+        &self,
+        params: &BreakdownParams,
+    ) -> Result<Vec<TagBreakdownDto>, AppError> {
+        let repo = TagRepository::new(self.pool.as_ref());
+        let names = params.tags.as_deref().map(parse_tag_names);
+        let include_untagged = params.include_untagged.unwrap_or(names.is_none());
+        let totals = repo
+            .get_asset_totals(names.as_deref(), include_untagged)
+            .await?;
+
+        // Rows are sorted by tag, so each tag is a consecutive run
+        let mut breakdowns: Vec<TagBreakdownDto> = Vec::new();
+        for row in totals {
+            let asset = asset_breakdown(&row);
+            match breakdowns.last_mut() {
+                Some(breakdown) if breakdown.tag_id == row.tag_id => add_asset(breakdown, asset),
+                _ => {
+                    let mut breakdown = TagBreakdownDto {
+                        tag_id: row.tag_id,
+                        tag: row.tag,
+                        value_usd: 0.0,
+                        cost_basis_usd: 0.0,
+                        realized_pnl_usd: 0.0,
+                        unrealized_pnl_usd: 0.0,
+                        assets: Vec::new(),
+                    };
+                    add_asset(&mut breakdown, asset);
+                    breakdowns.push(breakdown);
+                }
+            }
+        }
+        Ok(breakdowns)
+    }
+}
+
+// Calculates the holding and profit and loss of an asset within a tag from its trade totals
+fn asset_breakdown(row: &TagAssetTotalsDb) -> TagAssetBreakdownDto {
+    let amount = row.bought - row.sold;
+    let held = amount.max(0.0);
+    let average_cost = (row.bought > 0.0).then(|| row.cost / row.bought);
+    let realized_pnl = if row.sold > 0.0 {
+        average_cost.map(|cost| row.proceeds - row.sold * cost)
+    } else {
+        Some(0.0)
+    };
+    TagAssetBreakdownDto {
+        asset_id: row.asset_id,
+        symbol: row.symbol.clone(),
+        amount,
+        average_cost_usd: average_cost,
+        cost_basis_usd: average_cost.map(|cost| held * cost),
+        price_usd: row.price_usd,
+        value_usd: row.price_usd.map(|price| held * price),
+        realized_pnl_usd: realized_pnl,
+        unrealized_pnl_usd: row
+            .price_usd
+            .zip(average_cost)
+            .map(|(price, cost)| held * (price - cost)),
+    }
+}
+
+// Adds an asset to a tag breakdown, summing its known amounts into the totals
+fn add_asset(breakdown: &mut TagBreakdownDto, asset: TagAssetBreakdownDto) {
+    breakdown.value_usd += asset.value_usd.unwrap_or(0.0);
+    breakdown.cost_basis_usd += asset.cost_basis_usd.unwrap_or(0.0);
+    breakdown.realized_pnl_usd += asset.realized_pnl_usd.unwrap_or(0.0);
+    breakdown.unrealized_pnl_usd += asset.unrealized_pnl_usd.unwrap_or(0.0);
+    breakdown.assets.push(asset);
+}
+
+fn tag_not_found(id: i32) -> AppError {
+    AppError::not_found(format!("Tag {} not found", id))
+}
@@ -1,55 +1,261 @@
-use crate::dto::transaction::{CreateTransactionDto, TransactionDto};
-use crate::error::AppError;
-use crate::repository::asset::AssetRepository;
+use crate::dto::pagination::PageDto;
+use crate::dto::transaction::{
+    CreateTransactionBatchDto, CreateTransactionDto, TransactionBatchDto, TransactionDto,
+};
+use crate::error::{field_errors, AppError, FieldError};
+use crate::models::pagination::PageParams;
+use crate::models::stream::{HoldingsEventDb, HOLDINGS_EVENT};
+use crate::models::transaction::{BatchLookupDb, FilterParams, TransactionSort};
+use crate::models::webhook::WebhookEvent;
+use crate::repository::stream_event::StreamEventRepository;
 use crate::repository::transaction::TransactionRepository;
-use crate::repository::wallet::WalletRepository;
+use crate::services::webhook::WebhookService;
+use crate::utils::datetime::parse_iso8601;
 use actix_web::web;
 use anyhow::Result;
+use sqlx::types::time::PrimitiveDateTime;
 use sqlx::PgPool;
+use std::collections::HashMap;
+use validator::Validate;
+
+// Amounts are floats, so sells may exceed holdings by this much to absorb rounding
+const BALANCE_TOLERANCE: f64 = 1e-9;
 
 // Service for managing transactions
 #[derive(Clone)]
 pub struct TransactionService {
     pool: web::Data<PgPool>,
+    webhook_service: web::Data<WebhookService>,
 }
 
 impl TransactionService {
     // Creates a new instance of TransactionService
-    pub fn new(pool: web::Data<PgPool>) -> Self {
-        Self { pool }
+    pub fn new(pool: web::Data<PgPool>, webhook_service: web::Data<WebhookService>) -> Self {
+        Self {
+            pool,
+            webhook_service,
+        }
     }
 
-    // Creates a new transaction with validation
+    // Creates a new transaction with validation. It takes the same wallet lock as a batch, so a
+    // sell cannot exceed the holdings of its wallet even when transactions are created
+    // concurrently.
     pub async fn create(
         &self,
         transaction: CreateTransactionDto,
     ) -> Result<TransactionDto, AppError> {
-        let asset_repo = AssetRepository::new(self.pool.as_ref());
-        let wallet_repo = WalletRepository::new(self.pool.as_ref());
-        let transaction_repo = TransactionRepository::new(self.pool.as_ref());
+        let transactions = [transaction];
+        let record = TransactionRepository::new(self.pool.as_ref())
+            .create_batch(&transactions, |lookup| {
+                let errors = check_item(&transactions[0], lookup, &mut lookup.holdings.clone());
+                if errors.is_empty() {
+                    Ok(())
+                } else {
+                    Err(AppError::invalid_fields(errors).into())
+                }
+            })
+            .await?
+            .remove(0);
 
-        // Check if asset_id exists
-        if !asset_repo
-            .exists(transaction.asset_id)
-            .await
-            .map_err(AppError::internal)?
-        {
-            return Err(AppError::bad_request(anyhow::anyhow!("Asset not found")));
+        let transaction = TransactionDto::from(record);
+        self.notify_created(&transaction).await;
+        Ok(transaction)
+    }
+
+    // Notifies streaming clients and webhook subscriptions of a new transaction, including
+    // transactions created by other services such as confirmed DCA executions
+    pub async fn notify_created(&self, transaction: &TransactionDto) {
+        self.publish_holdings(vec![transaction.asset_id]).await;
+        self.webhook_service
+            .emit(WebhookEvent::TransactionCreated, transaction)
+            .await;
+    }
+
+    // Gets a filtered, sorted page of transactions, checking that the ranges of the filters
+    // are not inverted
+    pub async fn get_page(
+        &self,
+        filters: &FilterParams,
+        page: &PageParams<TransactionSort>,
+    ) -> Result<PageDto<TransactionDto>, AppError> {
+        let start_date = parse_filter_date("start_date", filters.start_date.as_deref())?;
+        let end_date = parse_filter_date("end_date", filters.end_date.as_deref())?;
+        if let (Some(start), Some(end)) = (start_date, end_date) {
+            if end < start {
+                return Err(AppError::invalid_field(
+                    "end_date",
+                    "end_date must not be before start_date",
+                ));
+            }
+        }
+        check_range("max_amount", filters.min_amount, filters.max_amount)?;
+        check_range("max_price", filters.min_price, filters.max_price)?;
+
+        let (transactions, total) = TransactionRepository::new(self.pool.as_ref())
+            .get_transactions(filters, start_date, end_date, page)
+            .await?;
+        let items = transactions
+            .into_iter()
+            .map(TransactionDto::from)
+            .collect::<Vec<_>>();
+        Ok(PageDto::new(items, total, page))
+    }
+
+    // Creates a batch of transactions atomically. Every item is validated, including the
+    // existence of its asset and wallet and, for sells, the holdings of its wallet after the
+    // preceding items; if any item fails, nothing is written and all failures are returned with
+    // fields prefixed by the item, e.g. "transactions[2].amount".
+    pub async fn create_batch(
+        &self,
+        batch: CreateTransactionBatchDto,
+    ) -> Result<TransactionBatchDto, AppError> {
+        let transactions = batch.transactions;
+
+        let errors = transactions
+            .iter()
+            .enumerate()
+            .filter_map(|(index, transaction)| {
+                transaction
+                    .validate()
+                    .err()
+                    .map(|e| item_errors(index, field_errors(&e)))
+            })
+            .flatten()
+            .collect::<Vec<_>>();
+        if !errors.is_empty() {
+            return Err(AppError::invalid_fields(errors));
+        }
+
+        let records = TransactionRepository::new(self.pool.as_ref())
+            .create_batch(&transactions, |lookup| {
+                check_batch(&transactions, lookup).map_err(anyhow::Error::from)
+            })
+            .await?;
+
+        let mut asset_ids = transactions.iter().map(|t| t.asset_id).collect::<Vec<_>>();
+        asset_ids.sort_unstable();
+        asset_ids.dedup();
+        let transactions = records
+            .into_iter()
+            .map(TransactionDto::from)
+            .collect::<Vec<_>>();
+        self.publish_holdings(asset_ids).await;
+        for transaction in &transactions {
+            self.webhook_service
+                .emit(WebhookEvent::TransactionCreated, transaction)
+                .await;
         }
+        Ok(TransactionBatchDto {
+            created: transactions.len(),
+            transactions,
+        })
+    }
 
-        // Check if wallet_id exists
-        if !wallet_repo
-            .exists(transaction.wallet_id)
+    // Notifies streaming clients that holdings of the given assets changed; failures are only
+    // logged because the transactions are already saved
+    async fn publish_holdings(&self, asset_ids: Vec<i32>) {
+        let payload = serde_json::json!(HoldingsEventDb { asset_ids });
+        if let Err(e) = StreamEventRepository::new(self.pool.as_ref())
+            .append(HOLDINGS_EVENT, payload)
             .await
-            .map_err(AppError::internal)?
         {
-            return Err(AppError::bad_request(anyhow::anyhow!("Wallet not found")));
+            log::warn!("Failed to publish holdings change: {}", e);
         }
+    }
+}
 
-        let record = transaction_repo
-            .create(transaction)
-            .await
-            .map_err(AppError::internal)?;
-        Ok(record.into())
+// Parses an optional ISO 8601 date filter
+fn parse_filter_date(
+    field: &str,
+    date: Option<&str>,
+) -> Result<Option<PrimitiveDateTime>, AppError> {
+    date.map(|date| parse_iso8601(date).map_err(|e| AppError::invalid_field(field, e.to_string())))
+        .transpose()
+}
+
+// Checks that the maximum of a range filter is not below its minimum
+fn check_range(field: &str, min: Option<f64>, max: Option<f64>) -> Result<(), AppError> {
+    match (min, max) {
+        (Some(min), Some(max)) if max < min => Err(AppError::invalid_field(
+            field,
+            format!("{} must not be less than the minimum", field),
+        )),
+        _ => Ok(()),
+    }
+}
+
+// Checks that the assets and wallets of a batch exist and that no sell exceeds the holdings of
+// its wallet, applying the items in order
+fn check_batch(
+    transactions: &[CreateTransactionDto],
+    lookup: &BatchLookupDb,
+) -> Result<(), AppError> {
+    let mut holdings = lookup.holdings.clone();
+    let errors = transactions
+        .iter()
+        .enumerate()
+        .flat_map(|(index, transaction)| {
+            item_errors(index, check_item(transaction, lookup, &mut holdings))
+        })
+        .collect::<Vec<_>>();
+
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(AppError::invalid_fields(errors))
+    }
+}
+
+// Checks that the asset and wallet of a transaction exist and that a sell does not exceed the
+// holdings of its wallet, applying the transaction to holdings when it is valid
+fn check_item(
+    transaction: &CreateTransactionDto,
+    lookup: &BatchLookupDb,
+    holdings: &mut HashMap<(i32, i32), f64>,
+) -> Vec<FieldError> {
+    let mut errors = Vec::new();
+    if !lookup.asset_ids.contains(&transaction.asset_id) {
+        errors.push(FieldError {
+            field: "asset_id".to_string(),
+            message: format!("Asset {} not found", transaction.asset_id),
+        });
+    }
+    if !lookup.wallet_ids.contains(&transaction.wallet_id) {
+        errors.push(FieldError {
+            field: "wallet_id".to_string(),
+            message: format!("Wallet {} not found", transaction.wallet_id),
+        });
     }
+    if errors.is_empty() {
+        let held = holdings
+            .entry((transaction.wallet_id, transaction.asset_id))
+            .or_insert(0.0);
+        if transaction.transaction_type == "BUY" {
+            *held += transaction.amount;
+        } else if transaction.amount > *held + BALANCE_TOLERANCE {
+            errors.push(FieldError {
+                field: "amount".to_string(),
+                message: format!(
+                    "Insufficient balance: wallet {} holds {} of asset {}",
+                    transaction.wallet_id,
+                    held.max(0.0),
+                    transaction.asset_id
+                ),
+            });
+        } else {
+            *held -= transaction.amount;
+        }
+    }
+    errors
+}
+
+// Prefixes the fields of errors with the position of their item in a batch
+fn item_errors(index: usize, errors: Vec<FieldError>) -> Vec<FieldError> {
+    errors
+        .into_iter()
+        .map(|error| FieldError {
+            field: format!("transactions[{}].{}", index, error.field),
+            message: error.message,
+        })
+        .collect()
 }
@@ -1,4 +1,7 @@
+use crate::dto::pagination::PageDto;
 use crate::dto::wallet::{CreateWalletDto, WalletDto};
+use crate::models::pagination::PageParams;
+use crate::models::wallet::WalletSort;
 use crate::repository::wallet::WalletRepository;
 use actix_web::web;
 use anyhow::Result;
@@ -25,10 +28,11 @@ impl WalletService {
         Ok(record.into())
     }
 
-    // Retrieves all wallets
-    pub async fn get_all(&self) -> Result<Vec<WalletDto>> {
+    // Retrieves a page of wallets
+    pub async fn get_all(&self, page: PageParams<WalletSort>) -> Result<PageDto<WalletDto>> {
         let repo = WalletRepository::new(self.pool.as_ref());
-        let wallets = repo.get_all().await?;
-        Ok(wallets.into_iter().map(WalletDto::from).collect())
+        let (wallets, total) = repo.get_page(&page).await?;
+        let items = wallets.into_iter().map(WalletDto::from).collect();
+        Ok(PageDto::new(items, total, &page))
     }
 }
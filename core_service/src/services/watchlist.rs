@@ -0,0 +1,124 @@
+use crate::dto::watchlist::{
+    CreateWatchlistDto, UpdateWatchlistDto, WatchlistAssetDto, WatchlistDetailDto, WatchlistDto,
+};
+use crate::error::AppError;
+use crate::repository::asset::AssetRepository;
+use crate::repository::watchlist::WatchlistRepository;
+use actix_web::web;
+use anyhow::Result;
+use sqlx::PgPool;
+
+// Service for managing watchlists of followed assets
+#[derive(Clone)]
+pub struct WatchlistService {
+    pool: web::Data<PgPool>,
+}
+
+impl WatchlistService {
+    // Creates a new instance of WatchlistService
+    pub fn new(pool: web::Data<PgPool>) -> Self {
+        Self { pool }
+    }
+
+    // Creates a new watchlist with optional initial assets
+    pub async fn create(&self, watchlist: CreateWatchlistDto) -> Result<WatchlistDto, AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        let asset_ids = watchlist.asset_ids.unwrap_or_default();
+        for asset_id in &asset_ids {
+            self.ensure_asset_exists(*asset_id).await?;
+        }
+
+        let id = repo.create(watchlist.name, &asset_ids).await?;
+        self.get_watchlist(id).await
+    }
+
+    // Retrieves all watchlists
+    pub async fn get_all(&self) -> Result<Vec<WatchlistDto>, AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        let watchlists = repo.get_all().await?;
+        Ok(watchlists.into_iter().map(WatchlistDto::from).collect())
+    }
+
+    // Retrieves a watchlist with the latest price and 24h/7d change of each asset
+    pub async fn get_details(&self, id: i32) -> Result<WatchlistDetailDto, AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        let watchlist = self.get_watchlist(id).await?;
+        let assets = repo.get_assets_with_prices(id).await?;
+
+        Ok(WatchlistDetailDto {
+            id: watchlist.id,
+            name: watchlist.name,
+            created_at: watchlist.created_at,
+            assets: assets.into_iter().map(WatchlistAssetDto::from).collect(),
+        })
+    }
+
+    // Renames a watchlist
+    pub async fn update(
+        &self,
+        id: i32,
+        watchlist: UpdateWatchlistDto,
+    ) -> Result<WatchlistDto, AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        if !repo.rename(id, watchlist.name).await? {
+            return Err(watchlist_not_found(id));
+        }
+        self.get_watchlist(id).await
+    }
+
+    // Deletes a watchlist
+    pub async fn delete(&self, id: i32) -> Result<(), AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        if !repo.delete(id).await? {
+            return Err(watchlist_not_found(id));
+        }
+        Ok(())
+    }
+
+    // Adds an asset to a watchlist
+    pub async fn add_asset(&self, id: i32, asset_id: i32) -> Result<WatchlistDto, AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        self.get_watchlist(id).await?;
+        self.ensure_asset_exists(asset_id).await?;
+        repo.add_asset(id, asset_id).await?;
+        self.get_watchlist(id).await
+    }
+
+    // Removes an asset from a watchlist
+    pub async fn remove_asset(&self, id: i32, asset_id: i32) -> Result<(), AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        self.get_watchlist(id).await?;
+        if !repo.remove_asset(id, asset_id).await? {
+            return Err(AppError::not_found(format!(
+                "Asset {} is not in watchlist {}",
+                asset_id, id
+            )));
+        }
+        Ok(())
+    }
+
+    // Fetches a watchlist or fails with 404
+    async fn get_watchlist(&self, id: i32) -> Result<WatchlistDto, AppError> {
+        let repo = WatchlistRepository::new(self.pool.as_ref());
+        repo.get_by_id(id)
+            .await?
+            .map(WatchlistDto::from)
+            .ok_or_else(|| watchlist_not_found(id))
+    }
+
+    // Fails with 400 if the asset does not exist
+    async fn ensure_asset_exists(&self, asset_id: i32) -> Result<(), AppError> {
+        let asset_repo = AssetRepository::new(self.pool.as_ref());
+        if !asset_repo.exists(asset_id).await? {
+            return Err(AppError::invalid_field(
+                "asset_id",
+                format!("Asset {} not found", asset_id),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn watchlist_not_found(id: i32) -> AppError {
+    AppError::not_found(format!("Watchlist {} not found", id))
+}
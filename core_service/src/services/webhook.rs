@@ -0,0 +1,371 @@
+use crate::config::WebhookConfig;
+use crate::dto::pagination::PageDto;
+use crate::dto::webhook::{CreateWebhookDto, UpdateWebhookDto, WebhookDeliveryDto, WebhookDto};
+use crate::error::AppError;
+use crate::models::pagination::PageParams;
+use crate::models::webhook::{DeliveryQueryParams, DeliverySort, DueDeliveryDb, WebhookEvent};
+use crate::repository::webhook::WebhookRepository;
+use crate::utils::datetime::format_iso8601;
+use actix_web::web;
+use anyhow::Result;
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::Sha256;
+use sqlx::types::Uuid;
+use sqlx::PgPool;
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+// Deliveries claimed per outbox poll
+const DELIVERY_BATCH: i64 = 20;
+// Longest delay between two attempts of a delivery
+const MAX_RETRY_DELAY_SECS: u64 = 3600;
+// Longest response body kept in the delivery log as the error of a failed attempt
+const MAX_ERROR_LEN: usize = 500;
+// Statuses of the delivery log
+const DELIVERY_STATUSES: [&str; 3] = ["pending", "delivered", "failed"];
+
+// Service managing webhook subscriptions, queueing domain events into the delivery outbox and
+// delivering them with HMAC-SHA256 signatures, retrying failed deliveries with backoff
+#[derive(Clone)]
+pub struct WebhookService {
+    pool: web::Data<PgPool>,
+    client: reqwest::Client,
+    poll_interval: Duration,
+    timeout: Duration,
+    max_attempts: i32,
+    retry_base_delay_secs: u64,
+}
+
+impl WebhookService {
+    // Creates a new WebhookService with the configured delivery settings
+    pub fn new(pool: web::Data<PgPool>, config: &WebhookConfig) -> Self {
+        let timeout = Duration::from_secs(config.timeout_secs);
+        let client = reqwest::Client::builder()
+            .timeout(timeout)
+            .user_agent("austeris-webhooks")
+            .build()
+            .expect("Failed to build webhook HTTP client");
+        Self {
+            pool,
+            client,
+            poll_interval: Duration::from_secs(config.poll_interval_secs),
+            timeout,
+            max_attempts: config.max_attempts as i32,
+            retry_base_delay_secs: config.retry_base_delay_secs,
+        }
+    }
+
+    // Queues an event for every subscription to it. Failures are only logged because the change
+    // that raised the event is already saved.
+    pub async fn emit(&self, event: WebhookEvent, data: &impl Serialize) {
+        let payload = match serde_json::to_value(data) {
+            Ok(payload) => payload,
+            Err(e) => {
+                log::error!(
+                    "Failed to serialize webhook event {}: {}",
+                    event.as_str(),
+                    e
+                );
+                return;
+            }
+        };
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        match repo.enqueue(Uuid::new_v4(), event.as_str(), payload).await {
+            Ok(0) => {}
+            Ok(count) => log::debug!("Queued {} {} deliveries", count, event.as_str()),
+            Err(e) => log::error!("Failed to queue webhook event {}: {}", event.as_str(), e),
+        }
+    }
+
+    // Creates a subscription, returning its secret once
+    pub async fn create(&self, webhook: CreateWebhookDto) -> Result<WebhookDto, AppError> {
+        let event_types = validate_event_types(webhook.event_types.unwrap_or_default())?;
+        validate_url(&webhook.url)?;
+        let secret = webhook.secret.unwrap_or_else(generate_secret);
+
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        let record = repo
+            .create(
+                webhook.url,
+                secret.clone(),
+                event_types,
+                webhook.description,
+            )
+            .await?;
+        let mut response = WebhookDto::from(record);
+        response.secret = Some(secret);
+        Ok(response)
+    }
+
+    // Retrieves all subscriptions
+    pub async fn get_all(&self) -> Result<Vec<WebhookDto>, AppError> {
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        let subscriptions = repo.get_all().await?;
+        Ok(subscriptions.into_iter().map(WebhookDto::from).collect())
+    }
+
+    // Retrieves a subscription by ID
+    pub async fn get(&self, id: i32) -> Result<WebhookDto, AppError> {
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        repo.get_by_id(id)
+            .await?
+            .map(WebhookDto::from)
+            .ok_or_else(|| webhook_not_found(id))
+    }
+
+    // Updates a subscription; a new secret is returned once
+    pub async fn update(&self, id: i32, webhook: UpdateWebhookDto) -> Result<WebhookDto, AppError> {
+        let event_types = webhook.event_types.map(validate_event_types).transpose()?;
+        if let Some(url) = &webhook.url {
+            validate_url(url)?;
+        }
+
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        let record = repo
+            .update(
+                id,
+                webhook.url,
+                webhook.secret.clone(),
+                event_types,
+                webhook.description,
+                webhook.active,
+            )
+            .await?
+            .ok_or_else(|| webhook_not_found(id))?;
+        let mut response = WebhookDto::from(record);
+        response.secret = webhook.secret;
+        Ok(response)
+    }
+
+    // Deletes a subscription together with its delivery log
+    pub async fn delete(&self, id: i32) -> Result<(), AppError> {
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        if !repo.delete(id).await? {
+            return Err(webhook_not_found(id));
+        }
+        Ok(())
+    }
+
+    // Retrieves a page of the delivery log of a subscription
+    pub async fn get_deliveries(
+        &self,
+        id: i32,
+        query: DeliveryQueryParams,
+        page: PageParams<DeliverySort>,
+    ) -> Result<PageDto<WebhookDeliveryDto>, AppError> {
+        if let Some(status) = &query.status {
+            if !DELIVERY_STATUSES.contains(&status.as_str()) {
+                return Err(AppError::invalid_field(
+                    "status",
+                    format!(
+                        "Invalid status '{}', expected one of: {}",
+                        status,
+                        DELIVERY_STATUSES.join(", ")
+                    ),
+                ));
+            }
+        }
+        self.get(id).await?;
+
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        let (deliveries, total) = repo.get_deliveries(id, query.status, &page).await?;
+        let items = deliveries
+            .into_iter()
+            .map(WebhookDeliveryDto::from)
+            .collect();
+        Ok(PageDto::new(items, total, &page))
+    }
+
+    // Schedules a delivery to be sent again immediately
+    pub async fn retry_delivery(
+        &self,
+        id: i32,
+        delivery_id: i64,
+    ) -> Result<WebhookDeliveryDto, AppError> {
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        repo.retry(id, delivery_id)
+            .await?
+            .map(WebhookDeliveryDto::from)
+            .ok_or_else(|| {
+                AppError::not_found(format!(
+                    "Delivery {} of webhook {} not found",
+                    delivery_id, id
+                ))
+            })
+    }
+
+    // Sends due deliveries from the outbox at the configured interval until cancelled; a batch
+    // in progress is completed before returning
+    pub async fn run(&self, cancel: CancellationToken) {
+        let mut interval = tokio::time::interval(self.poll_interval);
+        loop {
+            tokio::select! {
+                _ = cancel.cancelled() => return,
+                _ = interval.tick() => {}
+            }
+            if let Err(e) = self.deliver_due().await {
+                log::error!("Failed to process webhook deliveries: {}", e);
+            }
+        }
+    }
+
+    // Sends claimed batches of due deliveries concurrently until none is left
+    async fn deliver_due(&self) -> Result<()> {
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        // The lease outlasts every attempt of a batch, so only a crash makes a delivery due again
+        let lease_secs = self.timeout.as_secs() as i64 * 2 + 30;
+        loop {
+            let deliveries = repo.claim_due(DELIVERY_BATCH, lease_secs).await?;
+            if deliveries.is_empty() {
+                return Ok(());
+            }
+            let results = futures_util::future::join_all(
+                deliveries
+                    .into_iter()
+                    .map(|delivery| self.deliver(delivery)),
+            )
+            .await;
+            for result in results {
+                result?;
+            }
+        }
+    }
+
+    // Sends one delivery and records the outcome
+    async fn deliver(&self, delivery: DueDeliveryDb) -> Result<()> {
+        let body = serde_json::json!({
+            "id": delivery.event_id.to_string(),
+            "type": delivery.event_type,
+            "created_at": format_iso8601(delivery.created_at),
+            "data": delivery.payload.0,
+        })
+        .to_string();
+        let timestamp = Utc::now().timestamp().to_string();
+        let signature = sign(&delivery.secret, &timestamp, &body);
+
+        let result = self
+            .client
+            .post(&delivery.url)
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header("X-Webhook-Id", delivery.event_id.to_string())
+            .header("X-Webhook-Event", &delivery.event_type)
+            .header("X-Webhook-Delivery", delivery.id.to_string())
+            .header("X-Webhook-Timestamp", &timestamp)
+            .header("X-Webhook-Signature", format!("sha256={}", signature))
+            .body(body)
+            .send()
+            .await;
+
+        let repo = WebhookRepository::new(self.pool.as_ref());
+        let (response_status, error) = match result {
+            Ok(response) if response.status().is_success() => {
+                log::info!(
+                    "Delivered webhook {} ({}) to {}",
+                    delivery.id,
+                    delivery.event_type,
+                    delivery.url
+                );
+                return repo
+                    .mark_delivered(delivery.id, response.status().as_u16() as i32)
+                    .await;
+            }
+            Ok(response) => {
+                let status = response.status();
+                let text: String = response
+                    .text()
+                    .await
+                    .unwrap_or_default()
+                    .chars()
+                    .take(MAX_ERROR_LEN)
+                    .collect();
+                (
+                    Some(status.as_u16() as i32),
+                    format!("HTTP {}: {}", status, text),
+                )
+            }
+            Err(e) if e.is_timeout() => (None, "Request timed out".to_string()),
+            Err(e) => (None, e.to_string()),
+        };
+
+        let attempts = delivery.attempts + 1;
+        let retry_in_secs = (attempts < self.max_attempts).then(|| {
+            self.retry_base_delay_secs
+                .saturating_mul(2u64.saturating_pow(attempts as u32 - 1))
+                .min(MAX_RETRY_DELAY_SECS) as i64
+        });
+        match retry_in_secs {
+            Some(secs) => log::warn!(
+                "Webhook {} to {} failed (attempt {}), retrying in {}s: {}",
+                delivery.id,
+                delivery.url,
+                attempts,
+                secs,
+                error
+            ),
+            None => log::error!(
+                "Webhook {} to {} failed after {} attempts, giving up: {}",
+                delivery.id,
+                delivery.url,
+                attempts,
+                error
+            ),
+        }
+        repo.mark_attempt_failed(delivery.id, response_status, error, retry_in_secs)
+            .await
+    }
+}
+
+// Computes the hex HMAC-SHA256 of "{timestamp}.{body}" with the subscription secret
+fn sign(secret: &str, timestamp: &str, body: &str) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+// Generates a random 64-character secret
+fn generate_secret() -> String {
+    format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple())
+}
+
+// Fails with 400 unless every event type is known; duplicates are removed
+fn validate_event_types(event_types: Vec<String>) -> Result<Vec<String>, AppError> {
+    let mut validated: Vec<String> = Vec::new();
+    for event_type in event_types {
+        if WebhookEvent::parse(&event_type).is_none() {
+            let known: Vec<&str> = WebhookEvent::ALL.iter().map(|e| e.as_str()).collect();
+            return Err(AppError::invalid_field(
+                "event_types",
+                format!(
+                    "Unknown event type '{}', expected one of: {}",
+                    event_type,
+                    known.join(", ")
+                ),
+            ));
+        }
+        if !validated.contains(&event_type) {
+            validated.push(event_type);
+        }
+    }
+    Ok(validated)
+}
+
+// Fails with 400 unless the URL uses http or https
+fn validate_url(url: &str) -> Result<(), AppError> {
+    if url.starts_with("http://") || url.starts_with("https://") {
+        Ok(())
+    } else {
+        Err(AppError::invalid_field(
+            "url",
+            "Webhook URL must use http or https",
+        ))
+    }
+}
+
+fn webhook_not_found(id: i32) -> AppError {
+    AppError::not_found(format!("Webhook {} not found", id))
+}
@@ -1,6 +1,7 @@
 use anyhow::{anyhow, Result};
 use sqlx::types::time::PrimitiveDateTime;
-use time::format_description::well_known::Iso8601;
+use time::format_description::well_known::{Iso8601, Rfc3339};
+use time::{OffsetDateTime, UtcOffset};
 
 pub mod datetime {
     use super::*;
@@ -12,9 +13,23 @@ pub mod datetime {
             .unwrap_or_else(|_| dt.to_string())
     }
 
+    // Returns the current UTC time as a PrimitiveDateTime, as stored in TIMESTAMP columns.
+    pub fn now_utc() -> PrimitiveDateTime {
+        let now = OffsetDateTime::now_utc();
+        PrimitiveDateTime::new(now.date(), now.time())
+    }
+
     // Parses an ISO 8601 string into a PrimitiveDateTime.
     pub fn parse_iso8601(s: &str) -> Result<PrimitiveDateTime> {
         PrimitiveDateTime::parse(s, &Iso8601::DEFAULT)
             .map_err(|e| anyhow!("Invalid ISO 8601 date format: {}", e))
     }
+
+    // Parses an RFC 3339 string with an offset (e.g., "2013-04-28T00:00:00.000Z") into a UTC PrimitiveDateTime.
+    pub fn parse_rfc3339_utc(s: &str) -> Result<PrimitiveDateTime> {
+        let dt = OffsetDateTime::parse(s, &Rfc3339)
+            .map_err(|e| anyhow!("Invalid RFC 3339 date format: {}", e))?
+            .to_offset(UtcOffset::UTC);
+        Ok(PrimitiveDateTime::new(dt.date(), dt.time()))
+    }
 }
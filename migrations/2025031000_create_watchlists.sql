@@ -0,0 +1,14 @@
+CREATE TABLE watchlists (
+    id SERIAL PRIMARY KEY,
+    name VARCHAR(50) NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE watchlist_assets (
+    watchlist_id INT NOT NULL REFERENCES watchlists(id) ON DELETE CASCADE,
+    asset_id INT NOT NULL REFERENCES assets(id) ON DELETE CASCADE,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (watchlist_id, asset_id)
+);
+
+CREATE INDEX idx_watchlist_assets_asset_id ON watchlist_assets (asset_id);
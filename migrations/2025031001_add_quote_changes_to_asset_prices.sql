@@ -0,0 +1,3 @@
+ALTER TABLE asset_prices
+    ADD COLUMN percent_change_24h FLOAT,
+    ADD COLUMN percent_change_7d FLOAT;
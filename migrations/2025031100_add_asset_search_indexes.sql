@@ -0,0 +1,3 @@
+CREATE INDEX idx_assets_symbol_lower ON assets (LOWER(symbol) text_pattern_ops);
+CREATE INDEX idx_assets_name_lower ON assets (LOWER(name) text_pattern_ops);
+CREATE INDEX idx_assets_created_at ON assets (created_at);
@@ -0,0 +1,13 @@
+ALTER TABLE assets
+    ADD COLUMN slug VARCHAR(100),
+    ADD COLUMN tags TEXT[] NOT NULL DEFAULT '{}',
+    ADD COLUMN platform_name VARCHAR(100),
+    ADD COLUMN platform_symbol VARCHAR(20),
+    ADD COLUMN token_address VARCHAR(255),
+    ADD COLUMN circulating_supply FLOAT,
+    ADD COLUMN total_supply FLOAT,
+    ADD COLUMN max_supply FLOAT,
+    ADD COLUMN date_added TIMESTAMP,
+    ADD COLUMN metadata_updated_at TIMESTAMP;
+
+CREATE INDEX idx_assets_tags ON assets USING GIN (tags);
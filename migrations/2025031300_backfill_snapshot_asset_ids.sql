@@ -0,0 +1,23 @@
+-- Holdings are now keyed by asset id. Backfill asset_id (and the matching cmc_id)
+-- into existing snapshot entries whose symbol identifies exactly one asset;
+-- ambiguous symbols are left without an asset_id.
+UPDATE portfolio_snapshots ps
+SET assets = (
+    SELECT COALESCE(
+        jsonb_agg(
+            CASE
+                WHEN e.elem ? 'asset_id' OR u.id IS NULL THEN e.elem
+                ELSE e.elem || jsonb_build_object('asset_id', u.id, 'cmc_id', u.cmc_id)
+            END
+            ORDER BY e.ord
+        ),
+        '[]'::jsonb
+    )
+    FROM jsonb_array_elements(ps.assets) WITH ORDINALITY AS e(elem, ord)
+    LEFT JOIN LATERAL (
+        SELECT MIN(a.id) AS id, MIN(a.cmc_id) AS cmc_id
+        FROM assets a
+        WHERE a.symbol = e.elem->>'symbol'
+        HAVING COUNT(*) = 1
+    ) u ON TRUE
+);
@@ -0,0 +1,6 @@
+CREATE TABLE cmc_credit_usage (
+    day DATE PRIMARY KEY,
+    credits_used INT NOT NULL DEFAULT 0,
+    calls INT NOT NULL DEFAULT 0,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,9 @@
+-- Events pushed to streaming clients, kept for a limited time so reconnecting clients can resume
+CREATE TABLE stream_events (
+    id BIGSERIAL PRIMARY KEY,
+    kind VARCHAR(32) NOT NULL,
+    payload JSONB NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX idx_stream_events_created_at ON stream_events (created_at);
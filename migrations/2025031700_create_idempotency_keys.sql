@@ -0,0 +1,14 @@
+-- Responses of mutating requests sent with an Idempotency-Key header, replayed for retries
+CREATE TABLE idempotency_keys (
+    key VARCHAR(255) PRIMARY KEY,
+    request_hash CHAR(64) NOT NULL, -- SHA-256 of the method, path, query string and body
+    method VARCHAR(10) NOT NULL,
+    path TEXT NOT NULL,
+    response_status INT, -- NULL while the first request is being processed
+    response_content_type TEXT,
+    response_body BYTEA,
+    created_at TIMESTAMP NOT NULL DEFAULT NOW(),
+    completed_at TIMESTAMP
+);
+
+CREATE INDEX idx_idempotency_keys_created_at ON idempotency_keys (created_at);
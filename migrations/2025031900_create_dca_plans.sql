@@ -0,0 +1,34 @@
+-- Recurring dollar-cost-averaging buys of an asset into a wallet
+CREATE TABLE dca_plans (
+    id SERIAL PRIMARY KEY,
+    asset_id INT NOT NULL REFERENCES assets(id),
+    wallet_id INT NOT NULL REFERENCES wallets(id),
+    amount_usd FLOAT NOT NULL CHECK (amount_usd > 0),
+    frequency VARCHAR(10) NOT NULL CHECK (frequency IN ('daily', 'weekly', 'biweekly', 'monthly')),
+    start_date TIMESTAMP NOT NULL,
+    end_date TIMESTAMP,
+    next_execution_at TIMESTAMP, -- NULL once the plan has passed its end date
+    active BOOLEAN NOT NULL DEFAULT TRUE,
+    notes TEXT,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_dca_plans_due ON dca_plans (next_execution_at) WHERE active;
+
+-- Executions generated by the scheduler, pending until confirmed as a transaction or skipped
+CREATE TABLE dca_executions (
+    id SERIAL PRIMARY KEY,
+    plan_id INT NOT NULL REFERENCES dca_plans(id) ON DELETE CASCADE,
+    scheduled_at TIMESTAMP NOT NULL,
+    amount_usd FLOAT NOT NULL,
+    price FLOAT, -- Latest stored price when generated; NULL if the asset had none
+    amount FLOAT, -- amount_usd / price
+    status VARCHAR(16) NOT NULL DEFAULT 'pending', -- pending, confirmed or skipped
+    transaction_id INT REFERENCES transactions(id) ON DELETE SET NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    resolved_at TIMESTAMP,
+    UNIQUE (plan_id, scheduled_at)
+);
+
+CREATE INDEX idx_dca_executions_status ON dca_executions (status, scheduled_at);
@@ -0,0 +1,5 @@
+-- Identifies the request holding a key, so only it can store a response or release the key, and
+-- lets it keep the key claimed while it runs
+ALTER TABLE idempotency_keys
+    ADD COLUMN claim_token UUID,
+    ADD COLUMN heartbeat_at TIMESTAMP NOT NULL DEFAULT NOW(); -- Refreshed while the request runs